@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::types::{SubscriptionMessage, SubscriptionType};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BaseQuotePriceData {
     pub o: f64,
     pub h: f64,
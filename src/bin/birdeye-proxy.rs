@@ -0,0 +1,103 @@
+//! `birdeye-proxy`: share Birdeye streams between local services
+//!
+//! ```text
+//! birdeye-proxy --listen 127.0.0.1:8787
+//! websocat ws://127.0.0.1:8787   # then send SubscriptionMessage JSON
+//! curl -N 'http://127.0.0.1:8787/sse?subscribe=...'
+//! ```
+//!
+//! With the `metrics` feature, `--metrics-listen` serves Prometheus metrics
+//! of the upstream connections on `GET /metrics`.
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+#[cfg(feature = "metrics")]
+use birdeye_api_rs::metrics::BirdeyeMetrics;
+use birdeye_api_rs::proxy::{FanOutProxy, ProxyOptions};
+use birdeye_api_rs::BirdeyeWebSocket;
+use clap::Parser;
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Parser)]
+#[command(name = "birdeye-proxy", version, about = "Fan-out proxy for Birdeye WebSocket streams")]
+struct Args {
+    #[arg(long, env = "BIRDEYE_API_KEY", hide_env_values = true)]
+    api_key: String,
+    #[arg(long, default_value = "127.0.0.1:8787")]
+    listen: SocketAddr,
+    /// Maximum concurrent upstream Birdeye connections
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u16).range(1..))]
+    max_upstreams: u16,
+    /// Disable the `GET /sse` endpoint
+    #[arg(long)]
+    no_sse: bool,
+    /// Serve Prometheus metrics on this address
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_listen: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    // `RUST_LOG` overrides the default of info-level events
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let options = ProxyOptions::new()
+        .with_max_upstreams(args.max_upstreams.into())
+        .with_sse(!args.no_sse);
+    let ws = BirdeyeWebSocket::new(args.api_key);
+    #[cfg(feature = "metrics")]
+    let ws = match args.metrics_listen {
+        Some(addr) => match serve_metrics(addr).await {
+            Ok(metrics) => ws.with_metrics(metrics),
+            Err(e) => {
+                tracing::error!(error = %e, listen = %addr, "cannot serve metrics");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => ws,
+    };
+
+    let proxy = FanOutProxy::with_client(ws, options);
+
+    let listener = match TcpListener::bind(args.listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(error = %e, listen = %args.listen, "cannot listen");
+            return ExitCode::FAILURE;
+        }
+    };
+    tracing::info!(listen = %args.listen, "listening");
+
+    tokio::select! {
+        result = proxy.serve(listener) => {
+            if let Err(e) = result {
+                tracing::error!(error = %e, "proxy stopped");
+                return ExitCode::FAILURE;
+            }
+        }
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    ExitCode::SUCCESS
+}
+
+/// Bind `addr` and serve a fresh metrics registry on it in the background
+#[cfg(feature = "metrics")]
+async fn serve_metrics(addr: SocketAddr) -> std::io::Result<BirdeyeMetrics> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(listen = %addr, "serving metrics");
+    let metrics = BirdeyeMetrics::new();
+    let server = metrics.clone();
+    tokio::spawn(async move {
+        if let Err(e) = server.serve(listener).await {
+            tracing::error!(error = %e, "metrics endpoint stopped");
+        }
+    });
+    Ok(metrics)
+}
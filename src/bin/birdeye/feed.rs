@@ -0,0 +1,62 @@
+use birdeye_api_rs::{BirdeyeError, BirdeyeWebSocket, ResponseType, SubscriptionMessage};
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Picks the events of one subscription out of the socket's messages
+pub type Decoder<T> = fn(&ResponseType, serde_json::Value) -> Option<Result<T, BirdeyeError>>;
+
+pub enum FeedItem<T> {
+    Event(T),
+    /// An `ERROR` message from the server
+    ServerError(serde_json::Value),
+    /// A payload that failed to decode; the feed continues
+    Malformed(BirdeyeError),
+    /// The connection failed; the feed ends after this item
+    Failed(BirdeyeError),
+}
+
+/// Connect, subscribe and forward decoded events until the server closes
+/// the connection
+///
+/// Connecting happens before this returns so failures surface right away.
+pub async fn open_feed<T: Send + 'static>(
+    api_key: &str,
+    subscription: &SubscriptionMessage,
+    decode: Decoder<T>,
+) -> Result<mpsc::Receiver<FeedItem<T>>, BirdeyeError> {
+    let client = BirdeyeWebSocket::new(api_key);
+    let mut socket = client.connect().await?;
+    BirdeyeWebSocket::send_message(&mut socket, subscription).await?;
+
+    let (items, receiver) = mpsc::channel(1024);
+    tokio::spawn(async move {
+        while let Some(message) = socket.next().await {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => break,
+                Ok(_) => continue,
+                Err(e) => {
+                    let _ = items.send(FeedItem::Failed(e.into())).await;
+                    break;
+                }
+            };
+            // Welcome and acknowledgement messages have no `ResponseType`
+            let Ok(response) = client.decode(&text) else {
+                continue;
+            };
+            let item = match response.response_type {
+                ResponseType::Error => FeedItem::ServerError(response.data),
+                kind => match decode(&kind, response.data) {
+                    Some(Ok(event)) => FeedItem::Event(event),
+                    Some(Err(e)) => FeedItem::Malformed(e),
+                    None => continue,
+                },
+            };
+            if items.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(receiver)
+}
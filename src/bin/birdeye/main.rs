@@ -0,0 +1,325 @@
+//! `birdeye` command-line tool
+//!
+//! ```text
+//! birdeye stream price <ADDRESS> --interval 1m
+//! birdeye stream large-trades --min 50000 --format json
+//! birdeye ohlcv <ADDRESS> --interval 1h --from 1700000000
+//! ```
+
+mod feed;
+mod output;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use birdeye_api_rs::cache::RestCache;
+use birdeye_api_rs::large_trades::{create_large_trades_subscription, parse_large_trade_data, LargeTradeOptions};
+use birdeye_api_rs::new_pair::{create_new_pair_subscription, parse_new_pair_data, NewPairOptions};
+use birdeye_api_rs::price::{create_price_subscription, parse_price_data};
+use birdeye_api_rs::txs::{create_multi_txs_subscription, parse_transaction_data};
+use birdeye_api_rs::wallet_watcher::{WalletWatcher, WalletWatcherOptions};
+use birdeye_api_rs::{unix_now, BirdeyeError, BirdeyeRest, ResponseType, SubscriptionMessage};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use serde::Serialize;
+use tracing_subscriber::EnvFilter;
+
+use crate::feed::{open_feed, Decoder, FeedItem};
+use crate::output::{Format, Printer};
+
+#[derive(Debug, Parser)]
+#[command(name = "birdeye", version, about = "Stream and query Birdeye market data")]
+struct Cli {
+    // Required, but clap rejects required global arguments, so `main`
+    // checks it instead
+    /// Birdeye API key
+    #[arg(long, env = "BIRDEYE_API_KEY", hide_env_values = true, global = true)]
+    api_key: Option<String>,
+    /// Chain for REST queries; streams are Solana only
+    #[arg(long, default_value = "solana", global = true)]
+    chain: String,
+    #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+    /// Cache REST responses on disk under this directory
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Stream live events until interrupted
+    #[command(subcommand)]
+    Stream(StreamCommand),
+    /// Wallet activity
+    #[command(subcommand)]
+    Wallet(WalletCommand),
+    /// Historical candles of a token
+    Ohlcv {
+        address: String,
+        #[arg(long, default_value = "15m")]
+        interval: String,
+        /// Start (Unix seconds); defaults to 24 hours before `--to`
+        #[arg(long)]
+        from: Option<i64>,
+        /// End (Unix seconds); defaults to now
+        #[arg(long)]
+        to: Option<i64>,
+    },
+    /// Token overview, optionally with its security report
+    Token {
+        address: String,
+        #[arg(long)]
+        security: bool,
+    },
+    /// Live dashboard of a token watchlist
+    #[cfg(feature = "tui")]
+    Tui {
+        #[arg(required = true)]
+        tokens: Vec<String>,
+        /// Minimum USD volume of market-wide trades shown on the tape
+        #[arg(long, default_value_t = 50_000.0)]
+        large_trade_min: f64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum StreamCommand {
+    /// Candles of a token
+    Price {
+        address: String,
+        #[arg(long, default_value = "1m")]
+        interval: String,
+        #[arg(long, default_value = "usd")]
+        currency: String,
+    },
+    /// Swaps of tokens and/or pairs
+    Txs {
+        #[arg(required_unless_present = "pairs")]
+        addresses: Vec<String>,
+        #[arg(long = "pair")]
+        pairs: Vec<String>,
+    },
+    /// Swaps above a USD volume
+    LargeTrades {
+        #[arg(long, default_value_t = 10_000.0)]
+        min: f64,
+        #[arg(long)]
+        max: Option<f64>,
+    },
+    /// Newly created pairs
+    NewPairs {
+        #[arg(long)]
+        min_liquidity: Option<f64>,
+        #[arg(long)]
+        max_liquidity: Option<f64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum WalletCommand {
+    /// Stream transactions of one or more wallets
+    Watch {
+        #[arg(required = true)]
+        addresses: Vec<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let Some(api_key) = cli.api_key.clone().filter(|key| !key.is_empty()) else {
+        Cli::command()
+            .error(ErrorKind::MissingRequiredArgument, "--api-key or BIRDEYE_API_KEY is required")
+            .exit();
+    };
+    // Diagnostics go to stderr so they never mix with data on stdout. The
+    // dashboard owns the terminal, so it runs without them.
+    #[cfg(feature = "tui")]
+    let log = !matches!(cli.command, Command::Tui { .. });
+    #[cfg(not(feature = "tui"))]
+    let log = true;
+    if log {
+        tracing_subscriber::fmt()
+            .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+            .with_writer(std::io::stderr)
+            .init();
+    }
+    match run(cli, api_key).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, api_key: String) -> Result<(), BirdeyeError> {
+    let format = cli.format;
+    let rest = || -> Result<BirdeyeRest, BirdeyeError> {
+        let rest = BirdeyeRest::new(api_key.clone(), cli.chain.clone());
+        Ok(match &cli.cache_dir {
+            Some(dir) => rest.with_cache(RestCache::disk(dir)?),
+            None => rest,
+        })
+    };
+    match cli.command {
+        Command::Stream(command) => run_stream(&api_key, format, command).await,
+        Command::Wallet(WalletCommand::Watch { addresses }) => {
+            let (watcher, mut events) = WalletWatcher::new(api_key, WalletWatcherOptions::default());
+            for address in addresses {
+                watcher.add_wallet(address);
+            }
+            let mut printer = Printer::new(format, output::WALLET_TX_COLUMNS);
+            loop {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => return Ok(()),
+                    event = events.recv() => match event {
+                        Some(event) => printer.print(&event.data)?,
+                        None => return Ok(()),
+                    },
+                }
+            }
+        }
+        Command::Ohlcv { address, interval, from, to } => {
+            let rest = rest()?;
+            let to = to.unwrap_or_else(unix_now);
+            let from = from.unwrap_or(to - 24 * 3600);
+            let response = rest.get_ohlcv(&address, &interval, from, to).await?;
+            let mut printer = Printer::new(format, output::OHLCV_COLUMNS);
+            for candle in &response.data.items {
+                printer.print(candle)?;
+            }
+            Ok(())
+        }
+        Command::Token { address, security } => {
+            let rest = rest()?;
+            let mut printer = Printer::new(format, &[]);
+            printer.print(&rest.get_token_overview(&address).await?.data)?;
+            if security {
+                printer.print(&rest.get_token_security(&address).await?.data)?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui { tokens, large_trade_min } => {
+            if large_trade_min < 1000.0 {
+                Cli::command()
+                    .error(ErrorKind::ValueValidation, "--large-trade-min must be at least 1000")
+                    .exit();
+            }
+            let rest = rest()?;
+            tui::run(rest, &api_key, tokens, large_trade_min).await
+        }
+    }
+}
+
+async fn run_stream(api_key: &str, format: Format, command: StreamCommand) -> Result<(), BirdeyeError> {
+    match command {
+        StreamCommand::Price { address, interval, currency } => {
+            let subscription = create_price_subscription(address, interval, currency);
+            let mut printer = Printer::new(format, output::PRICE_COLUMNS);
+            stream(api_key, subscription, &mut printer, |kind, data| {
+                matches!(kind, ResponseType::PriceData).then(|| parse_price_data(data))
+            })
+            .await
+        }
+        StreamCommand::Txs { addresses, pairs } => {
+            let subscription = create_multi_txs_subscription(addresses, pairs);
+            let mut printer = Printer::new(format, output::TXS_COLUMNS);
+            stream(api_key, subscription, &mut printer, |kind, data| {
+                matches!(kind, ResponseType::TxsData).then(|| parse_transaction_data(data))
+            })
+            .await
+        }
+        StreamCommand::LargeTrades { min, max } => {
+            if min < 1000.0 || max.is_some_and(|max| max <= min) {
+                Cli::command()
+                    .error(ErrorKind::ValueValidation, "--min must be at least 1000 and --max greater than --min")
+                    .exit();
+            }
+            let mut options = LargeTradeOptions::new(min);
+            if let Some(max) = max {
+                options = options.with_max_volume(max);
+            }
+            let mut printer = Printer::new(format, output::LARGE_TRADE_COLUMNS);
+            stream(api_key, create_large_trades_subscription(options), &mut printer, |kind, data| {
+                matches!(kind, ResponseType::TxsLargeTradeData).then(|| parse_large_trade_data(data))
+            })
+            .await
+        }
+        StreamCommand::NewPairs { min_liquidity, max_liquidity } => {
+            let mut options = NewPairOptions::new();
+            if let Some(min) = min_liquidity {
+                options = options.with_min_liquidity(min);
+            }
+            if let Some(max) = max_liquidity {
+                options = options.with_max_liquidity(max);
+            }
+            let mut printer = Printer::new(format, output::NEW_PAIR_COLUMNS);
+            stream(api_key, create_new_pair_subscription(Some(options)), &mut printer, |kind, data| {
+                matches!(kind, ResponseType::NewPair).then(|| parse_new_pair_data(data))
+            })
+            .await
+        }
+    }
+}
+
+/// Subscribe and print every event `decode` accepts until Ctrl-C or the
+/// server closes the connection
+async fn stream<T: Serialize + Send + 'static>(
+    api_key: &str,
+    subscription: SubscriptionMessage,
+    printer: &mut Printer,
+    decode: Decoder<T>,
+) -> Result<(), BirdeyeError> {
+    let mut feed = open_feed(api_key, &subscription, decode).await?;
+    loop {
+        let item = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            item = feed.recv() => item,
+        };
+        match item {
+            Some(FeedItem::Event(event)) => printer.print(&event)?,
+            Some(FeedItem::ServerError(data)) => eprintln!("warning: server sent an error: {data}"),
+            Some(FeedItem::Malformed(e)) => eprintln!("warning: skipping malformed event: {e}"),
+            Some(FeedItem::Failed(e)) => return Err(e),
+            None => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn global_options_follow_the_subcommand() {
+        let cli = Cli::try_parse_from([
+            "birdeye", "ohlcv", "TOKEN", "--api-key", "key", "--chain", "ethereum", "--format", "csv",
+        ])
+        .unwrap();
+        assert_eq!((cli.api_key.as_deref(), cli.chain.as_str()), (Some("key"), "ethereum"));
+        assert!(matches!(cli.format, Format::Csv));
+        assert!(matches!(cli.command, Command::Ohlcv { ref address, .. } if address == "TOKEN"));
+
+        let cli = Cli::try_parse_from(["birdeye", "stream", "large-trades", "--min", "5000", "--api-key", "key"]).unwrap();
+        assert!(matches!(cli.command, Command::Stream(StreamCommand::LargeTrades { min, max: None }) if min == 5000.0));
+    }
+
+    #[test]
+    fn txs_need_a_token_or_a_pair() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["birdeye", "--api-key", "key", "stream", "txs"].iter().chain(args));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--pair", "PAIR"]).is_ok());
+        assert!(parse(&["TOKEN"]).is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+
+use birdeye_api_rs::export::{flatten, CsvWriter, NdjsonWriter, RecordWriter};
+use birdeye_api_rs::BirdeyeError;
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CellKind {
+    Text,
+    /// Unix seconds shown as `HH:MM:SS` UTC
+    Time,
+    /// Six significant digits, for prices and amounts
+    Number,
+    /// Two decimals
+    Usd,
+}
+
+/// A table column picked from the flattened JSON of a record
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub header: &'static str,
+    pub path: &'static str,
+    pub kind: CellKind,
+    pub width: usize,
+}
+
+const fn column(header: &'static str, path: &'static str, kind: CellKind, width: usize) -> Column {
+    Column { header, path, kind, width }
+}
+
+pub const PRICE_COLUMNS: &[Column] = &[
+    column("TIME", "unixTime", CellKind::Time, 8),
+    column("SYMBOL", "symbol", CellKind::Text, 10),
+    column("OPEN", "o", CellKind::Number, 12),
+    column("HIGH", "h", CellKind::Number, 12),
+    column("LOW", "l", CellKind::Number, 12),
+    column("CLOSE", "c", CellKind::Number, 12),
+    column("VOLUME", "v", CellKind::Number, 14),
+];
+
+pub const OHLCV_COLUMNS: &[Column] = &[
+    column("TIME", "unixTime", CellKind::Time, 8),
+    column("OPEN", "o", CellKind::Number, 12),
+    column("HIGH", "h", CellKind::Number, 12),
+    column("LOW", "l", CellKind::Number, 12),
+    column("CLOSE", "c", CellKind::Number, 12),
+    column("VOLUME", "v", CellKind::Number, 14),
+];
+
+pub const TXS_COLUMNS: &[Column] = &[
+    column("TIME", "blockUnixTime", CellKind::Time, 8),
+    column("SOURCE", "source", CellKind::Text, 12),
+    column("FROM", "from.symbol", CellKind::Text, 10),
+    column("AMOUNT", "from.uiAmount", CellKind::Number, 14),
+    column("TO", "to.symbol", CellKind::Text, 10),
+    column("AMOUNT", "to.uiAmount", CellKind::Number, 14),
+    column("USD", "volumeUSD", CellKind::Usd, 12),
+    column("OWNER", "owner", CellKind::Text, 44),
+];
+
+pub const LARGE_TRADE_COLUMNS: &[Column] = TXS_COLUMNS;
+
+pub const NEW_PAIR_COLUMNS: &[Column] = &[
+    column("TIME", "blockTime", CellKind::Time, 8),
+    column("SOURCE", "source", CellKind::Text, 12),
+    column("NAME", "name", CellKind::Text, 24),
+    column("BASE", "base.symbol", CellKind::Text, 10),
+    column("QUOTE", "quote.symbol", CellKind::Text, 10),
+    column("PAIR", "address", CellKind::Text, 44),
+];
+
+pub const WALLET_TX_COLUMNS: &[Column] = &[
+    column("TIME", "blockUnixTime", CellKind::Time, 8),
+    column("OWNER", "owner", CellKind::Text, 44),
+    column("TYPE", "type", CellKind::Text, 10),
+    column("FROM", "from.symbol", CellKind::Text, 10),
+    column("AMOUNT", "from.uiAmount", CellKind::Number, 14),
+    column("TO", "to.symbol", CellKind::Text, 10),
+    column("AMOUNT", "to.uiAmount", CellKind::Number, 14),
+    column("USD", "volumeUSD", CellKind::Usd, 12),
+];
+
+/// Prints records to stdout in the selected format, one at a time
+pub enum Printer {
+    /// Fixed-width rows; without columns every field is listed as
+    /// `key value` lines
+    Table { columns: &'static [Column], header_printed: bool },
+    Json(NdjsonWriter<Stdout>),
+    Csv(Box<CsvWriter<Stdout>>),
+}
+
+impl Printer {
+    pub fn new(format: Format, columns: &'static [Column]) -> Self {
+        match format {
+            Format::Table => Printer::Table { columns, header_printed: false },
+            Format::Json => Printer::Json(NdjsonWriter::new(io::stdout())),
+            Format::Csv => Printer::Csv(Box::new(CsvWriter::new(io::stdout()))),
+        }
+    }
+
+    pub fn print<T: Serialize>(&mut self, record: &T) -> Result<(), BirdeyeError> {
+        match self {
+            Printer::Table { columns, header_printed } => {
+                let mut cells = Vec::new();
+                flatten("", serde_json::to_value(record)?, &mut cells);
+                if columns.is_empty() {
+                    let width = cells.iter().map(|(key, _)| key.len()).max().unwrap_or_default();
+                    for (key, value) in cells {
+                        println!("{key:<width$}  {value}");
+                    }
+                    return Ok(());
+                }
+                if !*header_printed {
+                    println!("{}", row(columns, |c| c.header.to_string()));
+                    *header_printed = true;
+                }
+                let cells: HashMap<String, String> = cells.into_iter().collect();
+                println!(
+                    "{}",
+                    row(columns, |c| format_cell(c.kind, cells.get(c.path).map_or("", String::as_str)))
+                );
+                Ok(())
+            }
+            Printer::Json(writer) => {
+                writer.write_record(record)?;
+                writer.flush()
+            }
+            Printer::Csv(writer) => {
+                writer.write_record(record)?;
+                writer.flush()
+            }
+        }
+    }
+}
+
+fn row(columns: &[Column], cell: impl Fn(&Column) -> String) -> String {
+    columns
+        .iter()
+        .map(|c| {
+            let mut text = cell(c);
+            if text.chars().count() > c.width {
+                text = text.chars().take(c.width - 1).chain(['…']).collect();
+            }
+            match c.kind {
+                CellKind::Number | CellKind::Usd => format!("{text:>width$}", width = c.width),
+                _ => format!("{text:<width$}", width = c.width),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+fn format_cell(kind: CellKind, raw: &str) -> String {
+    match kind {
+        CellKind::Text => raw.to_string(),
+        CellKind::Time => raw.parse::<i64>().map_or_else(|_| raw.to_string(), clock),
+        CellKind::Number => raw.parse::<f64>().map_or_else(|_| raw.to_string(), significant),
+        CellKind::Usd => raw.parse::<f64>().map_or_else(|_| raw.to_string(), |v| format!("{v:.2}")),
+    }
+}
+
+/// `HH:MM:SS` UTC of a Unix timestamp
+pub fn clock(unix_time: i64) -> String {
+    let secs = unix_time.rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Six significant digits, keeping small memecoin prices readable
+pub fn significant(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return value.to_string();
+    }
+    let decimals = (5 - value.abs().log10().floor() as i32).max(0) as usize;
+    format!("{value:.decimals$}")
+}
@@ -0,0 +1,557 @@
+//! `birdeye tui`: live dashboard of a token watchlist
+//!
+//! Panels: watchlist prices from `PriceData`, fresh `NewPairData`, and a
+//! tape of `TransactionData` for the watchlist plus market-wide
+//! `LargeTradeData`. Enter opens the selected token's overview.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use birdeye_api_rs::export::flatten;
+use birdeye_api_rs::large_trades::{create_large_trades_subscription, parse_large_trade_data, LargeTradeData, LargeTradeOptions};
+use birdeye_api_rs::new_pair::{create_new_pair_subscription, parse_new_pair_data, NewPairData};
+use birdeye_api_rs::price::{create_multi_price_subscription, parse_price_data, PriceData};
+use birdeye_api_rs::rest::TokenOverviewData;
+use birdeye_api_rs::txs::{create_multi_txs_subscription, parse_transaction_data, TransactionData};
+use birdeye_api_rs::{BirdeyeError, BirdeyeRest, ResponseType};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::mpsc;
+
+use crate::feed::{open_feed, FeedItem};
+use crate::output::{clock, significant};
+
+const TAPE_LENGTH: usize = 200;
+const NEW_PAIRS_LENGTH: usize = 50;
+
+enum AppEvent {
+    Key(KeyEvent),
+    Price(PriceData),
+    Trade(TapeRow),
+    NewPair(NewPairData),
+    Overview(String, Result<TokenOverviewData, String>),
+    Status(String),
+}
+
+struct WatchRow {
+    address: String,
+    symbol: Option<String>,
+    price: Option<f64>,
+    /// Price 24 hours ago per the overview, else the first price seen
+    reference: Option<f64>,
+    updated: Option<i64>,
+}
+
+impl WatchRow {
+    fn change_percent(&self) -> Option<f64> {
+        let (price, reference) = (self.price?, self.reference?);
+        (reference > 0.0).then(|| (price / reference - 1.0) * 100.0)
+    }
+}
+
+struct TapeRow {
+    time: i64,
+    source: String,
+    from: String,
+    to: String,
+    volume_usd: f64,
+    large: bool,
+}
+
+impl From<TransactionData> for TapeRow {
+    fn from(tx: TransactionData) -> Self {
+        Self {
+            time: tx.block_unix_time,
+            source: tx.source,
+            from: format!("{} {}", significant(tx.from.ui_amount), tx.from.symbol),
+            to: format!("{} {}", significant(tx.to.ui_amount), tx.to.symbol),
+            volume_usd: tx.volume_usd,
+            large: false,
+        }
+    }
+}
+
+impl From<LargeTradeData> for TapeRow {
+    fn from(trade: LargeTradeData) -> Self {
+        Self {
+            time: trade.block_unix_time,
+            source: trade.source,
+            from: format!("{} {}", significant(trade.from.ui_amount), trade.from.symbol),
+            to: format!("{} {}", significant(trade.to.ui_amount), trade.to.symbol),
+            volume_usd: trade.volume_usd,
+            large: true,
+        }
+    }
+}
+
+struct App {
+    rest: BirdeyeRest,
+    events: mpsc::Sender<AppEvent>,
+    watchlist: Vec<WatchRow>,
+    selected: TableState,
+    tape: VecDeque<TapeRow>,
+    new_pairs: VecDeque<NewPairData>,
+    overviews: Vec<Option<TokenOverviewData>>,
+    /// Watchlist index whose overview is shown
+    detail: Option<usize>,
+    status: String,
+}
+
+/// Run the dashboard until `q` or Ctrl-C
+pub async fn run(rest: BirdeyeRest, api_key: &str, tokens: Vec<String>, large_trade_min: f64) -> Result<(), BirdeyeError> {
+    let (events, mut receiver) = mpsc::channel(4096);
+
+    // Connect before taking over the terminal so failures print normally
+    let prices = tokens
+        .iter()
+        .map(|t| (t.clone(), "1m".to_string(), "usd".to_string()))
+        .collect();
+    let price_feed = open_feed(api_key, &create_multi_price_subscription(prices), |kind, data| {
+        matches!(kind, ResponseType::PriceData).then(|| parse_price_data(data))
+    })
+    .await?;
+    let txs_feed = open_feed(api_key, &create_multi_txs_subscription(tokens.clone(), Vec::new()), |kind, data| {
+        matches!(kind, ResponseType::TxsData).then(|| parse_transaction_data(data))
+    })
+    .await?;
+    let large_feed = open_feed(
+        api_key,
+        &create_large_trades_subscription(LargeTradeOptions::new(large_trade_min)),
+        |kind, data| matches!(kind, ResponseType::TxsLargeTradeData).then(|| parse_large_trade_data(data)),
+    )
+    .await?;
+    let pair_feed = open_feed(api_key, &create_new_pair_subscription(None), |kind, data| {
+        matches!(kind, ResponseType::NewPair).then(|| parse_new_pair_data(data))
+    })
+    .await?;
+    forward(price_feed, events.clone(), AppEvent::Price);
+    forward(txs_feed, events.clone(), |tx| AppEvent::Trade(tx.into()));
+    forward(large_feed, events.clone(), |trade| AppEvent::Trade(trade.into()));
+    forward(pair_feed, events.clone(), AppEvent::NewPair);
+    spawn_input(events.clone());
+
+    let mut app = App::new(rest, events, tokens);
+    for index in 0..app.watchlist.len() {
+        app.fetch_overview(index);
+    }
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal, &mut receiver).await;
+    ratatui::restore();
+    result
+}
+
+fn forward<T: Send + 'static>(
+    mut feed: mpsc::Receiver<FeedItem<T>>,
+    events: mpsc::Sender<AppEvent>,
+    wrap: fn(T) -> AppEvent,
+) {
+    tokio::spawn(async move {
+        while let Some(item) = feed.recv().await {
+            let event = match item {
+                FeedItem::Event(event) => wrap(event),
+                FeedItem::ServerError(data) => AppEvent::Status(format!("server error: {data}")),
+                FeedItem::Malformed(e) => AppEvent::Status(format!("malformed event: {e}")),
+                FeedItem::Failed(e) => AppEvent::Status(format!("feed disconnected: {e}")),
+            };
+            if events.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Read key presses on a dedicated thread until the app goes away
+fn spawn_input(events: mpsc::Sender<AppEvent>) {
+    std::thread::spawn(move || {
+        while !events.is_closed() {
+            match event::poll(Duration::from_millis(250)) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(_) => break,
+            }
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press && events.blocking_send(AppEvent::Key(key)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+impl App {
+    fn new(rest: BirdeyeRest, events: mpsc::Sender<AppEvent>, tokens: Vec<String>) -> Self {
+        Self {
+            rest,
+            events,
+            overviews: tokens.iter().map(|_| None).collect(),
+            watchlist: tokens
+                .into_iter()
+                .map(|address| WatchRow {
+                    address,
+                    symbol: None,
+                    price: None,
+                    reference: None,
+                    updated: None,
+                })
+                .collect(),
+            selected: TableState::default().with_selected(Some(0)),
+            tape: VecDeque::with_capacity(TAPE_LENGTH),
+            new_pairs: VecDeque::with_capacity(NEW_PAIRS_LENGTH),
+            detail: None,
+            status: "connected".to_string(),
+        }
+    }
+
+    async fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        receiver: &mut mpsc::Receiver<AppEvent>,
+    ) -> Result<(), BirdeyeError> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            let Some(event) = receiver.recv().await else {
+                return Ok(());
+            };
+            if !self.on_event(event) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns `false` to quit
+    fn on_event(&mut self, event: AppEvent) -> bool {
+        match event {
+            AppEvent::Key(key) => return self.on_key(key),
+            AppEvent::Price(price) => self.on_price(price),
+            AppEvent::Trade(row) => {
+                if self.tape.len() == TAPE_LENGTH {
+                    self.tape.pop_back();
+                }
+                self.tape.push_front(row);
+            }
+            AppEvent::NewPair(pair) => {
+                if self.new_pairs.len() == NEW_PAIRS_LENGTH {
+                    self.new_pairs.pop_back();
+                }
+                self.new_pairs.push_front(pair);
+            }
+            AppEvent::Overview(address, result) => self.on_overview(&address, result),
+            AppEvent::Status(status) => self.status = status,
+        }
+        true
+    }
+
+    /// Returns `false` to quit
+    fn on_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc if self.detail.is_some() => self.detail = None,
+            KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.selected.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = self.watchlist.len().saturating_sub(1);
+                let next = self.selected.selected().map_or(0, |i| (i + 1).min(last));
+                self.selected.select(Some(next));
+            }
+            KeyCode::Enter => {
+                if let Some(index) = self.selected.selected() {
+                    self.detail = Some(index);
+                    self.fetch_overview(index);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn on_price(&mut self, price: PriceData) {
+        let Some(row) = self.watchlist.iter_mut().find(|row| row.address == price.address) else {
+            return;
+        };
+        row.symbol.get_or_insert(price.symbol);
+        row.price = Some(price.c);
+        row.reference.get_or_insert(price.c);
+        row.updated = Some(price.unix_time);
+    }
+
+    fn on_overview(&mut self, address: &str, result: Result<TokenOverviewData, String>) {
+        let Some(index) = self.watchlist.iter().position(|row| row.address == address) else {
+            return;
+        };
+        match result {
+            Ok(overview) => {
+                let row = &mut self.watchlist[index];
+                row.symbol = Some(overview.symbol.clone());
+                row.price.get_or_insert(overview.price);
+                if let Some(change) = overview.price_change_24h_percent {
+                    row.reference = Some(overview.price / (1.0 + change / 100.0));
+                }
+                self.overviews[index] = Some(overview);
+            }
+            Err(e) => self.status = format!("overview of {address} failed: {e}"),
+        }
+    }
+
+    fn fetch_overview(&self, index: usize) {
+        let (rest, events) = (self.rest.clone(), self.events.clone());
+        let address = self.watchlist[index].address.clone();
+        tokio::spawn(async move {
+            let result = rest
+                .get_token_overview(&address)
+                .await
+                .map(|response| response.data)
+                .map_err(|e| e.to_string());
+            let _ = events.send(AppEvent::Overview(address, result)).await;
+        });
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [top, tape, status] = Layout::vertical([
+            Constraint::Percentage(50),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [watchlist, new_pairs] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(top);
+
+        self.render_watchlist(frame, watchlist);
+        self.render_new_pairs(frame, new_pairs);
+        self.render_tape(frame, tape);
+        frame.render_widget(
+            Line::from(format!(" q quit  ↑/↓ select  enter overview  esc close │ {}", self.status)).reversed(),
+            status,
+        );
+        if let Some(index) = self.detail {
+            self.render_detail(frame, index);
+        }
+    }
+
+    fn render_watchlist(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.watchlist.iter().map(|row| {
+            let change = row.change_percent();
+            let color = match change {
+                Some(c) if c > 0.0 => Color::Green,
+                Some(c) if c < 0.0 => Color::Red,
+                _ => Color::Reset,
+            };
+            Row::new([
+                Cell::from(row.symbol.clone().unwrap_or_else(|| row.address.clone())),
+                Cell::from(row.price.map(significant).unwrap_or_default()),
+                Cell::from(change.map(|c| format!("{c:+.2}%")).unwrap_or_default()).fg(color),
+                Cell::from(row.updated.map(clock).unwrap_or_default()),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [Constraint::Fill(1), Constraint::Length(14), Constraint::Length(9), Constraint::Length(8)],
+        )
+        .header(Row::new(["TOKEN", "PRICE", "24H", "UPDATED"]).bold())
+        .block(Block::bordered().title(" Watchlist "))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut self.selected);
+    }
+
+    fn render_new_pairs(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.new_pairs.iter().map(|pair| {
+            Row::new([
+                clock(pair.block_time),
+                pair.source.clone(),
+                format!("{}/{}", pair.base.symbol, pair.quote.symbol),
+                pair.address.clone(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [Constraint::Length(8), Constraint::Length(12), Constraint::Length(18), Constraint::Fill(1)],
+        )
+        .header(Row::new(["TIME", "SOURCE", "PAIR", "ADDRESS"]).bold())
+        .block(Block::bordered().title(" New pairs "));
+        frame.render_widget(table, area);
+    }
+
+    fn render_tape(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.tape.iter().map(|trade| {
+            let row = Row::new([
+                clock(trade.time),
+                trade.source.clone(),
+                trade.from.clone(),
+                trade.to.clone(),
+                format!("{:.2}", trade.volume_usd),
+            ]);
+            if trade.large {
+                row.fg(Color::Yellow).bold()
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(14),
+            ],
+        )
+        .header(Row::new(["TIME", "SOURCE", "SOLD", "BOUGHT", "USD"]).bold())
+        .block(Block::bordered().title(" Tape (large trades highlighted) "));
+        frame.render_widget(table, area);
+    }
+
+    fn render_detail(&self, frame: &mut Frame, index: usize) {
+        let area = centered(frame.area(), 70, 80);
+        let row = &self.watchlist[index];
+        let lines: Vec<Line> = match &self.overviews[index] {
+            Some(overview) => {
+                let mut cells = Vec::new();
+                flatten("", serde_json::to_value(overview).unwrap_or_default(), &mut cells);
+                let width = cells.iter().map(|(key, _)| key.len()).max().unwrap_or_default();
+                cells
+                    .into_iter()
+                    .map(|(key, value)| Line::from(format!("{key:<width$}  {value}")))
+                    .collect()
+            }
+            None => vec![Line::from("loading…")],
+        };
+        let title = format!(" {} ", row.symbol.as_deref().unwrap_or(&row.address));
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+    }
+}
+
+fn centered(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(height_percent)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(width_percent)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use serde_json::json;
+
+    fn app(tokens: &[&str]) -> App {
+        let (events, _) = mpsc::channel(1);
+        let rest = BirdeyeRest::new("key".to_string(), "solana".to_string());
+        App::new(rest, events, tokens.iter().map(|t| t.to_string()).collect())
+    }
+
+    fn key(code: KeyCode) -> AppEvent {
+        AppEvent::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn price(address: &str, close: f64, unix_time: i64) -> AppEvent {
+        AppEvent::Price(
+            serde_json::from_value(json!({
+                "o": close, "h": close, "l": close, "c": close, "v": 1.0, "eventType": "ohlcv",
+                "type": "1m", "unixTime": unix_time, "symbol": address.to_lowercase(), "address": address,
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn overview(address: &str, price: f64, change: f64) -> AppEvent {
+        let data = serde_json::from_value(json!({
+            "address": address, "decimals": 6, "symbol": "OVR", "name": "Overview", "extensions": {},
+            "logoURI": null, "liquidity": 1.0, "price": price, "supply": 0.0, "mc": 0.0,
+            "v24hUSD": null, "priceChange24hPercent": change, "lastTradeUnixTime": 0,
+            "lastTradeHumanTime": "",
+        }))
+        .unwrap();
+        AppEvent::Overview(address.to_string(), Ok(data))
+    }
+
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn keys_move_the_selection_and_quit() {
+        let mut app = app(&["A", "B"]);
+        assert!(app.on_event(key(KeyCode::Down)));
+        assert!(app.on_event(key(KeyCode::Down)));
+        assert_eq!(app.selected.selected(), Some(1));
+        assert!(app.on_event(key(KeyCode::Char('k'))));
+        assert_eq!(app.selected.selected(), Some(0));
+
+        app.detail = Some(0);
+        assert!(app.on_event(key(KeyCode::Esc)));
+        assert_eq!(app.detail, None);
+        assert!(!app.on_event(key(KeyCode::Esc)));
+        assert!(!app.on_event(key(KeyCode::Char('q'))));
+        assert!(!app.on_event(AppEvent::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))));
+    }
+
+    #[test]
+    fn prices_change_against_the_first_price_until_an_overview_arrives() {
+        let mut app = app(&["A"]);
+        app.on_event(price("A", 2.0, 60));
+        app.on_event(price("A", 3.0, 120));
+        app.on_event(price("OTHER", 9.0, 120));
+        let row = &app.watchlist[0];
+        assert_eq!((row.symbol.as_deref(), row.price, row.updated), (Some("a"), Some(3.0), Some(120)));
+        assert_eq!(row.change_percent(), Some(50.0));
+
+        // 24h change of +100% puts the reference at half the overview price
+        app.on_event(overview("A", 4.0, 100.0));
+        let row = &app.watchlist[0];
+        assert_eq!((row.symbol.as_deref(), row.price), (Some("OVR"), Some(3.0)));
+        assert_eq!(row.change_percent(), Some(50.0));
+        assert!(app.overviews[0].is_some());
+
+        app.on_event(AppEvent::Overview("A".to_string(), Err("timeout".to_string())));
+        assert_eq!(app.status, "overview of A failed: timeout");
+    }
+
+    #[test]
+    fn tape_keeps_the_newest_trades() {
+        let mut app = app(&["A"]);
+        for time in 0..TAPE_LENGTH as i64 + 5 {
+            app.on_event(AppEvent::Trade(TapeRow {
+                time,
+                source: "raydium".to_string(),
+                from: "1 SOL".to_string(),
+                to: "150 USDC".to_string(),
+                volume_usd: 150.0,
+                large: false,
+            }));
+        }
+        assert_eq!(app.tape.len(), TAPE_LENGTH);
+        assert_eq!(app.tape.front().map(|row| row.time), Some(TAPE_LENGTH as i64 + 4));
+        assert_eq!(app.tape.back().map(|row| row.time), Some(5));
+    }
+
+    #[test]
+    fn renders_watchlist_tape_and_detail() {
+        let mut app = app(&["A"]);
+        app.on_event(price("A", 2.0, 60));
+        app.on_event(AppEvent::Status("feed disconnected".to_string()));
+        let text = screen(&mut app);
+        assert!(text.contains("Watchlist") && text.contains("Tape") && text.contains("New pairs"));
+        assert!(text.contains("feed disconnected"));
+
+        app.detail = Some(0);
+        assert!(screen(&mut app).contains("loading…"));
+        app.on_event(overview("A", 2.0, 0.0));
+        assert!(screen(&mut app).contains("lastTradeHumanTime"));
+    }
+}
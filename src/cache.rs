@@ -0,0 +1,556 @@
+//! Pluggable response cache for `BirdeyeRest`
+//!
+//! Attach one with `BirdeyeRest::with_cache`. Raw JSON bodies are cached
+//! per chain and request path, with lifetimes chosen by `CachePolicy`:
+//! candles that have fully closed never change and are kept forever, while
+//! live data such as token overviews is only reused briefly.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::types::unix_now;
+
+/// How long responses of an endpoint may be reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheTtl {
+    /// Never cached
+    Never,
+    For(Duration),
+    Forever,
+}
+
+impl CacheTtl {
+    /// Unix time the entry expires at, `None` for entries that never do
+    fn expires_at(self) -> Option<i64> {
+        match self {
+            CacheTtl::For(ttl) => Some(unix_now() + ttl.as_secs() as i64),
+            CacheTtl::Never | CacheTtl::Forever => None,
+        }
+    }
+}
+
+/// Storage for cached response bodies
+#[async_trait]
+pub trait ResponseCache: Send + Sync {
+    /// The body stored under `key`, unless it has expired
+    async fn get(&self, key: &str) -> Option<String>;
+    /// Store `body`; `expires_at` is a Unix time, `None` for never
+    async fn put(&self, key: &str, body: &str, expires_at: Option<i64>);
+}
+
+struct MemoryEntry {
+    body: Arc<str>,
+    expires_at: Option<i64>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    entries: HashMap<String, MemoryEntry>,
+    /// Keys by last use, least recent first
+    recency: BTreeMap<u64, String>,
+    /// Keys of expiring entries, soonest first
+    expiry: BTreeSet<(i64, String)>,
+    clock: u64,
+}
+
+impl MemoryState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: &str) -> Option<MemoryEntry> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        if let Some(at) = entry.expires_at {
+            self.expiry.remove(&(at, key.to_string()));
+        }
+        Some(entry)
+    }
+
+    /// Drop an expired entry if there is one, else the least recently used
+    fn evict(&mut self) {
+        let victim = match self.expiry.first() {
+            Some((at, key)) if *at <= unix_now() => Some(key.clone()),
+            _ => self.recency.first_key_value().map(|(_, key)| key.clone()),
+        };
+        if let Some(victim) = victim {
+            self.remove(&victim);
+        }
+    }
+}
+
+/// In-process cache evicting the least recently used entries
+pub struct MemoryCache {
+    capacity: usize,
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        Self {
+            capacity,
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+#[async_trait]
+impl ResponseCache for MemoryCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let tick = state.tick();
+        let entry = state.entries.get_mut(key)?;
+        if entry.expires_at.is_some_and(|at| at <= unix_now()) {
+            state.remove(key);
+            return None;
+        }
+        state.recency.remove(&entry.last_used);
+        state.recency.insert(tick, key.to_string());
+        entry.last_used = tick;
+        Some(entry.body.to_string())
+    }
+
+    async fn put(&self, key: &str, body: &str, expires_at: Option<i64>) {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.tick();
+        // Prefer dropping expired entries over live ones
+        if state.remove(key).is_none() && state.entries.len() >= self.capacity {
+            state.evict();
+        }
+        state.recency.insert(tick, key.to_string());
+        if let Some(at) = expires_at {
+            state.expiry.insert((at, key.to_string()));
+        }
+        state.entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                body: Arc::from(body),
+                expires_at,
+                last_used: tick,
+            },
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    key: String,
+    expires_at: Option<i64>,
+    body: String,
+}
+
+/// One JSON file per response under a directory, shared across restarts
+/// and processes
+pub struct DiskCache {
+    dir: PathBuf,
+    /// Numbers staging files so concurrent writes never share one
+    writes: AtomicU64,
+}
+
+impl DiskCache {
+    /// Use `dir`, creating it if needed
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            writes: AtomicU64::new(0),
+        })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
+
+#[async_trait]
+impl ResponseCache for DiskCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        let entry: DiskEntry = serde_json::from_slice(&bytes).ok()?;
+        // The file name is a hash; a different key means a collision
+        if entry.key != key {
+            return None;
+        }
+        if entry.expires_at.is_some_and(|at| at <= unix_now()) {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        Some(entry.body)
+    }
+
+    async fn put(&self, key: &str, body: &str, expires_at: Option<i64>) {
+        let entry = DiskEntry {
+            key: key.to_string(),
+            expires_at,
+            body: body.to_string(),
+        };
+        let Ok(bytes) = serde_json::to_vec(&entry) else {
+            return;
+        };
+        // Write then rename so concurrent readers never see a partial file
+        let path = self.path(key);
+        let write = self.writes.fetch_add(1, Ordering::Relaxed);
+        let staging = path.with_extension(format!("{}.{write}.tmp", std::process::id()));
+        if tokio::fs::write(&staging, bytes).await.is_ok() {
+            let _ = tokio::fs::rename(&staging, &path).await;
+        }
+    }
+}
+
+/// Per-endpoint cache lifetimes
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    endpoints: HashMap<String, CacheTtl>,
+    /// Lifetime of candle ranges that include a still-open candle
+    pub live_ohlcv_ttl: CacheTtl,
+    /// Lifetime of endpoints without an explicit entry
+    pub default_ttl: CacheTtl,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        let endpoints = [
+            ("/defi/token_overview", CacheTtl::For(Duration::from_secs(30))),
+            ("/defi/token_security", CacheTtl::For(Duration::from_secs(600))),
+            ("/defi/token_creation_info", CacheTtl::Forever),
+            ("/v1/wallet/token_list", CacheTtl::For(Duration::from_secs(15))),
+        ];
+        Self {
+            endpoints: endpoints.into_iter().map(|(path, ttl)| (path.to_string(), ttl)).collect(),
+            live_ohlcv_ttl: CacheTtl::For(Duration::from_secs(15)),
+            default_ttl: CacheTtl::Never,
+        }
+    }
+}
+
+impl CachePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the lifetime of `path`, e.g. `"/defi/token_overview"`
+    pub fn with_endpoint(mut self, path: impl Into<String>, ttl: CacheTtl) -> Self {
+        self.endpoints.insert(path.into(), ttl);
+        self
+    }
+
+    pub fn with_live_ohlcv_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.live_ohlcv_ttl = ttl;
+        self
+    }
+
+    pub fn with_default_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Lifetime of the response to `path_and_query`
+    ///
+    /// OHLCV ranges ending at least one interval in the past only contain
+    /// closed candles and are cached forever unless `/defi/ohlcv` has an
+    /// explicit entry.
+    pub fn ttl(&self, path_and_query: &str) -> CacheTtl {
+        let (path, query) = path_and_query.split_once('?').unwrap_or((path_and_query, ""));
+        if let Some(ttl) = self.endpoints.get(path) {
+            return *ttl;
+        }
+        if path == "/defi/ohlcv" {
+            let param = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            };
+            let closed = param("time_to")
+                .and_then(|t| t.parse::<i64>().ok())
+                .zip(param("type").and_then(interval_secs))
+                .is_some_and(|(time_to, interval)| time_to + interval <= unix_now());
+            return if closed { CacheTtl::Forever } else { self.live_ohlcv_ttl };
+        }
+        self.default_ttl
+    }
+}
+
+/// Length of a Birdeye interval such as `15m`, `4H` or `1W` (`1M` is a month)
+fn interval_secs(interval: &str) -> Option<i64> {
+    let unit = interval.chars().last()?;
+    let count: i64 = interval[..interval.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        'm' => 60,
+        'h' | 'H' => 3600,
+        'd' | 'D' => 86_400,
+        'w' | 'W' => 7 * 86_400,
+        'M' => 31 * 86_400,
+        _ => return None,
+    };
+    Some(count * secs)
+}
+
+/// Hit/miss counters of a `RestCache`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Responses written to the cache
+    pub stores: u64,
+    /// Requests whose endpoint is never cached
+    pub bypassed: u64,
+}
+
+impl CacheStats {
+    /// Share of cacheable requests served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// A cache backend with its policy and statistics
+pub struct RestCache {
+    backend: Arc<dyn ResponseCache>,
+    policy: CachePolicy,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    bypassed: AtomicU64,
+}
+
+impl fmt::Debug for RestCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestCache")
+            .field("policy", &self.policy)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RestCache {
+    pub fn new(backend: impl ResponseCache + 'static, policy: CachePolicy) -> Self {
+        Self::with_backend(Arc::new(backend), policy)
+    }
+
+    /// Share one backend between several clients
+    pub fn with_backend(backend: Arc<dyn ResponseCache>, policy: CachePolicy) -> Self {
+        Self {
+            backend,
+            policy,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            bypassed: AtomicU64::new(0),
+        }
+    }
+
+    /// In-memory cache with the default policy
+    pub fn memory() -> Self {
+        Self::new(MemoryCache::default(), CachePolicy::default())
+    }
+
+    /// On-disk cache under `dir` with the default policy
+    pub fn disk(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        Ok(Self::new(DiskCache::new(dir)?, CachePolicy::default()))
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Cached body for a request, or the TTL to store the fetched one with
+    pub(crate) async fn lookup(&self, key: &str, path_and_query: &str) -> Result<String, CacheTtl> {
+        let ttl = self.policy.ttl(path_and_query);
+        if ttl == CacheTtl::Never {
+            self.bypassed.fetch_add(1, Ordering::Relaxed);
+            return Err(ttl);
+        }
+        match self.backend.get(key).await {
+            Some(body) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(body)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Err(ttl)
+            }
+        }
+    }
+
+    pub(crate) async fn store(&self, key: &str, body: &str, ttl: CacheTtl) {
+        if ttl == CacheTtl::Never {
+            return;
+        }
+        self.backend.put(key, body, ttl.expires_at()).await;
+        self.stores.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("birdeye-cache-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn memory_cache_evicts_the_least_recently_used_entry() {
+        let cache = MemoryCache::new(2);
+        cache.put("a", "1", None).await;
+        cache.put("b", "2", None).await;
+        assert_eq!(cache.get("a").await.as_deref(), Some("1"));
+        cache.put("c", "3", None).await;
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b").await, None);
+        assert_eq!(cache.get("a").await.as_deref(), Some("1"));
+        // Overwriting an entry never evicts another
+        cache.put("c", "4", None).await;
+        assert_eq!((cache.get("a").await.as_deref(), cache.get("c").await.as_deref()), (Some("1"), Some("4")));
+    }
+
+    #[tokio::test]
+    async fn memory_cache_evicts_expired_entries_first() {
+        let cache = MemoryCache::new(2);
+        cache.put("expired", "1", Some(unix_now() - 1)).await;
+        cache.put("live", "2", Some(unix_now() + 60)).await;
+        assert_eq!(cache.get("live").await.as_deref(), Some("2"));
+        cache.put("new", "3", None).await;
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("live").await.as_deref(), Some("2"));
+        assert_eq!(cache.get("new").await.as_deref(), Some("3"));
+        cache.put("gone", "4", Some(unix_now())).await;
+        assert_eq!(cache.get("gone").await, None);
+    }
+
+    #[tokio::test]
+    async fn disk_cache_round_trips_and_expires() {
+        let dir = temp_dir("round-trip");
+        let cache = DiskCache::new(&dir).unwrap();
+        cache.put("key", "{\"a\":1}", None).await;
+        cache.put("old", "x", Some(unix_now() - 1)).await;
+
+        assert_eq!(cache.get("key").await.as_deref(), Some("{\"a\":1}"));
+        assert_eq!(cache.get("missing").await, None);
+        assert_eq!(cache.get("old").await, None);
+        assert!(!cache.path("old").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn concurrent_disk_writes_use_distinct_staging_files() {
+        let dir = temp_dir("concurrent");
+        let cache = Arc::new(DiskCache::new(&dir).unwrap());
+        let writes: Vec<_> = (0..16)
+            .map(|i| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.put("key", &format!("{{\"n\":{i}}}"), None).await })
+            })
+            .collect();
+        for write in writes {
+            write.await.unwrap();
+        }
+
+        let body = cache.get("key").await.unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&body).is_ok());
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(files, [cache.path("key").file_name().unwrap().to_owned()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interval_secs_parses_birdeye_intervals() {
+        assert_eq!(interval_secs("1m"), Some(60));
+        assert_eq!(interval_secs("15m"), Some(900));
+        assert_eq!(interval_secs("4H"), Some(4 * 3600));
+        assert_eq!(interval_secs("1D"), Some(86_400));
+        assert_eq!(interval_secs("1W"), Some(7 * 86_400));
+        assert_eq!(interval_secs("1M"), Some(31 * 86_400));
+        assert_eq!(interval_secs("m"), None);
+        assert_eq!(interval_secs("1y"), None);
+        assert_eq!(interval_secs(""), None);
+    }
+
+    #[test]
+    fn policy_caches_closed_candles_forever() {
+        let policy = CachePolicy::new();
+        let ohlcv = |time_to: i64, interval: &str| {
+            policy.ttl(&format!("/defi/ohlcv?address=A&type={interval}&time_from=0&time_to={time_to}"))
+        };
+        assert_eq!(ohlcv(unix_now() - 3600, "15m"), CacheTtl::Forever);
+        assert_eq!(ohlcv(unix_now() - 60, "15m"), CacheTtl::For(Duration::from_secs(15)));
+        assert_eq!(ohlcv(unix_now() - 3600, "1D"), CacheTtl::For(Duration::from_secs(15)));
+        assert_eq!(ohlcv(unix_now() - 3600, "bogus"), CacheTtl::For(Duration::from_secs(15)));
+
+        let pinned = CachePolicy::new().with_endpoint("/defi/ohlcv", CacheTtl::Never);
+        assert_eq!(pinned.ttl("/defi/ohlcv?type=1m&time_to=0"), CacheTtl::Never);
+    }
+
+    #[test]
+    fn policy_uses_endpoint_entries_then_the_default() {
+        let policy = CachePolicy::new();
+        assert_eq!(policy.ttl("/defi/token_overview?address=A"), CacheTtl::For(Duration::from_secs(30)));
+        assert_eq!(policy.ttl("/defi/token_creation_info?address=A"), CacheTtl::Forever);
+        assert_eq!(policy.ttl("/defi/price?address=A"), CacheTtl::Never);
+
+        let policy = policy.with_default_ttl(CacheTtl::For(Duration::from_secs(5)));
+        assert_eq!(policy.ttl("/defi/price"), CacheTtl::For(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn rest_cache_counts_hits_misses_stores_and_bypasses() {
+        let cache = RestCache::memory();
+        let overview = "/defi/token_overview?address=A";
+
+        let ttl = cache.lookup("solana:A", overview).await.unwrap_err();
+        cache.store("solana:A", "body", ttl).await;
+        assert_eq!(cache.lookup("solana:A", overview).await.as_deref(), Ok("body"));
+        assert_eq!(cache.lookup("solana:price", "/defi/price").await, Err(CacheTtl::Never));
+        cache.store("solana:price", "body", CacheTtl::Never).await;
+
+        let stats = cache.stats();
+        assert_eq!(stats, CacheStats { hits: 1, misses: 1, stores: 1, bypassed: 1 });
+        assert_eq!(stats.hit_rate(), 0.5);
+        assert_eq!(CacheStats::default().hit_rate(), 0.0);
+    }
+}
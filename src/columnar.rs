@@ -0,0 +1,536 @@
+//! Arrow record batches and partitioned Parquet files
+//!
+//! Files are laid out Hive-style so Polars and DuckDB can prune by token
+//! and day:
+//!
+//! ```text
+//! <root>/<dataset>/token=<address>/date=<YYYY-MM-DD>/part-<unix>-<seq>.parquet
+//! ```
+//!
+//! EVM addresses are lowercased in partition paths so checksummed and
+//! lowercase spellings of a token land in the same directory.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampSecondArray, UInt8Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::large_trades::LargeTradeData;
+use crate::rest::OHLCVData;
+use crate::trade::{is_quote_token, normalize_address, TradeLeg};
+use crate::txs::TransactionData;
+use crate::types::{unix_now, BirdeyeError};
+
+/// Payloads with a fixed Arrow schema
+pub trait Columnar: Sized {
+    /// Directory name of the dataset under the writer's root
+    const DATASET: &'static str;
+
+    fn schema() -> SchemaRef;
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch, BirdeyeError>;
+    /// Token address the row is partitioned under
+    fn partition_token(&self) -> &str;
+    fn unix_time(&self) -> i64;
+}
+
+fn timestamp_field(name: &str) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Second, Some("UTC".into())), false)
+}
+
+fn timestamps(values: impl IntoIterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampSecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+fn strings<'a>(values: impl IntoIterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn floats(values: impl IntoIterator<Item = f64>) -> ArrayRef {
+    Arc::new(Float64Array::from_iter_values(values))
+}
+
+fn leg_fields(prefix: &str) -> [Field; 5] {
+    [
+        Field::new(format!("{prefix}_address"), DataType::Utf8, false),
+        Field::new(format!("{prefix}_symbol"), DataType::Utf8, false),
+        Field::new(format!("{prefix}_decimals"), DataType::UInt8, false),
+        Field::new(format!("{prefix}_amount"), DataType::Float64, false),
+        Field::new(format!("{prefix}_price"), DataType::Float64, true),
+    ]
+}
+
+fn leg_columns<'a>(legs: &[(TradeLeg<'a>, u8)]) -> [ArrayRef; 5] {
+    [
+        strings(legs.iter().map(|(leg, _)| leg.address)),
+        strings(legs.iter().map(|(leg, _)| leg.symbol)),
+        Arc::new(UInt8Array::from_iter_values(legs.iter().map(|(_, decimals)| *decimals))),
+        floats(legs.iter().map(|(leg, _)| leg.ui_amount)),
+        Arc::new(legs.iter().map(|(leg, _)| leg.price).collect::<Float64Array>()),
+    ]
+}
+
+impl Columnar for OHLCVData {
+    const DATASET: &'static str = "ohlcv";
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("address", DataType::Utf8, false),
+            Field::new("interval", DataType::Utf8, false),
+            timestamp_field("unix_time"),
+            Field::new("open", DataType::Float64, false),
+            Field::new("high", DataType::Float64, false),
+            Field::new("low", DataType::Float64, false),
+            Field::new("close", DataType::Float64, false),
+            Field::new("volume", DataType::Float64, false),
+        ]))
+    }
+
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch, BirdeyeError> {
+        let columns = vec![
+            strings(rows.iter().map(|r| r.address.as_str())),
+            strings(rows.iter().map(|r| r.r#type.as_str())),
+            timestamps(rows.iter().map(|r| r.unix_time)),
+            floats(rows.iter().map(|r| r.open)),
+            floats(rows.iter().map(|r| r.high)),
+            floats(rows.iter().map(|r| r.low)),
+            floats(rows.iter().map(|r| r.close)),
+            floats(rows.iter().map(|r| r.volume)),
+        ];
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+
+    fn partition_token(&self) -> &str {
+        &self.address
+    }
+
+    fn unix_time(&self) -> i64 {
+        self.unix_time
+    }
+}
+
+impl Columnar for TransactionData {
+    const DATASET: &'static str = "transactions";
+
+    fn schema() -> SchemaRef {
+        let mut fields = vec![
+            Field::new("tx_hash", DataType::Utf8, false),
+            timestamp_field("block_unix_time"),
+            Field::new("owner", DataType::Utf8, false),
+            Field::new("source", DataType::Utf8, false),
+            Field::new("platform", DataType::Utf8, false),
+            Field::new("pool_address", DataType::Utf8, true),
+            Field::new("is_trade_on_be", DataType::Boolean, false),
+            Field::new("volume_usd", DataType::Float64, false),
+        ];
+        fields.extend(leg_fields("from"));
+        fields.extend(leg_fields("to"));
+        Arc::new(Schema::new(fields))
+    }
+
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch, BirdeyeError> {
+        let from: Vec<_> = rows.iter().map(|r| (TradeLeg::from(&r.from), r.from.decimals)).collect();
+        let to: Vec<_> = rows.iter().map(|r| (TradeLeg::from(&r.to), r.to.decimals)).collect();
+        let mut columns = vec![
+            strings(rows.iter().map(|r| r.tx_hash.as_str())),
+            timestamps(rows.iter().map(|r| r.block_unix_time)),
+            strings(rows.iter().map(|r| r.owner.as_str())),
+            strings(rows.iter().map(|r| r.source.as_str())),
+            strings(rows.iter().map(|r| r.platform.as_str())),
+            Arc::new(rows.iter().map(|r| r.pool_address.as_deref()).collect::<StringArray>()),
+            Arc::new(rows.iter().map(|r| Some(r.is_trade_on_be)).collect::<BooleanArray>()),
+            floats(rows.iter().map(|r| r.volume_usd)),
+        ];
+        columns.extend(leg_columns(&from));
+        columns.extend(leg_columns(&to));
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+
+    /// The non-quote side of the swap
+    fn partition_token(&self) -> &str {
+        partition_token(&self.from.address, &self.to.address)
+    }
+
+    fn unix_time(&self) -> i64 {
+        self.block_unix_time
+    }
+}
+
+impl Columnar for LargeTradeData {
+    const DATASET: &'static str = "large_trades";
+
+    fn schema() -> SchemaRef {
+        let mut fields = vec![
+            Field::new("tx_hash", DataType::Utf8, false),
+            timestamp_field("block_unix_time"),
+            Field::new("owner", DataType::Utf8, false),
+            Field::new("source", DataType::Utf8, false),
+            Field::new("pool_address", DataType::Utf8, false),
+            Field::new("network", DataType::Utf8, false),
+            Field::new("volume_usd", DataType::Float64, false),
+        ];
+        fields.extend(leg_fields("from"));
+        fields.extend(leg_fields("to"));
+        Arc::new(Schema::new(fields))
+    }
+
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch, BirdeyeError> {
+        let from: Vec<_> = rows.iter().map(|r| (TradeLeg::from(&r.from), r.from.decimals)).collect();
+        let to: Vec<_> = rows.iter().map(|r| (TradeLeg::from(&r.to), r.to.decimals)).collect();
+        let mut columns = vec![
+            strings(rows.iter().map(|r| r.tx_hash.as_str())),
+            timestamps(rows.iter().map(|r| r.block_unix_time)),
+            strings(rows.iter().map(|r| r.owner.as_str())),
+            strings(rows.iter().map(|r| r.source.as_str())),
+            strings(rows.iter().map(|r| r.pool_address.as_str())),
+            strings(rows.iter().map(|r| r.network.as_str())),
+            floats(rows.iter().map(|r| r.volume_usd)),
+        ];
+        columns.extend(leg_columns(&from));
+        columns.extend(leg_columns(&to));
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+
+    /// The non-quote side of the swap
+    fn partition_token(&self) -> &str {
+        partition_token(&self.from.address, &self.to.address)
+    }
+
+    fn unix_time(&self) -> i64 {
+        self.block_unix_time
+    }
+}
+
+fn partition_token<'a>(from: &'a str, to: &'a str) -> &'a str {
+    if is_quote_token(to) && !is_quote_token(from) {
+        from
+    } else {
+        to
+    }
+}
+
+/// `YYYY-MM-DD` of a Unix timestamp in UTC
+fn utc_date(unix_time: i64) -> String {
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = unix_time.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Options for `PartitionedParquetWriter`
+#[derive(Debug, Clone)]
+pub struct ParquetWriterOptions {
+    /// Rows buffered per partition before a file is written
+    pub rows_per_file: usize,
+    pub compression: Compression,
+}
+
+impl Default for ParquetWriterOptions {
+    fn default() -> Self {
+        Self {
+            rows_per_file: 100_000,
+            compression: Compression::SNAPPY,
+        }
+    }
+}
+
+impl ParquetWriterOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rows_per_file(mut self, rows: usize) -> Self {
+        assert!(rows >= 1, "rows_per_file must be at least 1");
+        self.rows_per_file = rows;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// Buffers rows per token and day and writes each partition to its own
+/// Parquet files
+///
+/// Parquet files cannot be appended to, so every flush of a partition
+/// creates a new part file next to the existing ones.
+pub struct PartitionedParquetWriter<T: Columnar> {
+    root: PathBuf,
+    options: ParquetWriterOptions,
+    buffers: HashMap<(String, String), Vec<T>>,
+    sequence: u64,
+}
+
+impl<T: Columnar> PartitionedParquetWriter<T> {
+    pub fn new(root: impl Into<PathBuf>, options: ParquetWriterOptions) -> Self {
+        Self {
+            root: root.into(),
+            options,
+            buffers: HashMap::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Buffer `rows`, writing any partition that reached `rows_per_file`
+    ///
+    /// Returns the paths of the files written.
+    pub fn write(&mut self, rows: impl IntoIterator<Item = T>) -> Result<Vec<PathBuf>, BirdeyeError> {
+        let mut written = Vec::new();
+        for row in rows {
+            let key = (normalize_address(row.partition_token()), utc_date(row.unix_time()));
+            let buffer = self.buffers.entry(key.clone()).or_default();
+            buffer.push(row);
+            if buffer.len() == self.options.rows_per_file {
+                let rows = self.buffers.remove(&key).unwrap_or_default();
+                written.push(self.write_partition(&key, &rows)?);
+            }
+        }
+        Ok(written)
+    }
+
+    /// Write every buffered partition
+    pub fn flush(&mut self) -> Result<Vec<PathBuf>, BirdeyeError> {
+        let mut written = Vec::new();
+        for (key, rows) in std::mem::take(&mut self.buffers) {
+            written.push(self.write_partition(&key, &rows)?);
+        }
+        Ok(written)
+    }
+
+    /// Write the partitions of days before `date` (`YYYY-MM-DD`, UTC)
+    ///
+    /// Used at day rollover: once a day is over its partitions receive no
+    /// new rows, so holding them back would only delay their files.
+    pub fn flush_days_before(&mut self, date: &str) -> Result<Vec<PathBuf>, BirdeyeError> {
+        let mut done: Vec<_> = self.buffers.keys().filter(|(_, day)| day.as_str() < date).cloned().collect();
+        done.sort();
+        let mut written = Vec::new();
+        for key in done {
+            if let Some(rows) = self.buffers.remove(&key) {
+                written.push(self.write_partition(&key, &rows)?);
+            }
+        }
+        Ok(written)
+    }
+
+    pub fn buffered_rows(&self) -> usize {
+        self.buffers.values().map(Vec::len).sum()
+    }
+
+    fn write_partition(&mut self, (token, date): &(String, String), rows: &[T]) -> Result<PathBuf, BirdeyeError> {
+        let dir = self
+            .root
+            .join(T::DATASET)
+            .join(format!("token={token}"))
+            .join(format!("date={date}"));
+        fs::create_dir_all(&dir)?;
+        self.sequence += 1;
+        let path = dir.join(format!("part-{}-{:05}.parquet", unix_now(), self.sequence));
+        write_parquet(&path, &T::to_record_batch(rows)?, self.options.compression)?;
+        Ok(path)
+    }
+}
+
+/// Write a single record batch to `path`
+pub fn write_parquet(path: &Path, batch: &RecordBatch, compression: Compression) -> Result<(), BirdeyeError> {
+    let properties = WriterProperties::builder().set_compression(compression).build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), Some(properties))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Record live events from `rows` into `writer`
+///
+/// Rows are handed to the writer every `period`, which writes a partition
+/// once it reaches `rows_per_file`. Partitions of earlier UTC days are
+/// written at day rollover and the rest on shutdown, so files stay close to
+/// `rows_per_file` rows instead of one per period. Writes run on the
+/// blocking thread pool. The task ends, after a final flush, once the
+/// channel is closed; the first write error, or a panic on the blocking
+/// thread, stops it.
+pub fn spawn_recorder<T>(
+    mut writer: PartitionedParquetWriter<T>,
+    mut rows: mpsc::Receiver<T>,
+    period: Duration,
+) -> JoinHandle<Result<(), BirdeyeError>>
+where
+    T: Columnar + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        let mut pending = Vec::new();
+        loop {
+            let closed = tokio::select! {
+                row = rows.recv() => match row {
+                    Some(row) => {
+                        pending.push(row);
+                        continue;
+                    }
+                    None => true,
+                },
+                _ = interval.tick() => false,
+            };
+
+            let batch = std::mem::take(&mut pending);
+            let result;
+            (writer, result) = tokio::task::spawn_blocking(move || {
+                let result = writer.write(batch).and_then(|_| {
+                    if closed {
+                        writer.flush()
+                    } else {
+                        writer.flush_days_before(&utc_date(unix_now()))
+                    }
+                });
+                (writer, result)
+            })
+            .await?;
+            result?;
+            if closed {
+                return Ok(());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn candle(address: &str, unix_time: i64) -> OHLCVData {
+        serde_json::from_value(json!({
+            "address": address, "o": 1.0, "h": 2.0, "l": 0.5, "c": 1.5,
+            "type": "1m", "unixTime": unix_time, "v": 10.0,
+        }))
+        .unwrap()
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("birdeye-columnar-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    /// Row counts of the part files under `dir`, sorted
+    fn part_rows(dir: &Path) -> Vec<usize> {
+        let mut counts: Vec<usize> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .map(|entry| {
+                    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(entry.unwrap().path()).unwrap())
+                        .unwrap()
+                        .build()
+                        .unwrap();
+                    reader.map(|batch| batch.unwrap().num_rows()).sum()
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        counts.sort();
+        counts
+    }
+
+    #[test]
+    fn utc_dates_match_the_civil_calendar() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_782_400), "2000-02-29");
+        assert_eq!(utc_date(1_709_251_199), "2024-02-29");
+        assert_eq!(utc_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn trades_partition_under_the_non_quote_token() {
+        assert_eq!(partition_token(USDC, "BONK"), "BONK");
+        assert_eq!(partition_token("BONK", USDC), "BONK");
+        assert_eq!(partition_token("A", "B"), "B");
+    }
+
+    #[test]
+    fn writes_a_partition_when_it_reaches_rows_per_file() {
+        let root = temp_root("full");
+        let mut writer = PartitionedParquetWriter::new(&root, ParquetWriterOptions::new().with_rows_per_file(2));
+
+        assert!(writer.write([candle("A", 0), candle("B", 0)]).unwrap().is_empty());
+        let written = writer.write([candle("A", 60)]).unwrap();
+        assert_eq!(written.len(), 1);
+        assert!(written[0].starts_with(root.join("ohlcv/token=A/date=1970-01-01")));
+        assert_eq!(writer.buffered_rows(), 1);
+
+        assert_eq!(writer.flush().unwrap().len(), 1);
+        assert_eq!(part_rows(&root.join("ohlcv/token=A/date=1970-01-01")), [2]);
+        assert_eq!(part_rows(&root.join("ohlcv/token=B/date=1970-01-01")), [1]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn evm_partitions_ignore_address_case() {
+        let root = temp_root("case");
+        let mut writer = PartitionedParquetWriter::new(&root, ParquetWriterOptions::new());
+        writer
+            .write([candle("0xAbCd", 0), candle("0xabcd", 60), candle("SoLana", 120)])
+            .unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(part_rows(&root.join("ohlcv/token=0xabcd/date=1970-01-01")), [2]);
+        assert_eq!(part_rows(&root.join("ohlcv/token=SoLana/date=1970-01-01")), [1]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn flush_days_before_keeps_the_current_day_buffered() {
+        let root = temp_root("rollover");
+        let mut writer = PartitionedParquetWriter::new(&root, ParquetWriterOptions::new());
+        writer.write([candle("A", 0), candle("A", 86_400)]).unwrap();
+
+        assert_eq!(writer.flush_days_before("1970-01-02").unwrap().len(), 1);
+        assert_eq!(writer.buffered_rows(), 1);
+        assert_eq!(part_rows(&root.join("ohlcv/token=A/date=1970-01-01")), [1]);
+        assert!(part_rows(&root.join("ohlcv/token=A/date=1970-01-02")).is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn recorder_holds_partitions_until_full_rollover_or_shutdown() {
+        let root = temp_root("recorder");
+        let writer = PartitionedParquetWriter::new(&root, ParquetWriterOptions::new().with_rows_per_file(2));
+        let (sender, receiver) = mpsc::channel(16);
+        let handle = spawn_recorder(writer, receiver, Duration::from_millis(10));
+
+        let now = unix_now();
+        let today = root.join(format!("ohlcv/token=A/date={}", utc_date(now)));
+        let yesterday = root.join(format!("ohlcv/token=B/date={}", utc_date(now - 86_400)));
+        for row in [candle("A", now), candle("A", now), candle("A", now), candle("B", now - 86_400)] {
+            sender.send(row).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(part_rows(&today), [2]);
+        assert_eq!(part_rows(&yesterday), [1]);
+
+        drop(sender);
+        handle.await.unwrap().unwrap();
+        assert_eq!(part_rows(&today), [1, 2]);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
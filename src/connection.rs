@@ -1,27 +1,117 @@
+use std::fmt;
+use std::time::Instant;
+
+use futures_util::SinkExt;
 use tokio_tungstenite::{connect_async, WebSocketStream, MaybeTlsStream};
-use tokio_tungstenite::tungstenite::http::{Request, HeaderValue};
+use tokio_tungstenite::tungstenite::http::Request;
+use tokio_tungstenite::tungstenite::Message;
 use tokio::net::TcpStream;
 use url::Url;
-use crate::types::BirdeyeError;
+#[cfg(feature = "metrics")]
+use crate::metrics::BirdeyeMetrics;
+use crate::latency::LatencyTracker;
+use crate::types::{unix_now_ms, BirdeyeError, ResponseType, SubscriptionMessage, WebSocketResponse, REDACTED};
+use crate::usage::UsageTracker;
+
+/// WebSocket endpoint; the API key is appended as a query parameter
+const WS_URL: &str = "wss://public-api.birdeye.so/socket/solana";
 
+/// An open Birdeye WebSocket connection
+pub type BirdeyeStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Clone)]
 pub struct BirdeyeWebSocket {
     api_key: String,
+    url: String,
+    usage: Option<UsageTracker>,
+    latency: Option<LatencyTracker>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<BirdeyeMetrics>,
+}
+
+impl fmt::Debug for BirdeyeWebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BirdeyeWebSocket")
+            .field("api_key", &REDACTED)
+            .field("usage", &self.usage)
+            .field("latency", &self.latency)
+            .finish_non_exhaustive()
+    }
 }
 
 impl BirdeyeWebSocket {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             api_key: api_key.into(),
+            url: WS_URL.to_string(),
+            usage: None,
+            latency: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         }
     }
 
-    pub async fn connect(&self) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, BirdeyeError> {
-        println!("Connecting to Birdeye WebSocket...");
-        let url = format!(
-            "wss://public-api.birdeye.so/socket/solana?x-api-key={}",
-            self.api_key
-        );
-        let url = Url::parse(&url)?;
+    /// Connect to `url` instead of Birdeye, e.g. a local stand-in
+    #[cfg(test)]
+    pub(crate) fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Count received messages, bytes and compute units into `usage`
+    ///
+    /// Only messages read through `decode` are counted. A blocking budget
+    /// makes `connect` fail with `BirdeyeError::BudgetExceeded` once used up.
+    pub fn with_usage(mut self, usage: UsageTracker) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    pub fn usage(&self) -> Option<&UsageTracker> {
+        self.usage.as_ref()
+    }
+
+    /// Keep latency percentiles of decoded events in `latency`
+    pub fn with_latency(mut self, latency: LatencyTracker) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    pub fn latency(&self) -> Option<&LatencyTracker> {
+        self.latency.as_ref()
+    }
+
+    /// Record connects, message rates, decode errors and lag into `metrics`
+    ///
+    /// Like usage, messages are only recorded when read through `decode`.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: BirdeyeMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<&BirdeyeMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Note that a lost or failed connection is about to be retried
+    pub(crate) fn record_reconnect(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_reconnect();
+        }
+    }
+
+    #[tracing::instrument(name = "connect", skip_all, fields(url = %self.url))]
+    pub async fn connect(&self) -> Result<BirdeyeStream, BirdeyeError> {
+        if let Some(usage) = &self.usage {
+            usage.check()?;
+        }
+        tracing::debug!("connecting to Birdeye WebSocket");
+        let started = Instant::now();
+        let mut url = Url::parse(&self.url)?;
+        url.query_pairs_mut().append_pair("x-api-key", &self.api_key);
 
         let request = Request::builder()
             .uri(url.as_str())
@@ -35,11 +125,161 @@ impl BirdeyeWebSocket {
             .header("Sec-WebSocket-Key", tokio_tungstenite::tungstenite::handshake::client::generate_key())
             .body(())
             .unwrap();
-        let (ws_stream, response) = connect_async(request).await?;
+        let connected = connect_async(request).await;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_connect(connected.is_ok());
+        }
+        let (ws_stream, response) =
+            connected.inspect_err(|e| tracing::warn!(error = %e, "WebSocket connection failed"))?;
+        tracing::info!(
+            status = response.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "connected to Birdeye WebSocket"
+        );
         Ok(ws_stream)
     }
 
-    pub fn parse_response(text: &str) -> Result<crate::types::WebSocketResponse, BirdeyeError> {
+    /// Send a (un)subscription message over an open connection
+    pub async fn send_message(stream: &mut BirdeyeStream, message: &SubscriptionMessage) -> Result<(), BirdeyeError> {
+        let text = serde_json::to_string(message)?;
+        tracing::debug!(msg_type = ?message.msg_type, data = %message.data, "sending subscription message");
+        stream.send(Message::Text(text.into())).await?;
+        Ok(())
+    }
+
+    pub fn parse_response(text: &str) -> Result<WebSocketResponse, BirdeyeError> {
         Ok(serde_json::from_str(text)?)
     }
-} 
\ No newline at end of file
+
+    /// `parse_response` for a message received just now: stamps
+    /// `received_at` and feeds the usage, latency and metrics trackers
+    pub fn decode(&self, text: &str) -> Result<WebSocketResponse, BirdeyeError> {
+        let received_at = unix_now_ms();
+        let response = Self::parse_response(text).map(|mut response| {
+            response.received_at = Some(received_at);
+            response
+        });
+        match &response {
+            Ok(response) if matches!(response.response_type, ResponseType::Error) => {
+                tracing::warn!(data = %response.data, "server sent an error");
+            }
+            Ok(response) => {
+                tracing::trace!(response_type = response.response_type.as_str(), bytes = text.len(), "decoded message");
+            }
+            // Welcome and acknowledgement messages have no `ResponseType`
+            Err(e) => tracing::debug!(error = %e, bytes = text.len(), "skipping non-protocol message"),
+        }
+        if let Some(usage) = &self.usage {
+            usage.record_message(response.as_ref().ok().map(|r| &r.response_type), text.len());
+        }
+        if let (Some(latency), Ok(response)) = (&self.latency, &response) {
+            latency.observe(response);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            match &response {
+                Ok(response) => metrics.record_message(response, text.len()),
+                Err(_) => metrics.record_unparsed(text),
+            }
+        }
+        response
+    }
+}
+
+/// Local stand-in for the Birdeye WebSocket: answers every message a client
+/// sends with `frames` and passes the message on to the returned receiver
+#[cfg(test)]
+pub(crate) async fn stand_in_upstream(frames: Vec<String>) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+    use futures_util::StreamExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/socket/solana", listener.local_addr().unwrap());
+    let (messages, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let (frames, messages) = (frames.clone(), messages.clone());
+            tokio::spawn(async move {
+                let head = crate::http::read_request_head(&mut stream).await.unwrap();
+                let key = head
+                    .lines()
+                    .find_map(|line| line.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case("sec-websocket-key")))
+                    .map(|(_, key)| key.trim())
+                    .unwrap();
+                // Clients ask for `echo-protocol` and expect it back
+                let accept = format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                     Sec-WebSocket-Accept: {}\r\nSec-WebSocket-Protocol: echo-protocol\r\n\r\n",
+                    derive_accept_key(key.as_bytes())
+                );
+                stream.write_all(accept.as_bytes()).await.unwrap();
+                let mut socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+                while let Some(Ok(Message::Text(text))) = socket.next().await {
+                    let _ = messages.send(text.to_string());
+                    for frame in &frames {
+                        if socket.send(Message::Text(frame.as_str().into())).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    (url, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::types::capture_logs;
+
+    const KEY: &str = "secret-api-key";
+
+    #[test]
+    fn debug_output_redacts_the_api_key() {
+        let debug = format!("{:?}", BirdeyeWebSocket::new(KEY));
+        assert!(debug.contains(REDACTED) && !debug.contains(KEY));
+    }
+
+    #[test]
+    fn decode_stamps_protocol_messages_and_rejects_others() {
+        let ws = BirdeyeWebSocket::new(KEY);
+        let before = unix_now_ms();
+        let response = ws.decode(r#"{"type":"PRICE_DATA","data":{"unixTime":1}}"#).unwrap();
+        assert!(response.received_at.is_some_and(|at| at >= before));
+        assert_eq!(response.event_time(), Some(1));
+        assert!(ws.decode(r#"{"message":"welcome"}"#).is_err());
+        assert!(BirdeyeWebSocket::parse_response(r#"{"type":"PRICE_DATA","data":{}}"#).unwrap().received_at.is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn logs_never_contain_the_api_key() {
+        let (url, mut messages) = stand_in_upstream(vec![r#"{"type":"ERROR","data":{"message":"bad"}}"#.to_string()]).await;
+        let ws = BirdeyeWebSocket::new(KEY).with_url(&url);
+        let (reply, logs) = capture_logs(async {
+            let mut stream = ws.connect().await.unwrap();
+            let subscribe = SubscriptionMessage {
+                msg_type: crate::types::SubscriptionType::SubscribePrice,
+                data: serde_json::json!({ "address": "A" }),
+            };
+            BirdeyeWebSocket::send_message(&mut stream, &subscribe).await.unwrap();
+            match tokio::time::timeout(Duration::from_secs(5), futures_util::StreamExt::next(&mut stream)).await {
+                Ok(Some(Ok(Message::Text(text)))) => ws.decode(&text),
+                other => panic!("expected a text frame, got {other:?}"),
+            }
+        })
+        .await;
+        assert!(matches!(reply.unwrap().response_type, ResponseType::Error));
+        assert!(messages.recv().await.unwrap().contains("SUBSCRIBE_PRICE"));
+
+        assert!(logs.contains(&format!("connect{{url={url}}}")));
+        assert!(logs.contains("connected to Birdeye WebSocket status=101 latency_ms="));
+        assert!(logs.contains("server sent an error"));
+        assert!(!logs.contains(KEY));
+    }
+}
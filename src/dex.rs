@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::large_trades::LargeTradeData;
+use crate::new_pair::NewPairData;
+use crate::txs::TransactionData;
+use crate::wallet_txs::WalletTxData;
+
+/// Known trading venues reported in `source` / `platform` fields
+///
+/// Unknown values are preserved in `Other` as lowercase strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DexSource {
+    // Solana
+    Raydium,
+    RaydiumClmm,
+    RaydiumCp,
+    Orca,
+    Meteora,
+    MeteoraDlmm,
+    Phoenix,
+    Lifinity,
+    OpenBook,
+    PumpFun,
+    PumpAmm,
+    Moonshot,
+    Jupiter,
+    FluxBeam,
+    Saber,
+    // EVM
+    UniswapV2,
+    UniswapV3,
+    UniswapV4,
+    SushiSwap,
+    PancakeSwap,
+    Curve,
+    Balancer,
+    Aerodrome,
+    TraderJoe,
+    Other(String),
+}
+
+impl DexSource {
+    /// Canonical Birdeye identifier for this venue
+    pub fn as_str(&self) -> &str {
+        match self {
+            DexSource::Raydium => "raydium",
+            DexSource::RaydiumClmm => "raydium_clamm",
+            DexSource::RaydiumCp => "raydium_cp",
+            DexSource::Orca => "orca",
+            DexSource::Meteora => "meteora",
+            DexSource::MeteoraDlmm => "meteora_dlmm",
+            DexSource::Phoenix => "phoenix",
+            DexSource::Lifinity => "lifinity",
+            DexSource::OpenBook => "openbook",
+            DexSource::PumpFun => "pump_dot_fun",
+            DexSource::PumpAmm => "pump_amm",
+            DexSource::Moonshot => "moonshot",
+            DexSource::Jupiter => "jupiter",
+            DexSource::FluxBeam => "fluxbeam",
+            DexSource::Saber => "saber",
+            DexSource::UniswapV2 => "uniswap_v2",
+            DexSource::UniswapV3 => "uniswap_v3",
+            DexSource::UniswapV4 => "uniswap_v4",
+            DexSource::SushiSwap => "sushiswap",
+            DexSource::PancakeSwap => "pancakeswap",
+            DexSource::Curve => "curve",
+            DexSource::Balancer => "balancer",
+            DexSource::Aerodrome => "aerodrome",
+            DexSource::TraderJoe => "traderjoe",
+            DexSource::Other(s) => s,
+        }
+    }
+
+    /// Parse a raw `source` / `platform` value, never failing
+    pub fn parse(raw: &str) -> Self {
+        let normalized = raw.trim().to_ascii_lowercase().replace(['-', ' ', '.'], "_");
+        match normalized.as_str() {
+            "raydium" | "raydium_v4" | "raydium_amm" => DexSource::Raydium,
+            "raydium_clamm" | "raydium_clmm" => DexSource::RaydiumClmm,
+            "raydium_cp" | "raydium_cpmm" => DexSource::RaydiumCp,
+            "orca" | "whirlpool" | "orca_whirlpool" => DexSource::Orca,
+            "meteora" | "meteora_amm" | "meteora_pools" => DexSource::Meteora,
+            "meteora_dlmm" => DexSource::MeteoraDlmm,
+            "phoenix" => DexSource::Phoenix,
+            "lifinity" | "lifinity_v2" => DexSource::Lifinity,
+            "openbook" | "openbook_v2" => DexSource::OpenBook,
+            "pump_dot_fun" | "pump_fun" | "pumpfun" | "pump" => DexSource::PumpFun,
+            "pump_amm" | "pumpswap" | "pump_swap" => DexSource::PumpAmm,
+            "moonshot" => DexSource::Moonshot,
+            "jupiter" | "jupiter_v6" => DexSource::Jupiter,
+            "fluxbeam" => DexSource::FluxBeam,
+            "saber" => DexSource::Saber,
+            "uniswap_v2" | "uniswapv2" => DexSource::UniswapV2,
+            "uniswap_v3" | "uniswapv3" => DexSource::UniswapV3,
+            "uniswap_v4" | "uniswapv4" => DexSource::UniswapV4,
+            "sushiswap" | "sushi" | "sushiswap_v2" | "sushiswap_v3" => DexSource::SushiSwap,
+            "pancakeswap" | "pancakeswap_v2" | "pancakeswap_v3" | "pancake" => DexSource::PancakeSwap,
+            "curve" => DexSource::Curve,
+            "balancer" | "balancer_v2" => DexSource::Balancer,
+            "aerodrome" | "aerodrome_v2" | "aerodrome_slipstream" => DexSource::Aerodrome,
+            "traderjoe" | "trader_joe" | "traderjoe_v2" => DexSource::TraderJoe,
+            _ => DexSource::Other(normalized),
+        }
+    }
+
+    /// Whether the venue is a Solana program
+    pub fn is_solana(&self) -> bool {
+        matches!(
+            self,
+            DexSource::Raydium
+                | DexSource::RaydiumClmm
+                | DexSource::RaydiumCp
+                | DexSource::Orca
+                | DexSource::Meteora
+                | DexSource::MeteoraDlmm
+                | DexSource::Phoenix
+                | DexSource::Lifinity
+                | DexSource::OpenBook
+                | DexSource::PumpFun
+                | DexSource::PumpAmm
+                | DexSource::Moonshot
+                | DexSource::Jupiter
+                | DexSource::FluxBeam
+                | DexSource::Saber
+        )
+    }
+
+    /// Whether the venue is deployed on EVM chains
+    pub fn is_evm(&self) -> bool {
+        matches!(
+            self,
+            DexSource::UniswapV2
+                | DexSource::UniswapV3
+                | DexSource::UniswapV4
+                | DexSource::SushiSwap
+                | DexSource::PancakeSwap
+                | DexSource::Curve
+                | DexSource::Balancer
+                | DexSource::Aerodrome
+                | DexSource::TraderJoe
+        )
+    }
+}
+
+impl FromStr for DexSource {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(DexSource::parse(s))
+    }
+}
+
+impl fmt::Display for DexSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for DexSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DexSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Ok(DexSource::parse(&raw))
+    }
+}
+
+/// Events that report the venue they were executed on
+pub trait TradeVenue {
+    fn dex_source(&self) -> DexSource;
+}
+
+impl TradeVenue for TransactionData {
+    fn dex_source(&self) -> DexSource {
+        DexSource::parse(&self.source)
+    }
+}
+
+impl TradeVenue for LargeTradeData {
+    fn dex_source(&self) -> DexSource {
+        DexSource::parse(&self.source)
+    }
+}
+
+impl TradeVenue for NewPairData {
+    fn dex_source(&self) -> DexSource {
+        DexSource::parse(&self.source)
+    }
+}
+
+impl TradeVenue for WalletTxData {
+    fn dex_source(&self) -> DexSource {
+        DexSource::parse(&self.source)
+    }
+}
+
+impl TransactionData {
+    /// Typed view of the `platform` field
+    pub fn dex_platform(&self) -> DexSource {
+        DexSource::parse(&self.platform)
+    }
+}
+
+/// Allow/deny list of venues for filtering event streams
+#[derive(Debug, Clone, Default)]
+pub struct DexSourceFilter {
+    allowed: Option<HashSet<DexSource>>,
+    denied: HashSet<DexSource>,
+}
+
+impl DexSourceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept events from the given venues
+    pub fn allow(mut self, sources: impl IntoIterator<Item = DexSource>) -> Self {
+        self.allowed.get_or_insert_with(HashSet::new).extend(sources);
+        self
+    }
+
+    /// Reject events from the given venues
+    pub fn deny(mut self, sources: impl IntoIterator<Item = DexSource>) -> Self {
+        self.denied.extend(sources);
+        self
+    }
+
+    /// Only accept Solana venues
+    pub fn solana_only(self) -> Self {
+        self.allow(ALL_KNOWN.iter().filter(|s| s.is_solana()).cloned())
+    }
+
+    /// Only accept EVM venues
+    pub fn evm_only(self) -> Self {
+        self.allow(ALL_KNOWN.iter().filter(|s| s.is_evm()).cloned())
+    }
+
+    pub fn accepts(&self, source: &DexSource) -> bool {
+        if self.denied.contains(source) {
+            return false;
+        }
+        match &self.allowed {
+            Some(allowed) => allowed.contains(source),
+            None => true,
+        }
+    }
+
+    pub fn matches<T: TradeVenue>(&self, event: &T) -> bool {
+        self.accepts(&event.dex_source())
+    }
+}
+
+/// Keep only events whose venue passes `filter`
+pub fn filter_by_source<'a, T, I>(events: I, filter: &'a DexSourceFilter) -> impl Iterator<Item = T> + 'a
+where
+    T: TradeVenue + 'a,
+    I: IntoIterator<Item = T>,
+    I::IntoIter: 'a,
+{
+    events.into_iter().filter(move |event| filter.matches(event))
+}
+
+const ALL_KNOWN: &[DexSource] = &[
+    DexSource::Raydium,
+    DexSource::RaydiumClmm,
+    DexSource::RaydiumCp,
+    DexSource::Orca,
+    DexSource::Meteora,
+    DexSource::MeteoraDlmm,
+    DexSource::Phoenix,
+    DexSource::Lifinity,
+    DexSource::OpenBook,
+    DexSource::PumpFun,
+    DexSource::PumpAmm,
+    DexSource::Moonshot,
+    DexSource::Jupiter,
+    DexSource::FluxBeam,
+    DexSource::Saber,
+    DexSource::UniswapV2,
+    DexSource::UniswapV3,
+    DexSource::UniswapV4,
+    DexSource::SushiSwap,
+    DexSource::PancakeSwap,
+    DexSource::Curve,
+    DexSource::Balancer,
+    DexSource::Aerodrome,
+    DexSource::TraderJoe,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_aliases_and_spelling_variants() {
+        assert_eq!(DexSource::parse("Raydium"), DexSource::Raydium);
+        assert_eq!(DexSource::parse("raydium-clmm"), DexSource::RaydiumClmm);
+        assert_eq!(DexSource::parse("Pump.fun"), DexSource::PumpFun);
+        assert_eq!(DexSource::parse(" Uniswap V3 "), DexSource::UniswapV3);
+        assert_eq!(DexSource::parse("whirlpool"), DexSource::Orca);
+    }
+
+    #[test]
+    fn unknown_sources_are_kept_normalized() {
+        let source = DexSource::parse("Some New-Dex");
+        assert_eq!(source, DexSource::Other("some_new_dex".to_string()));
+        assert_eq!(source.to_string(), "some_new_dex");
+        assert!(!source.is_solana() && !source.is_evm());
+    }
+
+    #[test]
+    fn canonical_names_round_trip() {
+        for source in ALL_KNOWN {
+            assert_eq!(&DexSource::parse(source.as_str()), source);
+            assert!(source.is_solana() != source.is_evm());
+        }
+    }
+
+    #[test]
+    fn serde_uses_canonical_names() {
+        let json = serde_json::to_string(&DexSource::PumpFun).unwrap();
+        assert_eq!(json, "\"pump_dot_fun\"");
+        let parsed: DexSource = serde_json::from_str("\"PUMP_FUN\"").unwrap();
+        assert_eq!(parsed, DexSource::PumpFun);
+    }
+
+    #[test]
+    fn filter_applies_deny_before_allow() {
+        let filter = DexSourceFilter::new()
+            .solana_only()
+            .deny([DexSource::PumpFun]);
+        assert!(filter.accepts(&DexSource::Raydium));
+        assert!(!filter.accepts(&DexSource::PumpFun));
+        assert!(!filter.accepts(&DexSource::UniswapV2));
+        assert!(!filter.accepts(&DexSource::Other("x".to_string())));
+
+        let open = DexSourceFilter::new().deny([DexSource::Curve]);
+        assert!(open.accepts(&DexSource::Other("x".to_string())));
+        assert!(!open.accepts(&DexSource::Curve));
+    }
+}
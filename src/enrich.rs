@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::{stream, StreamExt};
+use tokio::sync::{mpsc, OnceCell};
+
+use crate::large_trades::LargeTradeData;
+use crate::new_pair::NewPairData;
+use crate::rest::{BirdeyeRest, TokenOverviewData};
+use crate::token_listing::TokenListingData;
+use crate::trade::is_quote_token;
+use crate::txs::TransactionData;
+use crate::types::BirdeyeError;
+
+/// Options for `TokenOverviewCache`
+#[derive(Debug, Clone)]
+pub struct OverviewCacheOptions {
+    /// How long a fetched overview is served before it is refreshed
+    pub ttl: Duration,
+    /// How long a failed lookup is remembered before it is retried
+    pub failure_ttl: Duration,
+    /// Maximum number of tokens kept; least recently used are evicted
+    pub capacity: usize,
+}
+
+impl Default for OverviewCacheOptions {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            failure_ttl: Duration::from_secs(5),
+            capacity: 1000,
+        }
+    }
+}
+
+impl OverviewCacheOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_failure_ttl(mut self, ttl: Duration) -> Self {
+        self.failure_ttl = ttl;
+        self
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        self.capacity = capacity;
+        self
+    }
+}
+
+/// Outcome of a lookup; failures keep their message
+#[derive(Clone)]
+enum Lookup {
+    Found(Arc<TokenOverviewData>),
+    Failed(Arc<str>),
+}
+
+struct CacheEntry {
+    cell: Arc<OnceCell<Lookup>>,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    /// Addresses by last use, least recent first
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+/// TTL/LRU cache in front of `get_token_overview`
+///
+/// Concurrent lookups of the same token share a single REST request.
+/// Failures are cached for `failure_ttl` so a bad token is not refetched
+/// for every event.
+pub struct TokenOverviewCache {
+    rest: BirdeyeRest,
+    options: OverviewCacheOptions,
+    state: Mutex<CacheState>,
+}
+
+impl TokenOverviewCache {
+    pub fn new(rest: BirdeyeRest, options: OverviewCacheOptions) -> Self {
+        Self {
+            rest,
+            options,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Cached overview of `address`
+    ///
+    /// While a failed lookup is remembered, this returns
+    /// `BirdeyeError::CachedFailure`.
+    pub async fn get(&self, address: &str) -> Result<Arc<TokenOverviewData>, BirdeyeError> {
+        self.get_or_fetch(address, || async {
+            Ok(Arc::new(self.rest.get_token_overview(address).await?.data))
+        })
+        .await
+    }
+
+    async fn get_or_fetch<F, Fut>(&self, address: &str, fetch: F) -> Result<Arc<TokenOverviewData>, BirdeyeError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Arc<TokenOverviewData>, BirdeyeError>>,
+    {
+        let cell = self.cell_for(address);
+        // The caller that fetched gets the original error, others its message
+        let mut error = None;
+        let error_slot = &mut error;
+        let lookup = cell
+            .get_or_init(|| async move {
+                match fetch().await {
+                    Ok(overview) => Lookup::Found(overview),
+                    Err(e) => {
+                        let message = e.to_string();
+                        *error_slot = Some(e);
+                        Lookup::Failed(message.into())
+                    }
+                }
+            })
+            .await;
+        match (lookup, error) {
+            (_, Some(e)) => Err(e),
+            (Lookup::Found(overview), None) => Ok(overview.clone()),
+            (Lookup::Failed(message), None) => Err(BirdeyeError::CachedFailure(message.to_string())),
+        }
+    }
+
+    /// Drop the cached overview of `address`
+    pub fn invalidate(&self, address: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.entries.remove(address) {
+            state.recency.remove(&entry.last_used);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn cell_for(&self, address: &str) -> Arc<OnceCell<Lookup>> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let tick = state.clock;
+        state.clock += 1;
+
+        if let Some(entry) = state.entries.get_mut(address) {
+            // Pending fetches are always shared; finished ones until they expire
+            let ttl = match entry.cell.get() {
+                None => None,
+                Some(Lookup::Found(_)) => Some(self.options.ttl),
+                Some(Lookup::Failed(_)) => Some(self.options.failure_ttl),
+            };
+            if ttl.is_none_or(|ttl| entry.inserted_at.elapsed() < ttl) {
+                state.recency.remove(&entry.last_used);
+                state.recency.insert(tick, address.to_string());
+                entry.last_used = tick;
+                return entry.cell.clone();
+            }
+        }
+
+        if let Some(expired) = state.entries.remove(address) {
+            state.recency.remove(&expired.last_used);
+        } else if state.entries.len() >= self.options.capacity {
+            if let Some((_, oldest)) = state.recency.pop_first() {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        let cell = Arc::new(OnceCell::new());
+        state.recency.insert(tick, address.to_string());
+        state.entries.insert(
+            address.to_string(),
+            CacheEntry {
+                cell: cell.clone(),
+                inserted_at: Instant::now(),
+                last_used: tick,
+            },
+        );
+        cell
+    }
+}
+
+/// Events that reference a token worth enriching
+pub trait EnrichTarget {
+    fn token_address(&self) -> Option<&str>;
+}
+
+/// Pick the side of a swap or pair that is not a known quote token
+fn non_quote<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if is_quote_token(a) && !is_quote_token(b) {
+        b
+    } else {
+        a
+    }
+}
+
+impl EnrichTarget for TokenListingData {
+    fn token_address(&self) -> Option<&str> {
+        Some(&self.address)
+    }
+}
+
+impl EnrichTarget for NewPairData {
+    fn token_address(&self) -> Option<&str> {
+        Some(non_quote(&self.base.address, &self.quote.address))
+    }
+}
+
+impl EnrichTarget for TransactionData {
+    fn token_address(&self) -> Option<&str> {
+        Some(non_quote(&self.to.address, &self.from.address))
+    }
+}
+
+impl EnrichTarget for LargeTradeData {
+    fn token_address(&self) -> Option<&str> {
+        Some(non_quote(&self.to.address, &self.from.address))
+    }
+}
+
+/// An event together with the overview of the token it references
+#[derive(Debug)]
+pub struct Enriched<T> {
+    pub event: T,
+    /// `None` if the event has no token or the lookup failed
+    pub overview: Option<Arc<TokenOverviewData>>,
+}
+
+/// Default number of events `Enricher::run` looks up at once
+pub const DEFAULT_MAX_CONCURRENT_LOOKUPS: usize = 8;
+
+/// Opt-in layer attaching cached `TokenOverviewData` to streamed events
+#[derive(Clone)]
+pub struct Enricher {
+    cache: Arc<TokenOverviewCache>,
+    max_concurrent_lookups: usize,
+}
+
+impl Enricher {
+    pub fn new(rest: BirdeyeRest, options: OverviewCacheOptions) -> Self {
+        Self {
+            cache: Arc::new(TokenOverviewCache::new(rest, options)),
+            max_concurrent_lookups: DEFAULT_MAX_CONCURRENT_LOOKUPS,
+        }
+    }
+
+    /// Limit how many events `run` enriches at once
+    pub fn with_max_concurrent_lookups(mut self, count: usize) -> Self {
+        assert!(count > 0, "max_concurrent_lookups must be greater than 0");
+        self.max_concurrent_lookups = count;
+        self
+    }
+
+    pub fn cache(&self) -> &TokenOverviewCache {
+        &self.cache
+    }
+
+    pub async fn enrich<T: EnrichTarget>(&self, event: T) -> Enriched<T> {
+        let overview = match event.token_address() {
+            Some(address) => self.cache.get(address).await.ok(),
+            None => None,
+        };
+        Enriched { event, overview }
+    }
+
+    /// Enrich every event from `events` and forward it to `enriched`
+    ///
+    /// Up to `max_concurrent_lookups` events are looked up at once; they
+    /// are forwarded in the order they arrived.
+    pub async fn run<T: EnrichTarget>(&self, events: mpsc::Receiver<T>, enriched: mpsc::Sender<Enriched<T>>) {
+        let events = stream::unfold(events, |mut events| async {
+            events.recv().await.map(|event| (event, events))
+        });
+        let results = events
+            .map(|event| self.enrich(event))
+            .buffered(self.max_concurrent_lookups);
+        let mut results = std::pin::pin!(results);
+        while let Some(result) = results.next().await {
+            if enriched.send(result).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn overview(address: &str) -> Arc<TokenOverviewData> {
+        let data = serde_json::json!({
+            "address": address, "decimals": 6, "symbol": address, "name": address,
+            "extensions": {}, "logoURI": null, "liquidity": 0.0, "price": 1.0, "supply": 0.0,
+            "mc": 0.0, "v24hUSD": null, "priceChange24hPercent": null,
+            "lastTradeUnixTime": 0, "lastTradeHumanTime": "",
+        });
+        Arc::new(serde_json::from_value(data).unwrap())
+    }
+
+    fn cache(options: OverviewCacheOptions) -> TokenOverviewCache {
+        TokenOverviewCache::new(BirdeyeRest::new("key", "solana"), options)
+    }
+
+    /// Look up `address` through `cache`, counting fetches in `fetches`
+    async fn lookup(
+        cache: &TokenOverviewCache,
+        address: &str,
+        fetches: &AtomicUsize,
+    ) -> Result<Arc<TokenOverviewData>, BirdeyeError> {
+        cache
+            .get_or_fetch(address, || async {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                if address.starts_with("bad") {
+                    Err(BirdeyeError::Io(std::io::Error::other("unavailable")))
+                } else {
+                    Ok(overview(address))
+                }
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn hits_are_served_until_they_expire() {
+        let fetches = AtomicUsize::new(0);
+        let cache = cache(OverviewCacheOptions::new());
+        assert_eq!(lookup(&cache, "A", &fetches).await.unwrap().address, "A");
+        lookup(&cache, "A", &fetches).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let cache = self::cache(OverviewCacheOptions::new().with_ttl(Duration::ZERO));
+        lookup(&cache, "A", &fetches).await.unwrap();
+        lookup(&cache, "A", &fetches).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failures_are_cached_briefly() {
+        let fetches = AtomicUsize::new(0);
+        let cache = cache(OverviewCacheOptions::new());
+        assert!(matches!(lookup(&cache, "bad", &fetches).await, Err(BirdeyeError::Io(_))));
+        let cached = lookup(&cache, "bad", &fetches).await;
+        assert!(matches!(cached, Err(BirdeyeError::CachedFailure(message)) if message.contains("unavailable")));
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let cache = self::cache(OverviewCacheOptions::new().with_failure_ttl(Duration::ZERO));
+        lookup(&cache, "bad", &fetches).await.unwrap_err();
+        assert!(matches!(lookup(&cache, "bad", &fetches).await, Err(BirdeyeError::Io(_))));
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_is_evicted() {
+        let fetches = AtomicUsize::new(0);
+        let cache = cache(OverviewCacheOptions::new().with_capacity(2));
+        lookup(&cache, "A", &fetches).await.unwrap();
+        lookup(&cache, "B", &fetches).await.unwrap();
+        lookup(&cache, "A", &fetches).await.unwrap();
+        lookup(&cache, "C", &fetches).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+
+        lookup(&cache, "A", &fetches).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        lookup(&cache, "B", &fetches).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 4);
+
+        cache.invalidate("B");
+        assert_eq!(cache.len(), 1);
+    }
+
+    struct Event(Option<&'static str>);
+
+    impl EnrichTarget for Event {
+        fn token_address(&self) -> Option<&str> {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn run_keeps_event_order() {
+        let enricher = Enricher::new(BirdeyeRest::new("key", "solana"), OverviewCacheOptions::new())
+            .with_max_concurrent_lookups(2);
+        let fetches = AtomicUsize::new(0);
+        for address in ["A", "B"] {
+            lookup(enricher.cache(), address, &fetches).await.unwrap();
+        }
+
+        let (events, event_rx) = mpsc::channel(8);
+        let (enriched, mut enriched_rx) = mpsc::channel(8);
+        for event in [Some("B"), None, Some("A")] {
+            events.send(Event(event)).await.unwrap();
+        }
+        drop(events);
+        enricher.run(event_rx, enriched).await;
+
+        let mut seen = Vec::new();
+        while let Some(result) = enriched_rx.recv().await {
+            seen.push((result.event.0, result.overview.map(|o| o.address.clone())));
+        }
+        let expected = [(Some("B"), Some("B".to_string())), (None, None), (Some("A"), Some("A".to_string()))];
+        assert_eq!(seen, expected);
+    }
+}
@@ -0,0 +1,261 @@
+//! Streaming CSV and NDJSON writers for any `Serialize` payload
+//!
+//! CSV rows are flattened: nested objects become dotted columns such as
+//! `from.address` or `to.uiAmount`, arrays are written as JSON and `null`
+//! as an empty cell. Column names follow the payload's JSON field names.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::types::BirdeyeError;
+
+/// A sink for serialized records, used by `spawn_export`
+pub trait RecordWriter {
+    fn write_record<T: Serialize>(&mut self, record: &T) -> Result<(), BirdeyeError>;
+    fn flush(&mut self) -> Result<(), BirdeyeError>;
+
+    fn write_all<'a, T, I>(&mut self, records: I) -> Result<(), BirdeyeError>
+    where
+        T: Serialize + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        for record in records {
+            self.write_record(record)?;
+        }
+        Ok(())
+    }
+}
+
+/// One JSON document per line
+pub struct NdjsonWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> RecordWriter for NdjsonWriter<W> {
+    fn write_record<T: Serialize>(&mut self, record: &T) -> Result<(), BirdeyeError> {
+        serde_json::to_writer(&mut self.inner, record)?;
+        self.inner.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), BirdeyeError> {
+        Ok(self.inner.flush()?)
+    }
+}
+
+/// Header of the column collecting fields outside an inferred header
+pub const EXTRA_COLUMN: &str = "_extra";
+
+/// CSV with flattened columns
+///
+/// The header is taken from the first record unless set with
+/// `with_columns`, so every row has the same shape and missing fields are
+/// left empty. An inferred header ends with an `EXTRA_COLUMN` holding any
+/// later fields outside it as a JSON object, e.g. a nested object that was
+/// `null` in the first record; fields outside explicit columns are dropped.
+pub struct CsvWriter<W: Write> {
+    inner: csv::Writer<W>,
+    columns: Option<Vec<String>>,
+    /// Position of each named column in `columns`
+    index: HashMap<String, usize>,
+    /// Whether the header ends with `EXTRA_COLUMN`
+    overflow: bool,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: csv::Writer::from_writer(inner),
+            columns: None,
+            index: HashMap::new(),
+            overflow: false,
+            header_written: false,
+        }
+    }
+
+    /// Fix the columns, e.g. to select a subset or keep fields absent from
+    /// the first record in their own columns
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        assert!(!self.header_written, "columns must be set before the first record");
+        self.set_columns(columns.into_iter().map(Into::into).collect(), false);
+        self
+    }
+
+    pub fn columns(&self) -> Option<&[String]> {
+        self.columns.as_deref()
+    }
+
+    pub fn into_inner(self) -> Result<W, BirdeyeError> {
+        self.inner
+            .into_inner()
+            .map_err(|e| BirdeyeError::Io(e.into_error()))
+    }
+
+    fn set_columns(&mut self, mut columns: Vec<String>, overflow: bool) {
+        self.index = columns.iter().enumerate().map(|(i, column)| (column.clone(), i)).collect();
+        if overflow {
+            columns.push(EXTRA_COLUMN.to_string());
+        }
+        self.columns = Some(columns);
+        self.overflow = overflow;
+    }
+}
+
+impl<W: Write> RecordWriter for CsvWriter<W> {
+    fn write_record<T: Serialize>(&mut self, record: &T) -> Result<(), BirdeyeError> {
+        let mut cells = Vec::new();
+        flatten("", serde_json::to_value(record)?, &mut cells);
+
+        if self.columns.is_none() {
+            self.set_columns(cells.iter().map(|(column, _)| column.clone()).collect(), true);
+        }
+        let columns = self.columns.as_deref().unwrap_or_default();
+        if !self.header_written {
+            self.inner.write_record(columns)?;
+            self.header_written = true;
+        }
+
+        let mut row = vec![String::new(); columns.len()];
+        let mut extra = serde_json::Map::new();
+        for (column, cell) in cells {
+            match self.index.get(&column) {
+                Some(&i) => row[i] = cell,
+                None => {
+                    extra.insert(column, Value::String(cell));
+                }
+            }
+        }
+        if self.overflow && !extra.is_empty() {
+            if let Some(last) = row.last_mut() {
+                *last = Value::Object(extra).to_string();
+            }
+        }
+        self.inner.write_record(&row)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), BirdeyeError> {
+        Ok(self.inner.flush()?)
+    }
+}
+
+/// Flatten `value` into `(column, cell)` pairs in field order
+pub fn flatten(prefix: &str, value: Value, cells: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let column = if prefix.is_empty() { key } else { format!("{prefix}.{key}") };
+                flatten(&column, value, cells);
+            }
+        }
+        Value::Null => cells.push((prefix.to_string(), String::new())),
+        Value::String(s) => cells.push((prefix.to_string(), s)),
+        other => cells.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+/// Write every record received on `records` until the channel closes
+///
+/// Runs on the blocking thread pool and flushes whenever the channel is
+/// drained, so a slow trickle of live events still reaches disk promptly.
+/// Returns the writer after a final flush.
+pub fn spawn_export<T, W>(mut writer: W, mut records: mpsc::Receiver<T>) -> JoinHandle<Result<W, BirdeyeError>>
+where
+    T: Serialize + Send + 'static,
+    W: RecordWriter + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        while let Some(record) = records.blocking_recv() {
+            writer.write_record(&record)?;
+            if records.is_empty() {
+                writer.flush()?;
+            }
+        }
+        writer.flush()?;
+        Ok(writer)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn csv_output(writer: CsvWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn flattens_nested_objects_into_dotted_columns() {
+        let mut cells = Vec::new();
+        flatten(
+            "",
+            json!({ "tx": "a", "from": { "symbol": "SOL", "uiAmount": 1.5 }, "tags": [1, 2], "alias": null }),
+            &mut cells,
+        );
+        let cells: Vec<(&str, &str)> = cells.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(
+            cells,
+            [("tx", "a"), ("from.symbol", "SOL"), ("from.uiAmount", "1.5"), ("tags", "[1,2]"), ("alias", "")]
+        );
+    }
+
+    #[test]
+    fn csv_keeps_fields_missing_from_the_first_record() {
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.write_record(&json!({ "tx": "a", "pool": null })).unwrap();
+        writer
+            .write_record(&json!({ "tx": "b", "pool": { "address": "P" }, "fee": 0.5 }))
+            .unwrap();
+        writer.write_record(&json!({ "pool": "Q" })).unwrap();
+
+        assert_eq!(writer.columns().unwrap(), ["tx", "pool", EXTRA_COLUMN]);
+        assert_eq!(
+            csv_output(writer),
+            "tx,pool,_extra\na,,\nb,,\"{\"\"pool.address\"\":\"\"P\"\",\"\"fee\"\":\"\"0.5\"\"}\"\n,Q,\n"
+        );
+    }
+
+    #[test]
+    fn explicit_columns_select_fields() {
+        let mut writer = CsvWriter::new(Vec::new()).with_columns(["fee", "tx"]);
+        writer.write_record(&json!({ "tx": "a", "owner": "o" })).unwrap();
+        writer.write_record(&json!({ "tx": "b", "fee": 1 })).unwrap();
+        assert_eq!(csv_output(writer), "fee,tx\n,a\n1,b\n");
+    }
+
+    #[test]
+    fn ndjson_writes_one_document_per_line() {
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write_all(&[json!({ "a": 1 }), json!({ "b": [true] })]).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "{\"a\":1}\n{\"b\":[true]}\n");
+    }
+
+    #[tokio::test]
+    async fn spawn_export_writes_every_record_before_returning() {
+        let (sender, receiver) = mpsc::channel(4);
+        let handle = spawn_export(CsvWriter::new(Vec::new()), receiver);
+        for tx in ["a", "b", "c"] {
+            sender.send(json!({ "tx": tx })).await.unwrap();
+        }
+        drop(sender);
+        let writer = handle.await.unwrap().unwrap();
+        assert_eq!(csv_output(writer), "tx,_extra\na,\nb,\nc,\n");
+    }
+}
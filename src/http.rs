@@ -0,0 +1,73 @@
+//! Minimal HTTP/1.1 helpers for the proxy and the metrics endpoint
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::types::BirdeyeError;
+
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// Read an HTTP request head, up to the blank line ending it
+pub(crate) async fn read_request_head(stream: &mut TcpStream) -> Result<String, BirdeyeError> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..read]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Write a complete `text/plain` response; the connection is not reused
+pub(crate) async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), BirdeyeError> {
+    respond_with(stream, status, "text/plain", body).await
+}
+
+pub(crate) async fn respond_with(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<(), BirdeyeError> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn reads_the_head_across_writes_and_responds() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\nHo").await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            stream.write_all(b"st: x\r\n\r\n").await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let head = read_request_head(&mut stream).await.unwrap();
+        assert_eq!(head, "GET / HTTP/1.1\r\nHost: x\r\n\r\n");
+        respond_with(&mut stream, "200 OK", "text/csv", "a,b").await.unwrap();
+        drop(stream);
+
+        let response = client.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/csv\r\nContent-Length: 3\r\n"));
+        assert!(response.ends_with("\r\n\r\na,b"));
+    }
+}
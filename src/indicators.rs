@@ -0,0 +1,572 @@
+//! Incremental technical indicators over `Candle`s
+//!
+//! Every indicator consumes candles one at a time. Wrap it in `Streaming`
+//! to warm it from `get_ohlcv` history and then feed it live `PriceData`:
+//! updates for the candle that is still open are recomputed from the last
+//! closed state instead of being counted twice.
+//!
+//! ```
+//! use birdeye_api_rs::indicators::{Ema, Indicator, Rsi, Sma};
+//! use birdeye_api_rs::price::Candle;
+//!
+//! let closes = [1.0, 2.0, 3.0, 4.0, 5.0];
+//! let candle = |i: usize| Candle {
+//!     address: "token".into(),
+//!     symbol: None,
+//!     unix_time: i as i64 * 60,
+//!     open: closes[i],
+//!     high: closes[i],
+//!     low: closes[i],
+//!     close: closes[i],
+//!     volume: 1.0,
+//! };
+//!
+//! let (mut sma, mut ema, mut rsi) = (Sma::new(3), Ema::new(3), Rsi::new(3));
+//! let mut last = (None, None, None);
+//! for i in 0..closes.len() {
+//!     last = (sma.next(&candle(i)), ema.next(&candle(i)), rsi.next(&candle(i)));
+//! }
+//! assert_eq!(last.0, Some(4.0));
+//! // EMA(3) is seeded with SMA(1, 2, 3) = 2, then 3 and 4 with alpha 0.5
+//! assert_eq!(last.1, Some(4.0));
+//! // Only gains, so RSI saturates
+//! assert_eq!(last.2, Some(100.0));
+//! ```
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::price::{Candle, PriceData};
+use crate::rest::OHLCVData;
+
+/// An indicator updated with one closed candle at a time
+pub trait Indicator: Clone {
+    type Output: Copy;
+
+    /// Feed the next candle; `None` until enough data has been seen
+    fn next(&mut self, candle: &Candle) -> Option<Self::Output>;
+}
+
+/// Drives an `Indicator` from history and live, possibly repeated, candles
+#[derive(Debug, Clone)]
+pub struct Streaming<I: Indicator> {
+    committed: I,
+    working: Option<(i64, I)>,
+    value: Option<I::Output>,
+}
+
+impl<I: Indicator> Streaming<I> {
+    pub fn new(indicator: I) -> Self {
+        Self {
+            committed: indicator,
+            working: None,
+            value: None,
+        }
+    }
+
+    /// Feed historical candles, oldest first
+    pub fn warm(&mut self, history: &[OHLCVData]) -> Option<I::Output> {
+        for ohlcv in history {
+            self.update(&Candle::from(ohlcv));
+        }
+        self.value
+    }
+
+    pub fn on_price(&mut self, price: &PriceData) -> Option<I::Output> {
+        self.update(&Candle::from(price))
+    }
+
+    /// Apply a candle; a candle with the same `unix_time` as the previous
+    /// one replaces it, an older one is ignored
+    pub fn update(&mut self, candle: &Candle) -> Option<I::Output> {
+        match self.working.take() {
+            Some((time, working)) if candle.unix_time > time => self.committed = working,
+            Some((time, working)) if candle.unix_time < time => {
+                self.working = Some((time, working));
+                return self.value;
+            }
+            _ => {}
+        }
+        let mut working = self.committed.clone();
+        self.value = working.next(candle);
+        self.working = Some((candle.unix_time, working));
+        self.value
+    }
+
+    /// Value including the currently open candle
+    pub fn value(&self) -> Option<I::Output> {
+        self.value
+    }
+}
+
+/// Simple moving average of closes
+///
+/// The window is summed afresh on every value rather than kept as a
+/// running sum, which would accumulate rounding error over a long stream.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be greater than 0");
+        Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+        }
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        (self.window.len() == self.period).then(|| self.window.iter().sum::<f64>() / self.period as f64)
+    }
+}
+
+impl Indicator for Sma {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        self.push(candle.close)
+    }
+}
+
+/// Exponential moving average of closes, seeded with the SMA of the first
+/// `period` values
+#[derive(Debug, Clone)]
+pub struct Ema {
+    alpha: f64,
+    seed: Sma,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self {
+            alpha: 2.0 / (period as f64 + 1.0),
+            seed: Sma::new(period),
+            value: None,
+        }
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + self.alpha * (value - previous)),
+            None => self.seed.push(value),
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        self.push(candle.close)
+    }
+}
+
+/// Relative strength index with Wilder smoothing
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous_close: Option<f64>,
+    seen: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be greater than 0");
+        Self {
+            period,
+            previous_close: None,
+            seen: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let previous = self.previous_close.replace(candle.close)?;
+        let change = candle.close - previous;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let period = self.period as f64;
+
+        self.seen += 1;
+        if self.seen <= self.period {
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+            if self.seen < self.period {
+                return None;
+            }
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+
+        Some(if self.avg_loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MacdOutput {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Moving average convergence divergence
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        assert!(fast < slow, "fast period must be shorter than slow period");
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+}
+
+impl Default for Macd {
+    /// The conventional 12 / 26 / 9 configuration
+    fn default() -> Self {
+        Self::new(12, 26, 9)
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<MacdOutput> {
+        let fast = self.fast.push(candle.close);
+        let slow = self.slow.push(candle.close)?;
+        let macd = fast? - slow;
+        let signal = self.signal.push(macd)?;
+        Some(MacdOutput {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BollingerOutput {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+/// Bollinger bands: SMA +/- `k` population standard deviations
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    sma: Sma,
+    k: f64,
+}
+
+impl BollingerBands {
+    pub fn new(period: usize, k: f64) -> Self {
+        Self { sma: Sma::new(period), k }
+    }
+}
+
+impl Default for BollingerBands {
+    /// 20 periods, 2 standard deviations
+    fn default() -> Self {
+        Self::new(20, 2.0)
+    }
+}
+
+impl Indicator for BollingerBands {
+    type Output = BollingerOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<BollingerOutput> {
+        let middle = self.sma.push(candle.close)?;
+        let window = &self.sma.window;
+        let variance = window.iter().map(|v| (v - middle).powi(2)).sum::<f64>() / window.len() as f64;
+        let width = self.k * variance.sqrt();
+        Some(BollingerOutput {
+            upper: middle + width,
+            middle,
+            lower: middle - width,
+        })
+    }
+}
+
+/// Average true range with Wilder smoothing
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f64>,
+    seen: usize,
+    value: f64,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "period must be greater than 0");
+        Self {
+            period,
+            previous_close: None,
+            seen: 0,
+            value: 0.0,
+        }
+    }
+}
+
+impl Indicator for Atr {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let range = candle.high - candle.low;
+        let true_range = match self.previous_close.replace(candle.close) {
+            Some(previous) => range
+                .max((candle.high - previous).abs())
+                .max((candle.low - previous).abs()),
+            None => range,
+        };
+        let period = self.period as f64;
+
+        self.seen += 1;
+        if self.seen <= self.period {
+            self.value += true_range / period;
+            (self.seen == self.period).then_some(self.value)
+        } else {
+            self.value = (self.value * (period - 1.0) + true_range) / period;
+            Some(self.value)
+        }
+    }
+}
+
+/// Volume-weighted average of the typical price `(h + l + c) / 3`
+///
+/// Cumulative by default; `with_session` resets it at fixed boundaries
+/// (e.g. daily at 00:00 UTC).
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    session_secs: Option<i64>,
+    session: Option<i64>,
+    price_volume: f64,
+    volume: f64,
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_session(mut self, length: Duration) -> Self {
+        assert!(length.as_secs() > 0, "session length must be at least one second");
+        self.session_secs = Some(length.as_secs() as i64);
+        self
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        if let Some(length) = self.session_secs {
+            let session = candle.unix_time.div_euclid(length);
+            if self.session != Some(session) {
+                self.session = Some(session);
+                self.price_volume = 0.0;
+                self.volume = 0.0;
+            }
+        }
+        let typical = (candle.high + candle.low + candle.close) / 3.0;
+        self.price_volume += typical * candle.volume;
+        self.volume += candle.volume;
+        (self.volume > 0.0).then(|| self.price_volume / self.volume)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! SMA, EMA and RSI are checked against the worked examples of the
+    //! StockCharts ChartSchool articles, whose published values are rounded
+    //! to two decimals (RSI by a bit more, as the article rounds its
+    //! averages). MACD, Bollinger bands, ATR and VWAP are checked against
+    //! values computed independently from their textbook definitions.
+
+    use super::*;
+
+    /// Closes of the StockCharts SMA/EMA example
+    const CLOSES: [f64; 30] = [
+        22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38, 22.61, 23.36,
+        24.05, 23.75, 23.83, 23.95, 23.63, 23.82, 23.87, 23.65, 23.19, 23.10, 23.33, 22.68, 23.10, 22.40, 22.17,
+    ];
+
+    /// Closes of the StockCharts RSI example
+    const RSI_CLOSES: [f64; 33] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61, 46.28, 46.28,
+        46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45, 45.78, 45.35, 44.03, 44.18, 44.22, 44.57,
+        43.42, 42.66, 43.13,
+    ];
+
+    fn candle(index: usize, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle {
+            address: "T".to_string(),
+            symbol: None,
+            unix_time: index as i64 * 60,
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+        }
+    }
+
+    /// Outputs of `indicator` over `closes`, skipping the warm-up
+    fn run<I: Indicator>(mut indicator: I, closes: &[f64]) -> Vec<I::Output> {
+        closes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &close)| indicator.next(&candle(i, close, close, close, 1.0)))
+            .collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+            assert!((a - e).abs() <= tolerance, "value {i}: {a} != {e}");
+        }
+    }
+
+    #[test]
+    fn sma_matches_reference() {
+        let expected = [
+            22.22, 22.21, 22.23, 22.26, 22.30, 22.42, 22.61, 22.77, 22.91, 23.08, 23.21, 23.38, 23.52, 23.65, 23.71,
+            23.68, 23.61, 23.51, 23.43, 23.28, 23.13,
+        ];
+        assert_close(&run(Sma::new(10), &CLOSES), &expected, 0.0051);
+    }
+
+    #[test]
+    fn sma_does_not_drift() {
+        let mut sma = Sma::new(3);
+        for i in 0..100_000 {
+            sma.push(if i % 2 == 0 { 1e12 } else { 0.1 });
+        }
+        for _ in 0..3 {
+            sma.push(0.1);
+        }
+        // A running sum would be left off by the rounding of the 1e12 values
+        let value = sma.push(0.1).unwrap();
+        assert!((value - 0.1).abs() < 1e-12, "{value}");
+    }
+
+    #[test]
+    fn ema_matches_reference() {
+        let expected = [
+            22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28, 23.34, 23.43, 23.51, 23.54, 23.47,
+            23.40, 23.39, 23.26, 23.23, 23.08, 22.92,
+        ];
+        assert_close(&run(Ema::new(10), &CLOSES), &expected, 0.0101);
+    }
+
+    #[test]
+    fn rsi_matches_reference() {
+        let expected = [
+            70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42, 39.99, 41.46, 41.87,
+            45.46, 37.30, 33.08, 37.77,
+        ];
+        assert_close(&run(Rsi::new(14), &RSI_CLOSES), &expected, 0.1);
+    }
+
+    #[test]
+    fn macd_matches_reference() {
+        let outputs = run(Macd::new(3, 6, 4), &CLOSES);
+        // Slow EMA ready at the 6th close, signal after 4 MACD values
+        assert_eq!(outputs.len(), CLOSES.len() - 5 - 3);
+        let macd: Vec<f64> = outputs.iter().map(|o| o.macd).collect();
+        let signal: Vec<f64> = outputs.iter().map(|o| o.signal).collect();
+        assert_close(&macd[..3], &[0.023661, 0.020015, -0.014147], 1e-6);
+        assert_close(&signal[..3], &[0.016638, 0.017988, 0.005134], 1e-6);
+        let last = outputs.last().unwrap();
+        assert_close(&[last.macd, last.signal], &[-0.278298, -0.207110], 1e-6);
+        assert!((last.histogram - (last.macd - last.signal)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bollinger_matches_reference() {
+        let outputs = run(BollingerBands::new(10, 2.0), &CLOSES);
+        let bands = |o: &BollingerOutput| [o.upper, o.middle, o.lower];
+        assert_close(&bands(&outputs[0]), &[22.405054, 22.221, 22.036946], 1e-6);
+        assert_close(&bands(outputs.last().unwrap()), &[24.225804, 23.131, 22.036196], 1e-6);
+    }
+
+    #[test]
+    fn atr_matches_reference() {
+        let bars = [
+            (48.70, 47.79, 48.16),
+            (48.72, 48.14, 48.61),
+            (48.90, 48.39, 48.75),
+            (48.87, 48.37, 48.63),
+            (48.82, 48.24, 48.74),
+            (49.05, 48.64, 49.03),
+            (49.20, 48.94, 49.07),
+            (49.35, 48.86, 49.32),
+            (49.92, 49.50, 49.91),
+            (50.19, 49.87, 50.13),
+        ];
+        let mut atr = Atr::new(5);
+        let outputs: Vec<f64> = bars
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &(high, low, close))| atr.next(&candle(i, high, low, close, 1.0)))
+            .collect();
+        let expected = [0.616, 0.5748, 0.51184, 0.507472, 0.525978, 0.484782];
+        assert_close(&outputs, &expected, 1e-6);
+    }
+
+    #[test]
+    fn vwap_weights_typical_price_by_volume() {
+        let mut vwap = Vwap::new();
+        assert_eq!(vwap.next(&candle(0, 10.0, 8.0, 9.0, 100.0)), Some(9.0));
+        assert_eq!(vwap.next(&candle(1, 11.0, 9.0, 10.5, 300.0)), Some(9.875));
+        assert_eq!(vwap.next(&candle(2, 12.0, 10.0, 11.0, 0.0)), Some(9.875));
+
+        let mut vwap = Vwap::new().with_session(Duration::from_secs(120));
+        vwap.next(&candle(0, 10.0, 8.0, 9.0, 100.0));
+        vwap.next(&candle(1, 11.0, 9.0, 10.5, 300.0));
+        // Third candle starts a new session
+        assert_eq!(vwap.next(&candle(2, 12.0, 10.0, 11.0, 5.0)), Some(11.0));
+        assert_eq!(Vwap::new().next(&candle(0, 1.0, 1.0, 1.0, 0.0)), None);
+    }
+
+    #[test]
+    fn streaming_recomputes_the_open_candle() {
+        let mut streaming = Streaming::new(Sma::new(2));
+        streaming.update(&candle(0, 1.0, 1.0, 1.0, 1.0));
+        assert_eq!(streaming.update(&candle(1, 3.0, 3.0, 3.0, 1.0)), Some(2.0));
+        // Update of the open candle replaces it
+        assert_eq!(streaming.update(&candle(1, 5.0, 5.0, 5.0, 1.0)), Some(3.0));
+        // Older candles are ignored
+        assert_eq!(streaming.update(&candle(0, 9.0, 9.0, 9.0, 1.0)), Some(3.0));
+        assert_eq!(streaming.update(&candle(2, 7.0, 7.0, 7.0, 1.0)), Some(6.0));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TradeTokenInfo {
     pub symbol: String,
     pub decimals: u8,
@@ -15,7 +15,7 @@ pub struct TradeTokenInfo {
     pub ui_change_amount: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LargeTradeData {
     #[serde(rename = "blockUnixTime")]
     pub block_unix_time: i64,
@@ -0,0 +1,356 @@
+//! End-to-end latency of streamed events
+//!
+//! Attach a `LatencyTracker` with `BirdeyeWebSocket::with_latency` to keep
+//! percentiles of the time between an event's `unixTime`/`blockUnixTime`
+//! and its receipt, per `ResponseType`. Birdeye timestamps are whole
+//! seconds, so single measurements are only accurate to about a second.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::types::{ResponseType, WebSocketResponse};
+
+/// Unusual latency noticed while observing events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LatencyAnomaly {
+    /// An event is timestamped further ahead of the local clock than the
+    /// tolerance allows, so one of the clocks is off
+    ClockSkew { response_type: ResponseType, latency_ms: i64 },
+    /// Many stale events arrived within one second, typically the server
+    /// flushing a backlog
+    BacklogBurst {
+        response_type: ResponseType,
+        count: usize,
+        max_latency_ms: i64,
+    },
+}
+
+type AnomalyCallback = Arc<dyn Fn(&LatencyAnomaly) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct LatencyOptions {
+    /// Most recent samples kept per response type
+    pub window: usize,
+    /// How far in the future an event may be timestamped before it counts
+    /// as clock skew
+    pub clock_skew_tolerance: Duration,
+    /// Latency from which an event counts towards a backlog burst
+    pub backlog_latency: Duration,
+    /// Stale events within one second that make a backlog burst
+    pub burst_size: usize,
+    on_anomaly: Option<AnomalyCallback>,
+}
+
+impl fmt::Debug for LatencyOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyOptions")
+            .field("window", &self.window)
+            .field("clock_skew_tolerance", &self.clock_skew_tolerance)
+            .field("backlog_latency", &self.backlog_latency)
+            .field("burst_size", &self.burst_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for LatencyOptions {
+    fn default() -> Self {
+        Self {
+            window: 1000,
+            clock_skew_tolerance: Duration::from_secs(2),
+            backlog_latency: Duration::from_secs(10),
+            burst_size: 20,
+            on_anomaly: None,
+        }
+    }
+}
+
+impl LatencyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        assert!(window > 0, "window must be greater than 0");
+        self.window = window;
+        self
+    }
+
+    pub fn with_clock_skew_tolerance(mut self, tolerance: Duration) -> Self {
+        self.clock_skew_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_backlog_latency(mut self, latency: Duration) -> Self {
+        assert!(!latency.is_zero(), "latency must be greater than 0");
+        self.backlog_latency = latency;
+        self
+    }
+
+    pub fn with_burst_size(mut self, burst_size: usize) -> Self {
+        assert!(burst_size > 0, "burst_size must be greater than 0");
+        self.burst_size = burst_size;
+        self
+    }
+
+    /// Called for every anomaly, besides the warning logged for it
+    pub fn on_anomaly(mut self, callback: impl Fn(&LatencyAnomaly) + Send + Sync + 'static) -> Self {
+        self.on_anomaly = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Latency distribution of one response type, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencyPercentiles {
+    pub samples: usize,
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+}
+
+impl LatencyPercentiles {
+    fn from_samples(samples: &VecDeque<i64>) -> Option<Self> {
+        let mut sorted: Vec<i64> = samples.iter().copied().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+        // Nearest-rank percentile
+        let rank = |p: f64| sorted[((p * sorted.len() as f64).ceil() as usize).max(1) - 1];
+        Some(Self {
+            samples: sorted.len(),
+            p50_ms: rank(0.5),
+            p90_ms: rank(0.9),
+            p99_ms: rank(0.99),
+            max_ms: rank(1.0),
+        })
+    }
+}
+
+#[derive(Default)]
+struct TypeLatency {
+    samples: VecDeque<i64>,
+    /// Receive second of the current burst candidate
+    burst_second: i64,
+    burst_count: usize,
+    burst_max_ms: i64,
+    burst_flagged: bool,
+    /// Receive second of the last clock skew report, to report at most
+    /// one per second
+    skew_second: Option<i64>,
+}
+
+/// Rolling latency percentiles per `ResponseType`; clones share them
+#[derive(Clone)]
+pub struct LatencyTracker {
+    options: Arc<LatencyOptions>,
+    state: Arc<Mutex<HashMap<ResponseType, TypeLatency>>>,
+}
+
+impl fmt::Debug for LatencyTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyTracker")
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(LatencyOptions::default())
+    }
+}
+
+impl LatencyTracker {
+    pub fn new(options: LatencyOptions) -> Self {
+        Self {
+            options: Arc::new(options),
+            state: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Record the latency of `response`
+    ///
+    /// Responses without a receive time or event timestamp are ignored.
+    pub fn observe(&self, response: &WebSocketResponse) -> Option<LatencyAnomaly> {
+        let (Some(received_at), Some(latency_ms)) = (response.received_at, response.latency_ms()) else {
+            return None;
+        };
+        let response_type = response.response_type;
+        let second = received_at.div_euclid(1000);
+        let anomaly = {
+            let mut state = self.state.lock().unwrap();
+            let entry = state.entry(response_type).or_default();
+            if entry.samples.len() == self.options.window {
+                entry.samples.pop_front();
+            }
+            entry.samples.push_back(latency_ms);
+            self.detect(entry, response_type, second, latency_ms)
+        };
+
+        // Outside the lock so the callback may query the tracker
+        if let Some(anomaly) = &anomaly {
+            tracing::warn!(?anomaly, "stream latency anomaly");
+            if let Some(callback) = &self.options.on_anomaly {
+                callback(anomaly);
+            }
+        }
+        anomaly
+    }
+
+    fn detect(&self, entry: &mut TypeLatency, response_type: ResponseType, second: i64, latency_ms: i64) -> Option<LatencyAnomaly> {
+        if latency_ms < -(self.options.clock_skew_tolerance.as_millis() as i64) {
+            if entry.skew_second == Some(second) {
+                return None;
+            }
+            entry.skew_second = Some(second);
+            return Some(LatencyAnomaly::ClockSkew { response_type, latency_ms });
+        }
+
+        if latency_ms < self.options.backlog_latency.as_millis() as i64 {
+            return None;
+        }
+        if entry.burst_second != second {
+            entry.burst_second = second;
+            entry.burst_count = 0;
+            entry.burst_max_ms = 0;
+            entry.burst_flagged = false;
+        }
+        entry.burst_count += 1;
+        entry.burst_max_ms = entry.burst_max_ms.max(latency_ms);
+        if entry.burst_flagged || entry.burst_count < self.options.burst_size {
+            return None;
+        }
+        entry.burst_flagged = true;
+        Some(LatencyAnomaly::BacklogBurst {
+            response_type,
+            count: entry.burst_count,
+            max_latency_ms: entry.burst_max_ms,
+        })
+    }
+
+    /// Percentiles of the recent events of `response_type`
+    pub fn percentiles(&self, response_type: ResponseType) -> Option<LatencyPercentiles> {
+        let state = self.state.lock().unwrap();
+        LatencyPercentiles::from_samples(&state.get(&response_type)?.samples)
+    }
+
+    /// Percentiles of every response type seen so far
+    pub fn snapshot(&self) -> HashMap<ResponseType, LatencyPercentiles> {
+        let state = self.state.lock().unwrap();
+        state
+            .iter()
+            .filter_map(|(kind, entry)| Some((*kind, LatencyPercentiles::from_samples(&entry.samples)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use serde_json::json;
+
+    /// A price event stamped `event_secs`, received at `received_ms`
+    fn event(event_secs: i64, received_ms: i64) -> WebSocketResponse {
+        WebSocketResponse {
+            response_type: ResponseType::PriceData,
+            data: json!({ "unixTime": event_secs }),
+            received_at: Some(received_ms),
+        }
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let tracker = LatencyTracker::default();
+        // Latencies 100, 200, ..., 1000 ms, observed out of order
+        for latency in [700, 100, 1000, 300, 500, 200, 900, 400, 800, 600] {
+            tracker.observe(&event(1_000, 1_000_000 + latency));
+        }
+        let p = tracker.percentiles(ResponseType::PriceData).unwrap();
+        assert_eq!(
+            p,
+            LatencyPercentiles { samples: 10, p50_ms: 500, p90_ms: 900, p99_ms: 1000, max_ms: 1000 }
+        );
+        assert_eq!(tracker.percentiles(ResponseType::TxsData), None);
+        assert_eq!(tracker.snapshot().len(), 1);
+
+        let single = LatencyPercentiles::from_samples(&VecDeque::from([42])).unwrap();
+        assert_eq!((single.p50_ms, single.p99_ms), (42, 42));
+    }
+
+    #[test]
+    fn window_keeps_the_most_recent_samples() {
+        let tracker = LatencyTracker::new(LatencyOptions::new().with_window(3));
+        for latency in [5_000, 100, 200, 300] {
+            tracker.observe(&event(1_000, 1_000_000 + latency));
+        }
+        let p = tracker.percentiles(ResponseType::PriceData).unwrap();
+        assert_eq!((p.samples, p.max_ms), (3, 300));
+    }
+
+    #[test]
+    fn events_without_timestamps_are_ignored() {
+        let tracker = LatencyTracker::default();
+        let mut unstamped = event(1_000, 1_000_000);
+        unstamped.received_at = None;
+        assert_eq!(tracker.observe(&unstamped), None);
+        let untimed = WebSocketResponse { data: json!({}), ..event(1_000, 1_000_000) };
+        assert_eq!(tracker.observe(&untimed), None);
+        assert!(tracker.snapshot().is_empty());
+    }
+
+    #[test]
+    fn clock_skew_is_reported_once_per_second() {
+        let reported = Arc::new(AtomicUsize::new(0));
+        let counted = reported.clone();
+        let tracker = LatencyTracker::new(LatencyOptions::new().on_anomaly(move |_| {
+            counted.fetch_add(1, Ordering::SeqCst);
+        }));
+        // Event stamped 5 s ahead of receipt; tolerance is 2 s
+        let skewed = |received_ms| tracker.observe(&event(1_005, received_ms));
+        assert_eq!(
+            skewed(1_000_000),
+            Some(LatencyAnomaly::ClockSkew { response_type: ResponseType::PriceData, latency_ms: -5_000 })
+        );
+        assert_eq!(skewed(1_000_900), None);
+        assert!(matches!(skewed(1_001_000), Some(LatencyAnomaly::ClockSkew { .. })));
+        // Within the tolerance
+        assert_eq!(tracker.observe(&event(1_002, 1_000_500)), None);
+        assert_eq!(reported.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn backlog_burst_fires_once_at_burst_size() {
+        let tracker = LatencyTracker::new(
+            LatencyOptions::new()
+                .with_backlog_latency(Duration::from_secs(10))
+                .with_burst_size(3),
+        );
+        // Stale events, all received within second 2_000
+        let stale = |event_secs, received_ms| tracker.observe(&event(event_secs, received_ms));
+        assert_eq!(stale(1_980, 2_000_000), None);
+        assert_eq!(tracker.observe(&event(1_999, 2_000_100)), None, "fresh events do not count");
+        assert_eq!(stale(1_970, 2_000_200), None);
+        assert_eq!(
+            stale(1_985, 2_000_300),
+            Some(LatencyAnomaly::BacklogBurst {
+                response_type: ResponseType::PriceData,
+                count: 3,
+                max_latency_ms: 30_200,
+            })
+        );
+        assert_eq!(stale(1_960, 2_000_400), None);
+
+        // The count restarts in the next second
+        assert_eq!(stale(1_980, 2_001_000), None);
+        assert_eq!(stale(1_980, 2_001_100), None);
+        assert!(matches!(stale(1_980, 2_001_200), Some(LatencyAnomaly::BacklogBurst { count: 3, .. })));
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+
+use regex::Regex;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::dex::{DexSource, DexSourceFilter};
+use crate::new_pair::NewPairData;
+use crate::rest::{BirdeyeRest, TokenSecurityData};
+use crate::token_listing::TokenListingData;
+use crate::trade::{is_quote_token, same_address};
+use crate::types::unix_now;
+
+/// Default number of candidates `run` evaluates at once
+pub const DEFAULT_MAX_CONCURRENT_CHECKS: usize = 8;
+
+/// Raw launch event a candidate was built from
+#[derive(Debug)]
+pub enum LaunchEvent {
+    NewPair(NewPairData),
+    Listing(TokenListingData),
+}
+
+/// A newly launched token, normalized from `NEW_PAIR` or `TOKEN_NEW_LISTING`
+#[derive(Debug)]
+pub struct LaunchCandidate {
+    pub token_address: String,
+    pub pair_address: Option<String>,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub quote_address: Option<String>,
+    pub source: Option<DexSource>,
+    pub liquidity: Option<f64>,
+    /// Unix time the pair was created or liquidity was added
+    pub created_at: i64,
+    pub event: LaunchEvent,
+}
+
+impl From<NewPairData> for LaunchCandidate {
+    fn from(pair: NewPairData) -> Self {
+        // The launched token is whichever side is not a known quote token
+        let (token, quote) = if is_quote_token(&pair.base.address) && !is_quote_token(&pair.quote.address) {
+            (&pair.quote, &pair.base)
+        } else {
+            (&pair.base, &pair.quote)
+        };
+        Self {
+            token_address: token.address.clone(),
+            pair_address: Some(pair.address.clone()),
+            name: token.name.clone(),
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+            quote_address: Some(quote.address.clone()),
+            source: Some(DexSource::parse(&pair.source)),
+            liquidity: None,
+            created_at: pair.block_time,
+            event: LaunchEvent::NewPair(pair),
+        }
+    }
+}
+
+impl From<TokenListingData> for LaunchCandidate {
+    fn from(listing: TokenListingData) -> Self {
+        Self {
+            token_address: listing.address.clone(),
+            pair_address: None,
+            name: listing.name.clone(),
+            symbol: listing.symbol.clone(),
+            decimals: listing.decimals,
+            quote_address: None,
+            source: None,
+            liquidity: listing.liquidity.parse().ok(),
+            created_at: listing.liquidity_added_at,
+            event: LaunchEvent::Listing(listing),
+        }
+    }
+}
+
+impl LaunchCandidate {
+    /// Seconds since creation according to the local clock
+    pub fn age_secs(&self) -> i64 {
+        unix_now() - self.created_at
+    }
+}
+
+/// A synchronous rule applied to every candidate
+pub trait LaunchFilter: Send + Sync {
+    /// Short identifier reported when the rule rejects a candidate
+    fn name(&self) -> &str;
+    fn check(&self, candidate: &LaunchCandidate) -> bool;
+}
+
+struct PredicateFilter<F> {
+    name: String,
+    predicate: F,
+}
+
+impl<F> LaunchFilter for PredicateFilter<F>
+where
+    F: Fn(&LaunchCandidate) -> bool + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&self, candidate: &LaunchCandidate) -> bool {
+        (self.predicate)(candidate)
+    }
+}
+
+/// Token security requirements checked through `get_token_security`
+#[derive(Debug, Clone)]
+pub struct SecurityRules {
+    pub reject_mint_authority: bool,
+    pub reject_freeze_authority: bool,
+    pub reject_mutable_metadata: bool,
+    pub reject_transfer_fee: bool,
+    /// Maximum share of supply held by the top 10 holders (0.0 - 1.0)
+    pub max_top10_holder_percent: Option<f64>,
+}
+
+impl Default for SecurityRules {
+    fn default() -> Self {
+        Self {
+            reject_mint_authority: true,
+            reject_freeze_authority: true,
+            reject_mutable_metadata: false,
+            reject_transfer_fee: false,
+            max_top10_holder_percent: None,
+        }
+    }
+}
+
+impl SecurityRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_mutable_metadata_rejected(mut self, reject: bool) -> Self {
+        self.reject_mutable_metadata = reject;
+        self
+    }
+
+    pub fn with_transfer_fee_rejected(mut self, reject: bool) -> Self {
+        self.reject_transfer_fee = reject;
+        self
+    }
+
+    pub fn with_max_top10_holder_percent(mut self, max: f64) -> Self {
+        assert!((0.0..=1.0).contains(&max), "max_top10_holder_percent must be between 0 and 1");
+        self.max_top10_holder_percent = Some(max);
+        self
+    }
+
+    /// Name of the first violated rule, if any
+    pub fn violation(&self, security: &TokenSecurityData) -> Option<&'static str> {
+        if self.reject_mint_authority && security.has_mint_authority() {
+            return Some("mint_authority");
+        }
+        if self.reject_freeze_authority && security.has_freeze_authority() {
+            return Some("freeze_authority");
+        }
+        if self.reject_mutable_metadata && security.mutable_metadata == Some(true) {
+            return Some("mutable_metadata");
+        }
+        if self.reject_transfer_fee && security.transfer_fee_enable == Some(true) {
+            return Some("transfer_fee");
+        }
+        if let (Some(max), Some(actual)) = (self.max_top10_holder_percent, security.top10_holder_percent) {
+            if actual > max {
+                return Some("top10_holder_percent");
+            }
+        }
+        None
+    }
+}
+
+/// Outcome of running a candidate through the pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterVerdict {
+    Accepted,
+    Rejected { filter: String },
+}
+
+impl FilterVerdict {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, FilterVerdict::Accepted)
+    }
+
+    fn rejected(filter: impl Into<String>) -> Self {
+        FilterVerdict::Rejected { filter: filter.into() }
+    }
+}
+
+/// Composable client-side filters for newly launched tokens
+///
+/// Synchronous rules run first in insertion order; the REST security check
+/// runs last so rejected candidates cost no API calls.
+pub struct LaunchFilterPipeline {
+    filters: Vec<Box<dyn LaunchFilter>>,
+    min_age: Option<Duration>,
+    security: Option<(BirdeyeRest, SecurityRules)>,
+    max_concurrent_checks: usize,
+}
+
+impl Default for LaunchFilterPipeline {
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+            min_age: None,
+            security: None,
+            max_concurrent_checks: DEFAULT_MAX_CONCURRENT_CHECKS,
+        }
+    }
+}
+
+impl LaunchFilterPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_filter(mut self, filter: impl LaunchFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    pub fn with_predicate<F>(self, name: impl Into<String>, predicate: F) -> Self
+    where
+        F: Fn(&LaunchCandidate) -> bool + Send + Sync + 'static,
+    {
+        self.with_filter(PredicateFilter { name: name.into(), predicate })
+    }
+
+    /// Only accept pairs quoted in one of `quotes`; listings without a
+    /// known quote token pass
+    pub fn with_quote_allowlist(self, quotes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let quotes: HashSet<String> = quotes.into_iter().map(Into::into).collect();
+        self.with_predicate("quote_allowlist", move |c| match &c.quote_address {
+            Some(quote) => quotes.iter().any(|allowed| same_address(allowed, quote)),
+            None => true,
+        })
+    }
+
+    /// Only accept candidates whose venue passes `filter`
+    pub fn with_sources(self, filter: DexSourceFilter) -> Self {
+        self.with_predicate("source", move |c| match &c.source {
+            Some(source) => filter.accepts(source),
+            None => true,
+        })
+    }
+
+    pub fn with_name_regex(self, pattern: Regex) -> Self {
+        self.with_predicate("name_regex", move |c| pattern.is_match(&c.name))
+    }
+
+    pub fn with_symbol_regex(self, pattern: Regex) -> Self {
+        self.with_predicate("symbol_regex", move |c| pattern.is_match(&c.symbol))
+    }
+
+    pub fn with_decimals(self, range: RangeInclusive<u8>) -> Self {
+        self.with_predicate("decimals", move |c| range.contains(&c.decimals))
+    }
+
+    /// Hold candidates until they are at least `age` old
+    pub fn with_min_age(mut self, age: Duration) -> Self {
+        self.min_age = Some(age);
+        self
+    }
+
+    pub fn with_security_check(mut self, rest: BirdeyeRest, rules: SecurityRules) -> Self {
+        self.security = Some((rest, rules));
+        self
+    }
+
+    /// Limit how many candidates `run` evaluates at once, which bounds the
+    /// concurrent security requests
+    pub fn with_max_concurrent_checks(mut self, count: usize) -> Self {
+        assert!(count > 0, "max_concurrent_checks must be greater than 0");
+        self.max_concurrent_checks = count;
+        self
+    }
+
+    /// Run every filter against `candidate` right now
+    ///
+    /// Candidates younger than the minimum age are rejected as `min_age`;
+    /// `run` waits for them instead.
+    pub async fn evaluate(&self, candidate: &LaunchCandidate) -> FilterVerdict {
+        for filter in &self.filters {
+            if !filter.check(candidate) {
+                return FilterVerdict::rejected(filter.name());
+            }
+        }
+        if let Some(min_age) = self.min_age {
+            if candidate.age_secs() < min_age.as_secs() as i64 {
+                return FilterVerdict::rejected("min_age");
+            }
+        }
+        if let Some((rest, rules)) = &self.security {
+            let security = match rest.get_token_security(&candidate.token_address).await {
+                Ok(response) => response.data,
+                Err(_) => return FilterVerdict::rejected("security_unavailable"),
+            };
+            if let Some(rule) = rules.violation(&security) {
+                return FilterVerdict::rejected(rule);
+            }
+        }
+        FilterVerdict::Accepted
+    }
+
+    /// Filter `candidates` and forward the qualifying ones to `accepted`
+    ///
+    /// Each candidate is evaluated in its own task once it reaches the
+    /// minimum age, so slow REST checks do not hold up the stream; at most
+    /// `max_concurrent_checks` are evaluated at a time.
+    pub async fn run(self, mut candidates: mpsc::Receiver<LaunchCandidate>, accepted: mpsc::Sender<LaunchCandidate>) {
+        let permits = Arc::new(Semaphore::new(self.max_concurrent_checks));
+        let pipeline = Arc::new(self);
+        while let Some(candidate) = candidates.recv().await {
+            let pipeline = pipeline.clone();
+            let accepted = accepted.clone();
+            let permits = permits.clone();
+            tokio::spawn(async move {
+                if let Some(min_age) = pipeline.min_age {
+                    let wait = min_age.as_secs() as i64 - candidate.age_secs();
+                    if wait > 0 {
+                        tokio::time::sleep(Duration::from_secs(wait as u64)).await;
+                    }
+                }
+                // Waiting for the minimum age does not take a permit
+                let Ok(_permit) = permits.acquire().await else {
+                    return;
+                };
+                if pipeline.evaluate(&candidate).await.is_accepted() {
+                    let _ = accepted.send(candidate).await;
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_pair::TokenInfo;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn token(address: &str, symbol: &str) -> TokenInfo {
+        TokenInfo {
+            address: address.to_string(),
+            name: format!("{symbol} token"),
+            symbol: symbol.to_string(),
+            decimals: 6,
+        }
+    }
+
+    fn candidate(symbol: &str, age_secs: i64) -> LaunchCandidate {
+        LaunchCandidate::from(NewPairData {
+            address: "pair".to_string(),
+            name: format!("{symbol}-USDC"),
+            source: "raydium".to_string(),
+            base: token(USDC, "USDC"),
+            quote: token(symbol, symbol),
+            tx_hash: "hash".to_string(),
+            block_time: unix_now() - age_secs,
+        })
+    }
+
+    #[test]
+    fn launched_token_is_the_non_quote_side() {
+        let candidate = candidate("NEW", 0);
+        assert_eq!(candidate.token_address, "NEW");
+        assert_eq!(candidate.quote_address.as_deref(), Some(USDC));
+        assert_eq!(candidate.source, Some(DexSource::parse("raydium")));
+    }
+
+    #[tokio::test]
+    async fn first_failing_filter_is_reported() {
+        let pipeline = LaunchFilterPipeline::new()
+            .with_decimals(0..=9)
+            .with_symbol_regex(Regex::new("^[A-Z]+$").unwrap())
+            .with_quote_allowlist([USDC.to_lowercase()]);
+        let verdict = pipeline.evaluate(&candidate("NEW", 0)).await;
+        assert_eq!(verdict, FilterVerdict::rejected("quote_allowlist"));
+
+        let pipeline = LaunchFilterPipeline::new().with_symbol_regex(Regex::new("^[A-Z]+$").unwrap());
+        assert!(pipeline.evaluate(&candidate("NEW", 0)).await.is_accepted());
+        let verdict = pipeline.evaluate(&candidate("new", 0)).await;
+        assert_eq!(verdict, FilterVerdict::rejected("symbol_regex"));
+    }
+
+    #[tokio::test]
+    async fn young_candidates_fail_min_age() {
+        let pipeline = LaunchFilterPipeline::new().with_min_age(Duration::from_secs(60));
+        assert!(!pipeline.evaluate(&candidate("NEW", 10)).await.is_accepted());
+        assert!(pipeline.evaluate(&candidate("NEW", 120)).await.is_accepted());
+    }
+
+    #[test]
+    fn security_rules_report_the_first_violation() {
+        let security: TokenSecurityData = serde_json::from_value(json!({
+            "ownerAddress": null,
+            "freezeable": false,
+            "mutableMetadata": true,
+            "top10HolderPercent": 0.6,
+        }))
+        .unwrap();
+        assert_eq!(SecurityRules::new().violation(&security), None);
+        let rules = SecurityRules::new().with_max_top10_holder_percent(0.5);
+        assert_eq!(rules.violation(&security), Some("top10_holder_percent"));
+        let rules = rules.with_mutable_metadata_rejected(true);
+        assert_eq!(rules.violation(&security), Some("mutable_metadata"));
+    }
+
+    #[tokio::test]
+    async fn run_forwards_accepted_candidates() {
+        let pipeline = LaunchFilterPipeline::new()
+            .with_predicate("no_scam", |c| !c.symbol.contains("SCAM"))
+            .with_max_concurrent_checks(1);
+        let (candidates, candidate_rx) = mpsc::channel(8);
+        let (accepted, mut accepted_rx) = mpsc::channel(8);
+        let task = tokio::spawn(pipeline.run(candidate_rx, accepted));
+        for symbol in ["A", "SCAM", "B"] {
+            candidates.send(candidate(symbol, 0)).await.unwrap();
+        }
+        drop(candidates);
+        task.await.unwrap();
+        let mut symbols = Vec::new();
+        while let Some(candidate) = accepted_rx.recv().await {
+            symbols.push(candidate.symbol);
+        }
+        symbols.sort();
+        assert_eq!(symbols, ["A", "B"]);
+    }
+}
@@ -1,13 +1,38 @@
 pub mod base_quote;
+pub mod cache;
+#[cfg(feature = "arrow")]
+pub mod columnar;
 pub mod connection;
+pub mod dex;
+pub mod enrich;
+pub mod export;
+mod http;
+pub mod indicators;
 pub mod large_trades;
+pub mod latency;
+pub mod launch_filter;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod new_pair;
+pub mod order_flow;
+pub mod pnl;
+pub mod portfolio;
 pub mod price;
+pub mod price_alerts;
+pub mod proxy;
 pub mod rest;
+pub mod sinks;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod token_listing;
+pub mod token_risk;
+pub mod trade;
 pub mod types;
 pub mod txs;
+pub mod usage;
 pub mod wallet_txs;
+pub mod wallet_watcher;
+pub mod whale;
 
 // Re-export commonly used items
 pub use connection::*;
@@ -0,0 +1,262 @@
+//! Prometheus metrics for stream and REST health
+//!
+//! Attach one `BirdeyeMetrics` to the clients with `with_metrics`; clones
+//! record into the same series. `serve` exposes them in the Prometheus text
+//! format, or register them into an existing registry with
+//! `BirdeyeMetrics::with_registry`.
+
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use serde_json::Value;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::http::{read_request_head, respond, respond_with};
+use crate::types::{BirdeyeError, WebSocketResponse};
+use crate::usage::OTHER_MESSAGES;
+
+const LAG_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Collectors recorded by `BirdeyeWebSocket` and `BirdeyeRest`
+#[derive(Clone)]
+pub struct BirdeyeMetrics {
+    registry: Registry,
+    messages: IntCounterVec,
+    message_bytes: IntCounterVec,
+    decode_errors: IntCounterVec,
+    connects: IntCounterVec,
+    reconnects: IntCounter,
+    rest_duration: HistogramVec,
+    lag: HistogramVec,
+}
+
+impl std::fmt::Debug for BirdeyeMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BirdeyeMetrics").finish_non_exhaustive()
+    }
+}
+
+impl Default for BirdeyeMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BirdeyeMetrics {
+    /// Metrics in a registry of their own
+    pub fn new() -> Self {
+        Self::with_registry(&Registry::new()).expect("a fresh registry has no conflicting collectors")
+    }
+
+    /// Register the metrics into `registry`
+    pub fn with_registry(registry: &Registry) -> Result<Self, BirdeyeError> {
+        let messages = IntCounterVec::new(
+            Opts::new("birdeye_ws_messages_total", "WebSocket messages received by response type"),
+            &["type"],
+        )?;
+        let message_bytes = IntCounterVec::new(
+            Opts::new("birdeye_ws_message_bytes_total", "WebSocket bytes received by response type"),
+            &["type"],
+        )?;
+        let decode_errors = IntCounterVec::new(
+            Opts::new("birdeye_ws_decode_errors_total", "WebSocket frames that could not be decoded"),
+            &["reason"],
+        )?;
+        let connects = IntCounterVec::new(
+            Opts::new("birdeye_ws_connects_total", "WebSocket connection attempts by result"),
+            &["result"],
+        )?;
+        let reconnects = IntCounter::new("birdeye_ws_reconnects_total", "WebSocket reconnects after a lost or failed connection")?;
+        let rest_duration = HistogramVec::new(
+            HistogramOpts::new("birdeye_rest_request_duration_seconds", "REST request latency"),
+            &["endpoint", "status"],
+        )?;
+        let lag = HistogramVec::new(
+            HistogramOpts::new(
+                "birdeye_subscription_lag_seconds",
+                "Time between an event's unixTime/blockUnixTime and its receipt",
+            )
+            .buckets(LAG_BUCKETS.to_vec()),
+            &["type"],
+        )?;
+
+        registry.register(Box::new(messages.clone()))?;
+        registry.register(Box::new(message_bytes.clone()))?;
+        registry.register(Box::new(decode_errors.clone()))?;
+        registry.register(Box::new(connects.clone()))?;
+        registry.register(Box::new(reconnects.clone()))?;
+        registry.register(Box::new(rest_duration.clone()))?;
+        registry.register(Box::new(lag.clone()))?;
+        Ok(Self {
+            registry: registry.clone(),
+            messages,
+            message_bytes,
+            decode_errors,
+            connects,
+            reconnects,
+            rest_duration,
+            lag,
+        })
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// All metrics of the registry in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        // Encoding into a Vec only fails on malformed collectors
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Answer `GET /metrics` on `listener` until it fails
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), BirdeyeError> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let metrics = self.clone();
+            tokio::spawn(async move {
+                let _ = metrics.handle_scrape(stream).await;
+            });
+        }
+    }
+
+    async fn handle_scrape(&self, mut stream: TcpStream) -> Result<(), BirdeyeError> {
+        let head = read_request_head(&mut stream).await?;
+        let mut request_line = head.split_whitespace();
+        match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                let content_type = TextEncoder::new().format_type().to_string();
+                respond_with(&mut stream, "200 OK", &content_type, &self.render()).await
+            }
+            _ => respond(&mut stream, "404 Not Found", "try GET /metrics").await,
+        }
+    }
+
+    pub(crate) fn record_connect(&self, ok: bool) {
+        self.connects.with_label_values(&[if ok { "ok" } else { "error" }]).inc();
+    }
+
+    pub(crate) fn record_reconnect(&self) {
+        self.reconnects.inc();
+    }
+
+    pub(crate) fn record_message(&self, response: &WebSocketResponse, bytes: usize) {
+        let kind = response.response_type.as_str();
+        self.messages.with_label_values(&[kind]).inc();
+        self.message_bytes.with_label_values(&[kind]).inc_by(bytes as u64);
+        if let Some(latency_ms) = response.latency_ms() {
+            let lag = latency_ms as f64 / 1000.0;
+            self.lag.with_label_values(&[kind]).observe(lag.max(0.0));
+        }
+    }
+
+    /// Count a frame that did not decode into a `WebSocketResponse`
+    ///
+    /// JSON objects without a `type`, such as the welcome and subscription
+    /// acknowledgement messages, are counted as `OTHER` messages; only
+    /// invalid JSON and unknown or malformed protocol messages are decode
+    /// errors.
+    pub(crate) fn record_unparsed(&self, text: &str) {
+        let reason = match serde_json::from_str::<Value>(text) {
+            Ok(Value::Object(map)) if !map.contains_key("type") => {
+                self.messages.with_label_values(&[OTHER_MESSAGES]).inc();
+                self.message_bytes.with_label_values(&[OTHER_MESSAGES]).inc_by(text.len() as u64);
+                return;
+            }
+            Ok(_) => "unknown_type",
+            Err(_) => "invalid_json",
+        };
+        self.decode_errors.with_label_values(&[reason]).inc();
+    }
+
+    /// `status` is `None` when no response arrived
+    pub(crate) fn record_request(&self, endpoint: &str, status: Option<u16>, elapsed: Duration) {
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+        self.rest_duration
+            .with_label_values(&[endpoint, status.as_str()])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::BirdeyeWebSocket;
+
+    #[test]
+    fn frames_without_a_type_are_not_decode_errors() {
+        let metrics = BirdeyeMetrics::new();
+        let ws = BirdeyeWebSocket::new("key").with_metrics(metrics.clone());
+        let welcome = r#"{"message":"welcome"}"#;
+        assert!(ws.decode(welcome).is_err());
+        assert!(ws.decode("not json").is_err());
+        assert!(ws.decode(r#"{"type":"NOT_A_TYPE","data":{}}"#).is_err());
+        assert!(ws.decode(r#"{"type":"PRICE_DATA","data":{}}"#).is_ok());
+
+        let text = metrics.render();
+        assert!(text.contains(r#"birdeye_ws_messages_total{type="OTHER"} 1"#));
+        assert!(text.contains(&format!(r#"birdeye_ws_message_bytes_total{{type="OTHER"}} {}"#, welcome.len())));
+        assert!(text.contains(r#"birdeye_ws_messages_total{type="PRICE_DATA"} 1"#));
+        assert!(text.contains(r#"birdeye_ws_decode_errors_total{reason="invalid_json"} 1"#));
+        assert!(text.contains(r#"birdeye_ws_decode_errors_total{reason="unknown_type"} 1"#));
+    }
+
+    #[test]
+    fn records_lag_connects_and_request_latency() {
+        let metrics = BirdeyeMetrics::new();
+        let ws = BirdeyeWebSocket::new("key").with_metrics(metrics.clone());
+        let event_time = crate::types::unix_now() - 2;
+        ws.decode(&format!(r#"{{"type":"PRICE_DATA","data":{{"unixTime":{event_time}}}}}"#)).unwrap();
+        metrics.record_connect(true);
+        metrics.record_connect(false);
+        metrics.record_reconnect();
+        metrics.record_request("/defi/price", Some(200), Duration::from_millis(20));
+        metrics.record_request("/defi/price", None, Duration::from_millis(20));
+
+        let text = metrics.render();
+        assert!(text.contains(r#"birdeye_subscription_lag_seconds_count{type="PRICE_DATA"} 1"#));
+        assert!(text.contains(r#"birdeye_subscription_lag_seconds_bucket{type="PRICE_DATA",le="1"} 0"#));
+        assert!(text.contains(r#"birdeye_ws_connects_total{result="error"} 1"#));
+        assert!(text.contains("birdeye_ws_reconnects_total 1"));
+        assert!(text.contains(r#"birdeye_rest_request_duration_seconds_count{endpoint="/defi/price",status="200"} 1"#));
+        assert!(text.contains(r#"birdeye_rest_request_duration_seconds_count{endpoint="/defi/price",status="error"} 1"#));
+    }
+
+    #[test]
+    fn registering_twice_into_one_registry_fails() {
+        let registry = Registry::new();
+        assert!(BirdeyeMetrics::with_registry(&registry).is_ok());
+        assert!(matches!(BirdeyeMetrics::with_registry(&registry), Err(BirdeyeError::Metrics(_))));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        let metrics = BirdeyeMetrics::new();
+        metrics.record_reconnect();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = metrics.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {path} HTTP/1.1\r\nHost: x\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK") && response.contains("birdeye_ws_reconnects_total 1"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+        assert!(get("/other").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenInfo {
     pub address: String,
     pub name: String,
@@ -9,7 +9,7 @@ pub struct TokenInfo {
     pub decimals: u8,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NewPairData {
     pub address: String,
     pub name: String,
@@ -23,7 +23,7 @@ pub struct NewPairData {
 }
 
 /// Options for new pair subscription
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NewPairOptions {
     /// Minimum liquidity requirement (must be > 10)
     pub min_liquidity: Option<f64>,
@@ -31,15 +31,6 @@ pub struct NewPairOptions {
     pub max_liquidity: Option<f64>,
 }
 
-impl Default for NewPairOptions {
-    fn default() -> Self {
-        Self {
-            min_liquidity: None,
-            max_liquidity: None,
-        }
-    }
-}
-
 impl NewPairOptions {
     pub fn new() -> Self {
         Self::default()
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::trade::{SeenTxs, ToTrade, Trade, TradeSide};
+use crate::txs::TransactionData;
+use crate::types::unix_now;
+
+/// What a set of order-flow metrics is aggregated over
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", content = "address", rename_all = "snake_case")]
+pub enum FlowKey {
+    Token(String),
+    Pair(String),
+}
+
+#[derive(Debug, Clone)]
+struct FlowTrade {
+    unix_time: i64,
+    side: TradeSide,
+    volume_usd: f64,
+    owner: String,
+}
+
+/// Order-flow metrics of one key over one rolling window
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderFlowSnapshot {
+    pub key: FlowKey,
+    pub window_secs: u64,
+    /// End of the window (Unix seconds)
+    pub as_of: i64,
+    pub buy_volume_usd: f64,
+    pub sell_volume_usd: f64,
+    pub net_volume_usd: f64,
+    /// `(buy - sell) / (buy + sell)`, from -1.0 (all sells) to 1.0 (all buys)
+    pub imbalance: f64,
+    pub trade_count: usize,
+    pub buy_count: usize,
+    pub sell_count: usize,
+    pub unique_traders: usize,
+    pub avg_trade_size_usd: f64,
+}
+
+/// Rolling buy/sell metrics per token and per pair from `TXS_DATA`
+///
+/// Trades are classified relative to the tracked tokens if configured,
+/// otherwise relative to the leg that is not a known quote token. A
+/// transaction delivered more than once, e.g. through overlapping token
+/// and pair subscriptions, is counted once.
+#[derive(Debug)]
+pub struct OrderFlowTracker {
+    windows: Vec<Duration>,
+    tracked_tokens: Option<HashSet<String>>,
+    trades: HashMap<FlowKey, VecDeque<FlowTrade>>,
+    seen: SeenTxs,
+}
+
+impl OrderFlowTracker {
+    pub fn new(windows: impl IntoIterator<Item = Duration>) -> Self {
+        let windows: Vec<Duration> = windows.into_iter().collect();
+        assert!(!windows.is_empty(), "at least one window is required");
+        Self {
+            windows,
+            tracked_tokens: None,
+            trades: HashMap::new(),
+            seen: SeenTxs::default(),
+        }
+    }
+
+    /// Classify buys/sells relative to these tokens
+    pub fn with_tracked_tokens(mut self, tokens: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tracked_tokens = Some(tokens.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn windows(&self) -> &[Duration] {
+        &self.windows
+    }
+
+    /// Count a trade; returns false if it is not a trade or was already
+    /// recorded
+    pub fn record(&mut self, tx: &TransactionData) -> bool {
+        if self.seen.contains(&tx.tx_hash) {
+            return false;
+        }
+        let Some(trade) = self.classify(tx) else {
+            return false;
+        };
+        self.seen.insert(&tx.tx_hash);
+        let flow = FlowTrade {
+            unix_time: trade.block_unix_time,
+            side: trade.side,
+            volume_usd: trade.volume_usd,
+            owner: trade.owner.clone(),
+        };
+        if let Some(pool) = &trade.pool_address {
+            self.push(FlowKey::Pair(pool.clone()), flow.clone());
+        }
+        self.push(FlowKey::Token(trade.base_address), flow);
+        true
+    }
+
+    /// Metrics of `key` over the last `window`, ending now
+    pub fn snapshot(&self, key: &FlowKey, window: Duration) -> Option<OrderFlowSnapshot> {
+        self.snapshot_at(key, window, unix_now())
+    }
+
+    pub fn snapshot_at(&self, key: &FlowKey, window: Duration, as_of: i64) -> Option<OrderFlowSnapshot> {
+        let trades = self.trades.get(key)?;
+        let since = as_of - window.as_secs() as i64;
+
+        let mut snapshot = OrderFlowSnapshot {
+            key: key.clone(),
+            window_secs: window.as_secs(),
+            as_of,
+            buy_volume_usd: 0.0,
+            sell_volume_usd: 0.0,
+            net_volume_usd: 0.0,
+            imbalance: 0.0,
+            trade_count: 0,
+            buy_count: 0,
+            sell_count: 0,
+            unique_traders: 0,
+            avg_trade_size_usd: 0.0,
+        };
+        let mut traders = HashSet::new();
+        for trade in trades.iter().filter(|t| t.unix_time > since && t.unix_time <= as_of) {
+            match trade.side {
+                TradeSide::Buy => {
+                    snapshot.buy_volume_usd += trade.volume_usd;
+                    snapshot.buy_count += 1;
+                }
+                TradeSide::Sell => {
+                    snapshot.sell_volume_usd += trade.volume_usd;
+                    snapshot.sell_count += 1;
+                }
+            }
+            traders.insert(trade.owner.as_str());
+        }
+
+        let total = snapshot.buy_volume_usd + snapshot.sell_volume_usd;
+        snapshot.trade_count = snapshot.buy_count + snapshot.sell_count;
+        snapshot.unique_traders = traders.len();
+        snapshot.net_volume_usd = snapshot.buy_volume_usd - snapshot.sell_volume_usd;
+        if total > 0.0 {
+            snapshot.imbalance = snapshot.net_volume_usd / total;
+        }
+        if snapshot.trade_count > 0 {
+            snapshot.avg_trade_size_usd = total / snapshot.trade_count as f64;
+        }
+        Some(snapshot)
+    }
+
+    /// Metrics for every key and window, ending now
+    pub fn snapshots(&self) -> Vec<OrderFlowSnapshot> {
+        let now = unix_now();
+        self.trades
+            .keys()
+            .flat_map(|key| self.windows.iter().filter_map(move |w| self.snapshot_at(key, *w, now)))
+            .collect()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &FlowKey> {
+        self.trades.keys()
+    }
+
+    /// Drop trades older than the longest window and keys left empty
+    pub fn prune(&mut self) {
+        let horizon = unix_now() - self.longest_window();
+        self.trades.retain(|_, trades| {
+            while trades.front().is_some_and(|t| t.unix_time <= horizon) {
+                trades.pop_front();
+            }
+            !trades.is_empty()
+        });
+    }
+
+    fn classify(&self, tx: &TransactionData) -> Option<Trade> {
+        match &self.tracked_tokens {
+            Some(tokens) => [&tx.to.address, &tx.from.address]
+                .into_iter()
+                .find(|address| tokens.contains(*address))
+                .and_then(|base| tx.to_trade(base)),
+            None => tx.to_trade_inferred(),
+        }
+    }
+
+    fn push(&mut self, key: FlowKey, trade: FlowTrade) {
+        let horizon = trade.unix_time - self.longest_window();
+        let trades = self.trades.entry(key).or_default();
+        // Keep trades ordered even if the stream delivers them slightly out of order
+        let position = trades.partition_point(|t| t.unix_time <= trade.unix_time);
+        trades.insert(position, trade);
+        while trades.front().is_some_and(|t| t.unix_time <= horizon) {
+            trades.pop_front();
+        }
+    }
+
+    fn longest_window(&self) -> i64 {
+        self.windows.iter().max().map_or(0, |w| w.as_secs() as i64)
+    }
+}
+
+/// Publish `snapshots()` of `tracker` every `period`, pruning as it goes
+pub fn spawn_snapshots(
+    tracker: Arc<Mutex<OrderFlowTracker>>,
+    period: Duration,
+    snapshots: mpsc::Sender<Vec<OrderFlowSnapshot>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let batch = {
+                let mut tracker = tracker.lock().unwrap();
+                tracker.prune();
+                tracker.snapshots()
+            };
+            if snapshots.send(batch).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    /// A swap of `owner` buying (or selling) TOKEN against USDC
+    fn swap(hash: &str, owner: &str, buy: bool, volume_usd: f64, unix_time: i64) -> TransactionData {
+        let leg = |address: &str, change: f64| {
+            json!({
+                "symbol": "S", "decimals": 6, "address": address, "amount": 0,
+                "type": "", "typeSwap": if change < 0.0 { "from" } else { "to" },
+                "uiAmount": change.abs(), "price": null, "nearestPrice": null,
+                "changeAmount": 0, "uiChangeAmount": change,
+            })
+        };
+        let (from, to) = if buy { (USDC, "TOKEN") } else { ("TOKEN", USDC) };
+        serde_json::from_value(json!({
+            "blockUnixTime": unix_time, "owner": owner, "source": "raydium", "txHash": hash,
+            "alias": null, "isTradeOnBe": false,
+            "platform": "", "volumeUSD": volume_usd, "poolAddress": "pool",
+            "from": leg(from, -1.0), "to": leg(to, 1.0),
+        }))
+        .unwrap()
+    }
+
+    fn snapshot(tracker: &OrderFlowTracker, key: FlowKey, window: u64, as_of: i64) -> OrderFlowSnapshot {
+        tracker.snapshot_at(&key, Duration::from_secs(window), as_of).unwrap()
+    }
+
+    #[test]
+    fn aggregates_buys_and_sells_per_token_and_pair() {
+        let mut tracker = OrderFlowTracker::new([Duration::from_secs(60)]);
+        assert!(tracker.record(&swap("a", "alice", true, 300.0, 100)));
+        assert!(tracker.record(&swap("b", "bob", false, 100.0, 110)));
+        assert!(tracker.record(&swap("c", "alice", true, 200.0, 120)));
+
+        let token = snapshot(&tracker, FlowKey::Token("TOKEN".to_string()), 60, 120);
+        assert_eq!((token.buy_count, token.sell_count, token.unique_traders), (2, 1, 2));
+        assert_eq!(token.net_volume_usd, 400.0);
+        assert_eq!(token.imbalance, 400.0 / 600.0);
+        assert_eq!(token.avg_trade_size_usd, 200.0);
+
+        let pair = snapshot(&tracker, FlowKey::Pair("pool".to_string()), 60, 120);
+        assert_eq!(pair.trade_count, 3);
+    }
+
+    #[test]
+    fn duplicate_transactions_count_once() {
+        let mut tracker = OrderFlowTracker::new([Duration::from_secs(60)]);
+        assert!(tracker.record(&swap("a", "alice", true, 300.0, 100)));
+        assert!(!tracker.record(&swap("a", "alice", true, 300.0, 100)));
+        let token = snapshot(&tracker, FlowKey::Token("TOKEN".to_string()), 60, 100);
+        assert_eq!(token.trade_count, 1);
+        assert_eq!(token.buy_volume_usd, 300.0);
+    }
+
+    #[test]
+    fn windows_exclude_older_trades() {
+        let mut tracker = OrderFlowTracker::new([Duration::from_secs(30), Duration::from_secs(120)]);
+        tracker.record(&swap("a", "alice", true, 100.0, 100));
+        tracker.record(&swap("b", "bob", true, 100.0, 150));
+        // Arriving out of order still lands in the window
+        tracker.record(&swap("c", "carol", false, 50.0, 140));
+        let key = FlowKey::Token("TOKEN".to_string());
+        assert_eq!(snapshot(&tracker, key.clone(), 30, 150).trade_count, 2);
+        assert_eq!(snapshot(&tracker, key, 120, 150).trade_count, 3);
+    }
+
+    #[test]
+    fn tracked_tokens_decide_the_side() {
+        let mut tracker = OrderFlowTracker::new([Duration::from_secs(60)]).with_tracked_tokens([USDC]);
+        tracker.record(&swap("a", "alice", true, 100.0, 100));
+        // Buying TOKEN with USDC sells USDC
+        let usdc = snapshot(&tracker, FlowKey::Token(USDC.to_string()), 60, 100);
+        assert_eq!(usdc.sell_count, 1);
+    }
+}
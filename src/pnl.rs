@@ -0,0 +1,413 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::price::PriceData;
+use crate::trade::is_quote_token;
+use crate::wallet_txs::{WalletTxData, WalletTxKind};
+
+/// Lot matching used to compute the cost of sold units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostBasisMethod {
+    Fifo,
+    Lifo,
+    AverageCost,
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    quantity: f64,
+    unit_cost: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TokenBook {
+    symbol: String,
+    lots: VecDeque<Lot>,
+    realized_pnl: f64,
+    fees_paid: f64,
+    bought: f64,
+    sold: f64,
+    /// Units sold beyond the tracked holdings
+    unmatched_sold: f64,
+}
+
+impl TokenBook {
+    fn quantity(&self) -> f64 {
+        self.lots.iter().map(|lot| lot.quantity).sum()
+    }
+
+    fn cost_basis(&self) -> f64 {
+        self.lots.iter().map(|lot| lot.quantity * lot.unit_cost).sum()
+    }
+
+    fn buy(&mut self, method: CostBasisMethod, quantity: f64, unit_cost: f64) {
+        self.bought += quantity;
+        match method {
+            CostBasisMethod::AverageCost => {
+                let total_quantity = self.quantity() + quantity;
+                let total_cost = self.cost_basis() + quantity * unit_cost;
+                self.lots.clear();
+                if total_quantity > 0.0 {
+                    self.lots.push_back(Lot {
+                        quantity: total_quantity,
+                        unit_cost: total_cost / total_quantity,
+                    });
+                }
+            }
+            CostBasisMethod::Fifo | CostBasisMethod::Lifo => {
+                self.lots.push_back(Lot { quantity, unit_cost });
+            }
+        }
+    }
+
+    /// Consume lots for `quantity` units and return the units matched
+    /// against them and their cost
+    ///
+    /// Units sold beyond the tracked holdings (e.g. bought before tracking
+    /// started) have no known cost; they are counted in `unmatched_sold`
+    /// and left out of the realized PnL.
+    fn sell(&mut self, method: CostBasisMethod, quantity: f64) -> (f64, f64) {
+        self.sold += quantity;
+        let mut remaining = quantity;
+        let mut cost = 0.0;
+        while remaining > 0.0 {
+            let lot = match method {
+                CostBasisMethod::Lifo => self.lots.back_mut(),
+                CostBasisMethod::Fifo | CostBasisMethod::AverageCost => self.lots.front_mut(),
+            };
+            let Some(lot) = lot else { break };
+            let taken = remaining.min(lot.quantity);
+            cost += taken * lot.unit_cost;
+            lot.quantity -= taken;
+            remaining -= taken;
+            if lot.quantity <= f64::EPSILON {
+                match method {
+                    CostBasisMethod::Lifo => self.lots.pop_back(),
+                    CostBasisMethod::Fifo | CostBasisMethod::AverageCost => self.lots.pop_front(),
+                };
+            }
+        }
+        if remaining > f64::EPSILON {
+            self.unmatched_sold += remaining;
+        } else {
+            remaining = 0.0;
+        }
+        (quantity - remaining, cost)
+    }
+}
+
+/// Per-token line of a `PnlReport`
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionReport {
+    pub token: String,
+    pub symbol: String,
+    pub quantity: f64,
+    pub cost_basis: f64,
+    pub average_cost: Option<f64>,
+    pub last_price: Option<f64>,
+    pub market_value: Option<f64>,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: Option<f64>,
+    pub fees_paid: f64,
+    pub total_bought: f64,
+    pub total_sold: f64,
+    /// Units sold without a tracked purchase, excluded from `realized_pnl`
+    pub unmatched_sold: f64,
+}
+
+/// Serializable PnL summary for one wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlReport {
+    pub wallet: String,
+    pub method: CostBasisMethod,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees_paid: f64,
+    pub positions: Vec<PositionReport>,
+}
+
+/// Realized and unrealized PnL per wallet and token, in USD
+///
+/// Every swap is booked as a sell of the `from` token and a buy of the
+/// `to` token at their USD prices. Quote tokens (`KNOWN_QUOTE_TOKENS`) are
+/// treated as cash: paying with one is not a sale and receiving one is
+/// not a buy. Fees passed to `apply_wallet_tx` are deducted from the
+/// proceeds of the sell leg, or added to the cost of the buy leg when
+/// there is no sale.
+#[derive(Debug)]
+pub struct PnlEngine {
+    method: CostBasisMethod,
+    books: HashMap<String, BTreeMap<String, TokenBook>>,
+    prices: HashMap<String, f64>,
+}
+
+impl PnlEngine {
+    pub fn new(method: CostBasisMethod) -> Self {
+        Self {
+            method,
+            books: HashMap::new(),
+            prices: HashMap::new(),
+        }
+    }
+
+    pub fn method(&self) -> CostBasisMethod {
+        self.method
+    }
+
+    /// Book an acquisition of `quantity` units at `price_usd` each
+    pub fn record_buy(&mut self, wallet: &str, token: &str, symbol: &str, quantity: f64, price_usd: f64, fee_usd: f64) {
+        let method = self.method;
+        let book = self.book(wallet, token, symbol);
+        let unit_cost = if quantity > 0.0 {
+            price_usd + fee_usd / quantity
+        } else {
+            price_usd
+        };
+        book.fees_paid += fee_usd;
+        book.buy(method, quantity, unit_cost);
+    }
+
+    /// Book a disposal and return the realized PnL of this sale
+    ///
+    /// Only units matched against tracked purchases are realized; the
+    /// rest is reported as `unmatched_sold`.
+    pub fn record_sell(&mut self, wallet: &str, token: &str, symbol: &str, quantity: f64, price_usd: f64, fee_usd: f64) -> f64 {
+        let method = self.method;
+        let book = self.book(wallet, token, symbol);
+        let (matched, cost) = book.sell(method, quantity);
+        let realized = matched * price_usd - fee_usd - cost;
+        book.fees_paid += fee_usd;
+        book.realized_pnl += realized;
+        realized
+    }
+
+    /// Book a swap; returns the realized PnL of its sell leg, zero when
+    /// it was paid with a quote token
+    ///
+    /// Non-swap transactions, swaps between two quote tokens and legs
+    /// without a USD price are ignored.
+    pub fn apply_wallet_tx(&mut self, tx: &WalletTxData, fee_usd: f64) -> Option<f64> {
+        if tx.kind() != WalletTxKind::Swap {
+            return None;
+        }
+        let (from, to) = (tx.from.as_ref()?, tx.to.as_ref()?);
+        let (sells, buys) = (!is_quote_token(&from.address), !is_quote_token(&to.address));
+        if !sells && !buys {
+            return None;
+        }
+        let from_amount = from.ui_amount.abs();
+        let to_amount = to.ui_amount.abs();
+
+        // Fall back to the trade's USD volume when a leg has no price
+        let price_of = |price: Option<f64>, amount: f64| {
+            price.or_else(|| (amount > 0.0 && tx.volume_usd > 0.0).then(|| tx.volume_usd / amount))
+        };
+        let from_price = price_of(from.price.or(from.nearest_price), from_amount)?;
+        let to_price = price_of(to.price.or(to.nearest_price), to_amount)?;
+
+        self.prices.insert(to.address.clone(), to_price);
+        self.prices.insert(from.address.clone(), from_price);
+        if buys {
+            let buy_fee = if sells { 0.0 } else { fee_usd };
+            self.record_buy(&tx.owner, &to.address, &to.symbol, to_amount, to_price, buy_fee);
+        }
+        if !sells {
+            return Some(0.0);
+        }
+        Some(self.record_sell(&tx.owner, &from.address, &from.symbol, from_amount, from_price, fee_usd))
+    }
+
+    /// Mark a token to the latest streamed close price
+    pub fn apply_price(&mut self, price: &PriceData) {
+        self.prices.insert(price.address.clone(), price.c);
+    }
+
+    pub fn set_price(&mut self, token: impl Into<String>, price_usd: f64) {
+        self.prices.insert(token.into(), price_usd);
+    }
+
+    pub fn wallets(&self) -> impl Iterator<Item = &str> {
+        self.books.keys().map(String::as_str)
+    }
+
+    pub fn report(&self, wallet: &str) -> PnlReport {
+        let positions: Vec<PositionReport> = self
+            .books
+            .get(wallet)
+            .into_iter()
+            .flatten()
+            .map(|(token, book)| {
+                let quantity = book.quantity();
+                let cost_basis = book.cost_basis();
+                let last_price = self.prices.get(token).copied();
+                let market_value = last_price.map(|price| price * quantity);
+                PositionReport {
+                    token: token.clone(),
+                    symbol: book.symbol.clone(),
+                    quantity,
+                    cost_basis,
+                    average_cost: (quantity > 0.0).then(|| cost_basis / quantity),
+                    last_price,
+                    market_value,
+                    realized_pnl: book.realized_pnl,
+                    unrealized_pnl: market_value.map(|value| value - cost_basis),
+                    fees_paid: book.fees_paid,
+                    total_bought: book.bought,
+                    total_sold: book.sold,
+                    unmatched_sold: book.unmatched_sold,
+                }
+            })
+            .collect();
+
+        PnlReport {
+            wallet: wallet.to_string(),
+            method: self.method,
+            realized_pnl: positions.iter().map(|p| p.realized_pnl).sum(),
+            unrealized_pnl: positions.iter().filter_map(|p| p.unrealized_pnl).sum(),
+            fees_paid: positions.iter().map(|p| p.fees_paid).sum(),
+            positions,
+        }
+    }
+
+    fn book(&mut self, wallet: &str, token: &str, symbol: &str) -> &mut TokenBook {
+        let book = self
+            .books
+            .entry(wallet.to_string())
+            .or_default()
+            .entry(token.to_string())
+            .or_default();
+        if book.symbol.is_empty() {
+            book.symbol = symbol.to_string();
+        }
+        book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet_txs::parse_wallet_txs_data;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const USDT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    /// Buy 10 @ 1, 10 @ 2, then sell 15 @ 3, leaving part of one lot
+    fn engine_after_partial_sale(method: CostBasisMethod) -> (PnlEngine, f64) {
+        let mut engine = PnlEngine::new(method);
+        engine.record_buy("w", "T", "T", 10.0, 1.0, 0.0);
+        engine.record_buy("w", "T", "T", 10.0, 2.0, 0.0);
+        let realized = engine.record_sell("w", "T", "T", 15.0, 3.0, 0.0);
+        (engine, realized)
+    }
+
+    #[test]
+    fn fifo_sells_oldest_lots_first() {
+        let (engine, realized) = engine_after_partial_sale(CostBasisMethod::Fifo);
+        // 10 @ 1 + 5 @ 2
+        assert_close(realized, 45.0 - 20.0);
+        let position = &engine.report("w").positions[0];
+        assert_close(position.quantity, 5.0);
+        assert_close(position.cost_basis, 10.0);
+    }
+
+    #[test]
+    fn lifo_sells_newest_lots_first() {
+        let (engine, realized) = engine_after_partial_sale(CostBasisMethod::Lifo);
+        // 10 @ 2 + 5 @ 1
+        assert_close(realized, 45.0 - 25.0);
+        let position = &engine.report("w").positions[0];
+        assert_close(position.quantity, 5.0);
+        assert_close(position.cost_basis, 5.0);
+    }
+
+    #[test]
+    fn average_cost_pools_lots() {
+        let (engine, realized) = engine_after_partial_sale(CostBasisMethod::AverageCost);
+        assert_close(realized, 45.0 - 22.5);
+        let position = &engine.report("w").positions[0];
+        assert_close(position.quantity, 5.0);
+        assert_close(position.average_cost.unwrap(), 1.5);
+    }
+
+    #[test]
+    fn oversell_realizes_only_matched_units() {
+        for method in [CostBasisMethod::Fifo, CostBasisMethod::Lifo, CostBasisMethod::AverageCost] {
+            let mut engine = PnlEngine::new(method);
+            engine.record_buy("w", "T", "T", 10.0, 1.0, 0.0);
+            let realized = engine.record_sell("w", "T", "T", 25.0, 2.0, 1.0);
+            assert_close(realized, 20.0 - 1.0 - 10.0);
+            let position = &engine.report("w").positions[0];
+            assert_close(position.quantity, 0.0);
+            assert_close(position.unmatched_sold, 15.0);
+            assert_close(position.total_sold, 25.0);
+        }
+    }
+
+    #[test]
+    fn buy_fees_raise_the_cost_basis() {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        engine.record_buy("w", "T", "T", 10.0, 1.0, 2.0);
+        let report = engine.report("w");
+        assert_close(report.positions[0].cost_basis, 12.0);
+        assert_close(report.fees_paid, 2.0);
+    }
+
+    fn swap(from: (&str, f64, f64), to: (&str, f64, f64)) -> WalletTxData {
+        let leg = |(address, amount, price): (&str, f64, f64), sign: f64| {
+            json!({
+                "symbol": address, "decimals": 6, "address": address, "uiAmount": amount,
+                "amount": "0", "price": price, "nearestPrice": null, "uiChangeAmount": sign * amount,
+            })
+        };
+        parse_wallet_txs_data(json!({
+            "type": "swap", "blockUnixTime": 1, "blockHumanTime": "", "owner": "w",
+            "source": "", "txHash": "h", "volumeUSD": 0.0, "network": "solana",
+            "from": leg(from, -1.0), "to": leg(to, 1.0),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn paying_with_a_quote_token_is_not_a_sale() {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        let realized = engine.apply_wallet_tx(&swap((USDC, 100.0, 1.0), ("T", 50.0, 2.0)), 1.0);
+        assert_eq!(realized, Some(0.0));
+        let report = engine.report("w");
+        assert_eq!(report.positions.len(), 1);
+        assert_eq!(report.positions[0].token, "T");
+        assert_close(report.positions[0].cost_basis, 101.0);
+    }
+
+    #[test]
+    fn selling_for_a_quote_token_realizes_pnl() {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        engine.apply_wallet_tx(&swap((USDC, 100.0, 1.0), ("T", 50.0, 2.0)), 0.0);
+        let realized = engine.apply_wallet_tx(&swap(("T", 50.0, 3.0), (USDC, 150.0, 1.0)), 0.0);
+        assert_close(realized.unwrap(), 50.0);
+        assert!(engine.report("w").positions.iter().all(|p| p.token == "T"));
+    }
+
+    #[test]
+    fn quote_to_quote_swaps_are_ignored() {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        assert_eq!(engine.apply_wallet_tx(&swap((USDC, 10.0, 1.0), (USDT, 10.0, 1.0)), 0.0), None);
+        assert!(engine.report("w").positions.is_empty());
+    }
+
+    #[test]
+    fn every_trade_updates_the_mark_price() {
+        let mut engine = PnlEngine::new(CostBasisMethod::Fifo);
+        engine.apply_wallet_tx(&swap((USDC, 20.0, 1.0), ("T", 10.0, 2.0)), 0.0);
+        engine.apply_wallet_tx(&swap((USDC, 15.0, 1.0), ("T", 5.0, 3.0)), 0.0);
+        let position = &engine.report("w").positions[0];
+        assert_eq!(position.last_price, Some(3.0));
+        assert_close(position.unrealized_pnl.unwrap(), 45.0 - 35.0);
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::price::PriceData;
+use crate::rest::{BirdeyeRest, WalletPortfolioData};
+use crate::trade::{same_address, SeenTxs};
+use crate::types::{unix_now, BirdeyeError};
+use crate::wallet_txs::WalletTxData;
+
+/// Balance differences smaller than this are not reported as drift
+const DRIFT_EPSILON: f64 = 1e-9;
+
+/// A single token holding of the tracked wallet
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub address: String,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub ui_amount: f64,
+    pub price_usd: Option<f64>,
+    /// Unix time of the last price update, if it came from `PriceData`
+    pub price_unix_time: Option<i64>,
+}
+
+impl Position {
+    pub fn value_usd(&self) -> Option<f64> {
+        self.price_usd.map(|price| price * self.ui_amount)
+    }
+}
+
+/// Difference between the tracked and the REST-reported balance of a token
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub address: String,
+    pub tracked: f64,
+    pub actual: f64,
+}
+
+/// Live view of a wallet's holdings
+///
+/// Seed it from REST with `seed`, then feed it `WalletTxData` and
+/// `PriceData` as they stream in. Balances are corrected by `reconcile`.
+#[derive(Debug)]
+pub struct PortfolioTracker {
+    rest: BirdeyeRest,
+    wallet: String,
+    positions: HashMap<String, Position>,
+    seen: SeenTxs,
+    /// Balance changes applied while a reconciliation snapshot is being
+    /// fetched
+    in_flight: Option<Vec<InFlightDelta>>,
+}
+
+/// A balance change applied while a snapshot was being fetched
+#[derive(Debug)]
+struct InFlightDelta {
+    address: String,
+    block_unix_time: i64,
+    ui_change_amount: f64,
+}
+
+impl PortfolioTracker {
+    pub fn new(rest: BirdeyeRest, wallet: impl Into<String>) -> Self {
+        Self {
+            rest,
+            wallet: wallet.into(),
+            positions: HashMap::new(),
+            seen: SeenTxs::default(),
+            in_flight: None,
+        }
+    }
+
+    pub fn wallet(&self) -> &str {
+        &self.wallet
+    }
+
+    /// Replace all balances with the current REST snapshot
+    pub async fn seed(&mut self) -> Result<(), BirdeyeError> {
+        let snapshot = self.rest.get_wallet_portfolio(&self.wallet).await?;
+        self.apply_snapshot(snapshot.data);
+        Ok(())
+    }
+
+    /// Fetch a REST snapshot and return how far the tracked balances drifted
+    pub async fn reconcile(&mut self) -> Result<Vec<Drift>, BirdeyeError> {
+        let snapshot = self.rest.get_wallet_portfolio(&self.wallet).await?;
+        Ok(self.apply_snapshot(snapshot.data))
+    }
+
+    /// Overwrite balances with `snapshot`, keeping fresher streamed prices
+    pub fn apply_snapshot(&mut self, snapshot: WalletPortfolioData) -> Vec<Drift> {
+        let mut drifts = Vec::new();
+        let mut previous = std::mem::take(&mut self.positions);
+
+        for item in snapshot.items {
+            let old = previous.remove(&item.address);
+            let tracked = old.as_ref().map_or(0.0, |p| p.ui_amount);
+            if (tracked - item.ui_amount).abs() > DRIFT_EPSILON {
+                drifts.push(Drift {
+                    address: item.address.clone(),
+                    tracked,
+                    actual: item.ui_amount,
+                });
+            }
+
+            let (price_usd, price_unix_time) = match old {
+                Some(Position { price_usd, price_unix_time: Some(at), .. }) => (price_usd, Some(at)),
+                _ => (item.price_usd, None),
+            };
+            self.positions.insert(
+                item.address.clone(),
+                Position {
+                    address: item.address,
+                    symbol: item.symbol,
+                    decimals: item.decimals,
+                    ui_amount: item.ui_amount,
+                    price_usd,
+                    price_unix_time,
+                },
+            );
+        }
+
+        // Tokens we tracked that REST no longer reports
+        for (address, position) in previous {
+            if position.ui_amount.abs() > DRIFT_EPSILON {
+                drifts.push(Drift {
+                    address,
+                    tracked: position.ui_amount,
+                    actual: 0.0,
+                });
+            }
+        }
+        drifts
+    }
+
+    /// Apply the `from`/`to` balance changes of a transaction
+    ///
+    /// Returns false if the transaction was already applied or belongs to
+    /// another wallet.
+    pub fn apply_wallet_tx(&mut self, tx: &WalletTxData) -> bool {
+        if !same_address(&self.wallet, &tx.owner) || !self.seen.insert(&tx.tx_hash) {
+            return false;
+        }
+        for leg in tx.from.iter().chain(tx.to.iter()) {
+            let position = self
+                .positions
+                .entry(leg.address.clone())
+                .or_insert_with(|| Position {
+                    address: leg.address.clone(),
+                    symbol: Some(leg.symbol.clone()),
+                    decimals: Some(leg.decimals),
+                    ui_amount: 0.0,
+                    price_usd: None,
+                    price_unix_time: None,
+                });
+            position.ui_amount += leg.ui_change_amount;
+            if let Some(deltas) = &mut self.in_flight {
+                deltas.push(InFlightDelta {
+                    address: leg.address.clone(),
+                    block_unix_time: tx.block_unix_time,
+                    ui_change_amount: leg.ui_change_amount,
+                });
+            }
+            if position.price_unix_time.is_none() {
+                if let Some(price) = leg.price.or(leg.nearest_price) {
+                    position.price_usd = Some(price);
+                }
+            }
+        }
+        true
+    }
+
+    /// Value a held token with the latest streamed close price
+    pub fn apply_price(&mut self, price: &PriceData) {
+        if let Some(position) = self.positions.get_mut(&price.address) {
+            if position.price_unix_time.is_none_or(|at| at <= price.unix_time) {
+                position.price_usd = Some(price.c);
+                position.price_unix_time = Some(price.unix_time);
+            }
+        }
+    }
+
+    pub fn position(&self, address: &str) -> Option<&Position> {
+        self.positions.get(address)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    /// Total USD value of all positions with a known price
+    pub fn total_value_usd(&self) -> f64 {
+        self.positions.values().filter_map(Position::value_usd).sum()
+    }
+
+    /// Start recording deltas for `finish_snapshot`
+    fn begin_snapshot(&mut self) {
+        self.in_flight = Some(Vec::new());
+    }
+
+    /// Apply a snapshot fetched since `begin_snapshot`, as of `as_of`
+    ///
+    /// Deltas of transactions up to `as_of` are already part of the
+    /// snapshot. Newer ones are taken out before comparing, so they are not
+    /// reported as drift, and re-applied on top of it.
+    fn finish_snapshot(&mut self, snapshot: WalletPortfolioData, as_of: i64) -> Vec<Drift> {
+        let mut deltas: HashMap<String, f64> = HashMap::new();
+        for delta in self.in_flight.take().unwrap_or_default() {
+            if delta.block_unix_time > as_of {
+                *deltas.entry(delta.address).or_default() += delta.ui_change_amount;
+            }
+        }
+        let mut streamed = HashMap::new();
+        for (address, delta) in &deltas {
+            if let Some(position) = self.positions.get_mut(address) {
+                position.ui_amount -= delta;
+                streamed.insert(address.clone(), position.clone());
+            }
+        }
+        let drifts = self.apply_snapshot(snapshot);
+        for (address, delta) in deltas {
+            if let Some(position) = self.positions.get_mut(&address) {
+                position.ui_amount += delta;
+            } else if let Some(mut position) = streamed.remove(&address) {
+                position.ui_amount = delta;
+                self.positions.insert(address, position);
+            }
+        }
+        drifts
+    }
+}
+
+/// Reconcile `tracker` against REST every `period`
+///
+/// The snapshot is fetched without holding the lock, so deltas keep being
+/// applied meanwhile. It is taken to include every transaction up to the
+/// second its response arrived; deltas of later transactions are replayed on
+/// top of it. `on_drift` receives any corrections made.
+pub fn spawn_reconciler<F>(tracker: Arc<Mutex<PortfolioTracker>>, period: Duration, mut on_drift: F) -> JoinHandle<()>
+where
+    F: FnMut(Vec<Drift>) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let (rest, wallet) = {
+                let mut tracker = tracker.lock().await;
+                tracker.begin_snapshot();
+                (tracker.rest.clone(), tracker.wallet.clone())
+            };
+            let snapshot = match rest.get_wallet_portfolio(&wallet).await {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    tracing::warn!(error = %e, wallet, "portfolio reconciliation failed");
+                    tracker.lock().await.in_flight = None;
+                    continue;
+                }
+            };
+            let drifts = tracker.lock().await.finish_snapshot(snapshot.data, unix_now());
+            if !drifts.is_empty() {
+                on_drift(drifts);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet_txs::parse_wallet_txs_data;
+    use serde_json::json;
+
+    const WALLET: &str = "wallet";
+
+    fn tracker() -> PortfolioTracker {
+        PortfolioTracker::new(BirdeyeRest::new("key", "solana"), WALLET)
+    }
+
+    fn tx(owner: &str, hash: &str, address: &str, change: f64) -> WalletTxData {
+        tx_at(1, owner, hash, address, change)
+    }
+
+    fn tx_at(block_unix_time: i64, owner: &str, hash: &str, address: &str, change: f64) -> WalletTxData {
+        parse_wallet_txs_data(json!({
+            "type": "transfer", "blockUnixTime": block_unix_time, "blockHumanTime": "", "owner": owner,
+            "source": "", "txHash": hash, "volumeUSD": 0.0, "network": "solana",
+            "to": {
+                "symbol": "T", "decimals": 6, "address": address, "uiAmount": change.abs(),
+                "amount": "0", "price": 2.0, "nearestPrice": null, "uiChangeAmount": change,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn snapshot(items: &[(&str, f64)]) -> WalletPortfolioData {
+        serde_json::from_value(json!({
+            "wallet": WALLET,
+            "totalUsd": null,
+            "items": items.iter().map(|(address, amount)| json!({
+                "address": address, "decimals": 6, "name": null, "symbol": "T",
+                "uiAmount": amount, "priceUsd": 1.0, "valueUsd": null, "logoURI": null,
+            })).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_each_transaction_once() {
+        let mut tracker = tracker();
+        assert!(tracker.apply_wallet_tx(&tx(WALLET, "a", "T", 5.0)));
+        assert!(!tracker.apply_wallet_tx(&tx(WALLET, "a", "T", 5.0)));
+        assert_eq!(tracker.position("T").unwrap().ui_amount, 5.0);
+        assert_eq!(tracker.total_value_usd(), 10.0);
+    }
+
+    #[test]
+    fn ignores_other_wallets() {
+        let mut tracker = tracker();
+        assert!(!tracker.apply_wallet_tx(&tx("someone", "a", "T", 5.0)));
+        assert!(tracker.position("T").is_none());
+    }
+
+    #[test]
+    fn snapshot_reports_drift() {
+        let mut tracker = tracker();
+        tracker.apply_wallet_tx(&tx(WALLET, "a", "T", 5.0));
+        tracker.apply_wallet_tx(&tx(WALLET, "b", "U", 1.0));
+        let mut drifts = tracker.apply_snapshot(snapshot(&[("T", 4.0)]));
+        drifts.sort_by(|a, b| a.address.cmp(&b.address));
+        assert_eq!(drifts.len(), 2);
+        assert_eq!((drifts[0].tracked, drifts[0].actual), (5.0, 4.0));
+        assert_eq!((drifts[1].tracked, drifts[1].actual), (1.0, 0.0));
+        assert!(tracker.position("U").is_none());
+    }
+
+    #[test]
+    fn deltas_during_fetch_are_replayed() {
+        let mut tracker = tracker();
+        tracker.apply_snapshot(snapshot(&[("T", 5.0)]));
+        tracker.begin_snapshot();
+        tracker.apply_wallet_tx(&tx_at(30, WALLET, "a", "T", -2.0));
+        tracker.apply_wallet_tx(&tx_at(30, WALLET, "b", "U", 3.0));
+        let drifts = tracker.finish_snapshot(snapshot(&[("T", 5.0)]), 20);
+        assert!(drifts.is_empty(), "{drifts:?}");
+        assert_eq!(tracker.position("T").unwrap().ui_amount, 3.0);
+        assert_eq!(tracker.position("U").unwrap().ui_amount, 3.0);
+    }
+
+    #[test]
+    fn deltas_already_in_the_snapshot_are_not_replayed() {
+        let mut tracker = tracker();
+        tracker.apply_snapshot(snapshot(&[("T", 5.0)]));
+        tracker.begin_snapshot();
+        tracker.apply_wallet_tx(&tx_at(10, WALLET, "a", "T", -2.0));
+        tracker.apply_wallet_tx(&tx_at(30, WALLET, "b", "T", -1.0));
+        let drifts = tracker.finish_snapshot(snapshot(&[("T", 3.0)]), 20);
+        assert!(drifts.is_empty(), "{drifts:?}");
+        assert_eq!(tracker.position("T").unwrap().ui_amount, 2.0);
+
+        // The next reconciliation sees no drift either
+        tracker.begin_snapshot();
+        assert!(tracker.finish_snapshot(snapshot(&[("T", 2.0)]), 40).is_empty());
+    }
+
+    #[test]
+    fn older_prices_are_ignored() {
+        let mut tracker = tracker();
+        tracker.apply_snapshot(snapshot(&[("T", 2.0)]));
+        let price = |c: f64, unix_time: i64| PriceData {
+            o: c,
+            h: c,
+            l: c,
+            c,
+            v: 0.0,
+            event_type: "ohlcv".to_string(),
+            chart_type: "1m".to_string(),
+            unix_time,
+            symbol: "T".to_string(),
+            address: "T".to_string(),
+        };
+        tracker.apply_price(&price(3.0, 20));
+        tracker.apply_price(&price(1.0, 10));
+        assert_eq!(tracker.position("T").unwrap().value_usd(), Some(6.0));
+    }
+}
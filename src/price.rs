@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use crate::rest::OHLCVData;
 use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PriceData {
     pub o: f64,
     pub h: f64,
@@ -18,6 +19,49 @@ pub struct PriceData {
     pub address: String,
 }
 
+/// OHLCV candle shared by streamed `PriceData` and REST `OHLCVData`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    pub address: String,
+    pub symbol: Option<String>,
+    pub unix_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl From<&PriceData> for Candle {
+    fn from(price: &PriceData) -> Self {
+        Self {
+            address: price.address.clone(),
+            symbol: Some(price.symbol.clone()),
+            unix_time: price.unix_time,
+            open: price.o,
+            high: price.h,
+            low: price.l,
+            close: price.c,
+            volume: price.v,
+        }
+    }
+}
+
+impl From<&OHLCVData> for Candle {
+    fn from(ohlcv: &OHLCVData) -> Self {
+        Self {
+            address: ohlcv.address.clone(),
+            symbol: None,
+            unix_time: ohlcv.unix_time,
+            open: ohlcv.open,
+            high: ohlcv.high,
+            low: ohlcv.low,
+            close: ohlcv.close,
+            volume: ohlcv.volume,
+        }
+    }
+}
+
 pub fn create_price_subscription(address: impl Into<String>, chart_type: impl Into<String>, currency: impl Into<String>) -> SubscriptionMessage {
     SubscriptionMessage {
         msg_type: SubscriptionType::SubscribePrice,
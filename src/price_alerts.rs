@@ -0,0 +1,312 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::price::{Candle, PriceData};
+use crate::rest::OHLCVData;
+use crate::sinks::{CrossDirection, PriceCrossing};
+
+/// Condition evaluated on every candle update
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "condition", rename_all = "snake_case")]
+pub enum PriceCondition {
+    /// Close at or above `threshold`
+    Above { threshold: f64 },
+    /// Close at or below `threshold`
+    Below { threshold: f64 },
+    /// Close moved at least `percent` (either direction) within `window`
+    PercentMove { percent: f64, window: Duration },
+    /// Candle body `|c - o| / o` is at least `percent`
+    BodyPercent { percent: f64 },
+    /// Upper wick is at least `ratio` of the high-low range
+    UpperWick { ratio: f64 },
+    /// Lower wick is at least `ratio` of the high-low range
+    LowerWick { ratio: f64 },
+}
+
+/// A named price alert with hysteresis
+///
+/// Once triggered, an alert re-arms only after its metric falls back below
+/// the trigger level by `hysteresis` (a fraction of the level), so prices
+/// oscillating around a threshold do not flap.
+#[derive(Debug, Clone)]
+pub struct PriceAlert {
+    pub id: String,
+    /// Token to watch; `None` applies the alert to every token
+    pub address: Option<String>,
+    pub condition: PriceCondition,
+    pub hysteresis: f64,
+}
+
+impl PriceAlert {
+    pub fn new(id: impl Into<String>, condition: PriceCondition) -> Self {
+        Self {
+            id: id.into(),
+            address: None,
+            condition,
+            hysteresis: 0.0,
+        }
+    }
+
+    pub fn for_token(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    pub fn with_hysteresis(mut self, fraction: f64) -> Self {
+        assert!((0.0..1.0).contains(&fraction), "hysteresis must be in [0, 1)");
+        self.hysteresis = fraction;
+        self
+    }
+
+    /// Current metric and the level it has to reach to trigger
+    ///
+    /// `history` holds earlier closes of the same token, oldest first.
+    fn metric(&self, candle: &Candle, history: &VecDeque<(i64, f64)>) -> Option<(f64, f64)> {
+        match &self.condition {
+            PriceCondition::Above { threshold } => Some((candle.close, *threshold)),
+            // Negate so that "lower is worse" still reads as crossing upwards
+            PriceCondition::Below { threshold } => Some((-candle.close, -*threshold)),
+            PriceCondition::PercentMove { percent, window } => {
+                let since = candle.unix_time - window.as_secs() as i64;
+                let (_, reference) = history.iter().find(|(time, _)| *time >= since)?;
+                if *reference == 0.0 {
+                    return None;
+                }
+                Some((((candle.close - reference) / reference * 100.0).abs(), *percent))
+            }
+            PriceCondition::BodyPercent { percent } => {
+                if candle.open == 0.0 {
+                    return None;
+                }
+                Some(((candle.close - candle.open).abs() / candle.open * 100.0, *percent))
+            }
+            PriceCondition::UpperWick { ratio } => {
+                let range = candle.high - candle.low;
+                (range > 0.0).then(|| ((candle.high - candle.open.max(candle.close)) / range, *ratio))
+            }
+            PriceCondition::LowerWick { ratio } => {
+                let range = candle.high - candle.low;
+                (range > 0.0).then(|| ((candle.open.min(candle.close) - candle.low) / range, *ratio))
+            }
+        }
+    }
+}
+
+/// A triggered price alert
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceAlertEvent {
+    pub alert_id: String,
+    pub address: String,
+    pub symbol: Option<String>,
+    pub condition: PriceCondition,
+    /// Value of the metric that crossed (price, percent or ratio)
+    pub value: f64,
+    pub price: f64,
+    pub unix_time: i64,
+}
+
+impl PriceAlertEvent {
+    /// Threshold crossings in the format accepted by the alert sinks
+    pub fn as_crossing(&self) -> Option<PriceCrossing> {
+        let (threshold, direction) = match self.condition {
+            PriceCondition::Above { threshold } => (threshold, CrossDirection::Above),
+            PriceCondition::Below { threshold } => (threshold, CrossDirection::Below),
+            _ => return None,
+        };
+        Some(PriceCrossing {
+            address: self.address.clone(),
+            symbol: self.symbol.clone().unwrap_or_default(),
+            threshold,
+            price: self.price,
+            direction,
+            unix_time: self.unix_time,
+        })
+    }
+}
+
+/// Evaluates `PriceAlert`s against live or historical candles
+#[derive(Debug, Default)]
+pub struct PriceAlertEngine {
+    alerts: Vec<PriceAlert>,
+    triggered: HashMap<(usize, String), bool>,
+    closes: HashMap<String, VecDeque<(i64, f64)>>,
+    max_window: i64,
+}
+
+impl PriceAlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_alert(mut self, alert: PriceAlert) -> Self {
+        self.add_alert(alert);
+        self
+    }
+
+    pub fn add_alert(&mut self, alert: PriceAlert) {
+        if let PriceCondition::PercentMove { window, .. } = alert.condition {
+            self.max_window = self.max_window.max(window.as_secs() as i64);
+        }
+        self.alerts.push(alert);
+    }
+
+    pub fn on_price(&mut self, price: &PriceData) -> Vec<PriceAlertEvent> {
+        self.on_candle(&Candle::from(price))
+    }
+
+    /// Evaluate every matching alert on a new or updated candle
+    pub fn on_candle(&mut self, candle: &Candle) -> Vec<PriceAlertEvent> {
+        let closes = self.closes.entry(candle.address.clone()).or_default();
+        // Streamed updates of the same candle replace its close
+        if closes.back().is_some_and(|(time, _)| *time == candle.unix_time) {
+            closes.pop_back();
+        }
+
+        let mut events = Vec::new();
+        for (index, alert) in self.alerts.iter().enumerate() {
+            if alert.address.as_ref().is_some_and(|a| a != &candle.address) {
+                continue;
+            }
+            let Some((value, level)) = alert.metric(candle, closes) else {
+                continue;
+            };
+            let triggered = self.triggered.entry((index, candle.address.clone())).or_insert(false);
+            if !*triggered && value >= level {
+                *triggered = true;
+                events.push(PriceAlertEvent {
+                    alert_id: alert.id.clone(),
+                    address: candle.address.clone(),
+                    symbol: candle.symbol.clone(),
+                    condition: alert.condition.clone(),
+                    value: value.abs(),
+                    price: candle.close,
+                    unix_time: candle.unix_time,
+                });
+            } else if *triggered && value < level - level.abs() * alert.hysteresis {
+                *triggered = false;
+            }
+        }
+
+        closes.push_back((candle.unix_time, candle.close));
+        let horizon = candle.unix_time - self.max_window;
+        while closes.front().is_some_and(|(time, _)| *time < horizon) {
+            closes.pop_front();
+        }
+        events
+    }
+
+    /// Replay `history` through a fresh copy of the configured alerts
+    ///
+    /// Useful to check how often an alert would have fired on `get_ohlcv` data.
+    pub fn backtest(&self, history: &[OHLCVData]) -> Vec<PriceAlertEvent> {
+        let mut engine = PriceAlertEngine::new();
+        for alert in &self.alerts {
+            engine.add_alert(alert.clone());
+        }
+        history
+            .iter()
+            .flat_map(|ohlcv| engine.on_candle(&Candle::from(ohlcv)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(unix_time: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            address: "T".to_string(),
+            symbol: Some("T".to_string()),
+            unix_time,
+            open,
+            high,
+            low,
+            close,
+            volume: 0.0,
+        }
+    }
+
+    fn closes(engine: &mut PriceAlertEngine, closes: &[f64]) -> Vec<f64> {
+        closes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &close)| engine.on_candle(&candle(i as i64 * 60, close, close, close, close)))
+            .map(|event| event.price)
+            .collect()
+    }
+
+    #[test]
+    fn above_rearms_only_past_the_hysteresis_band() {
+        let alert = PriceAlert::new("up", PriceCondition::Above { threshold: 100.0 }).with_hysteresis(0.05);
+        let mut engine = PriceAlertEngine::new().with_alert(alert);
+        // 98 and 96 stay within 5% of the level, 94 re-arms
+        assert_eq!(closes(&mut engine, &[99.0, 100.0, 98.0, 101.0, 96.0, 102.0, 94.0, 103.0]), [100.0, 103.0]);
+    }
+
+    #[test]
+    fn below_mirrors_above() {
+        let alert = PriceAlert::new("down", PriceCondition::Below { threshold: 50.0 }).with_hysteresis(0.1);
+        let mut engine = PriceAlertEngine::new().with_alert(alert);
+        assert_eq!(closes(&mut engine, &[51.0, 49.0, 54.0, 48.0, 56.0, 47.0]), [49.0, 47.0]);
+    }
+
+    #[test]
+    fn without_hysteresis_every_crossing_fires() {
+        let alert = PriceAlert::new("up", PriceCondition::Above { threshold: 10.0 });
+        let mut engine = PriceAlertEngine::new().with_alert(alert);
+        assert_eq!(closes(&mut engine, &[10.0, 9.99, 10.0, 10.5]), [10.0, 10.0]);
+    }
+
+    #[test]
+    fn percent_move_looks_back_over_the_window() {
+        let condition = PriceCondition::PercentMove {
+            percent: 10.0,
+            window: Duration::from_secs(120),
+        };
+        let mut engine = PriceAlertEngine::new().with_alert(PriceAlert::new("move", condition));
+        // Candles are a minute apart: 100 leaves the window before 111
+        assert_eq!(closes(&mut engine, &[100.0, 105.0, 108.0, 111.0, 95.0]), [95.0]);
+    }
+
+    #[test]
+    fn wick_and_body_conditions_use_the_candle_shape() {
+        let mut engine = PriceAlertEngine::new()
+            .with_alert(PriceAlert::new("upper", PriceCondition::UpperWick { ratio: 0.5 }))
+            .with_alert(PriceAlert::new("body", PriceCondition::BodyPercent { percent: 5.0 }));
+        let events = engine.on_candle(&candle(0, 100.0, 120.0, 99.0, 101.0));
+        let ids: Vec<_> = events.iter().map(|e| e.alert_id.as_str()).collect();
+        assert_eq!(ids, ["upper"]);
+        let events = engine.on_candle(&candle(60, 100.0, 110.0, 100.0, 108.0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].alert_id, "body");
+        assert!((events[0].value - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn updates_of_one_candle_replace_its_close() {
+        let condition = PriceCondition::PercentMove {
+            percent: 10.0,
+            window: Duration::from_secs(60),
+        };
+        let mut engine = PriceAlertEngine::new().with_alert(PriceAlert::new("move", condition));
+        assert!(engine.on_candle(&candle(0, 100.0, 100.0, 100.0, 100.0)).is_empty());
+        // Same candle updated: its earlier close is not a reference
+        assert!(engine.on_candle(&candle(0, 100.0, 120.0, 100.0, 120.0)).is_empty());
+        assert_eq!(engine.on_candle(&candle(60, 120.0, 140.0, 120.0, 140.0)).len(), 1);
+    }
+
+    #[test]
+    fn token_alerts_ignore_other_tokens_and_convert_to_crossings() {
+        let alert = PriceAlert::new("up", PriceCondition::Above { threshold: 1.0 }).for_token("other");
+        let mut engine = PriceAlertEngine::new().with_alert(alert);
+        assert!(engine.on_candle(&candle(0, 2.0, 2.0, 2.0, 2.0)).is_empty());
+
+        let alert = PriceAlert::new("up", PriceCondition::Above { threshold: 1.0 });
+        let mut engine = PriceAlertEngine::new().with_alert(alert);
+        let crossing = engine.on_candle(&candle(0, 2.0, 2.0, 2.0, 2.0))[0].as_crossing().unwrap();
+        assert_eq!((crossing.threshold, crossing.price, crossing.direction), (1.0, 2.0, CrossDirection::Above));
+    }
+}
@@ -0,0 +1,591 @@
+//! Fan-out proxy re-broadcasting Birdeye streams to local clients
+//!
+//! Clients connect over WebSocket and speak the Birdeye protocol: they send
+//! `SubscriptionMessage`s and receive `WebSocketResponse` frames verbatim.
+//! Identical subscriptions (same type and data, regardless of key order)
+//! share one upstream connection, which is opened on the first subscriber
+//! and closed after the last one leaves.
+//!
+//! With SSE enabled, `GET /sse?subscribe=<url-encoded SubscriptionMessage>`
+//! (repeatable) streams the same frames as `data:` lines.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Map, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::Instrument;
+
+use crate::connection::BirdeyeWebSocket;
+use crate::http::{read_request_head, respond};
+use crate::types::{BirdeyeError, ResponseType, SubscriptionMessage, SubscriptionType, WebSocketResponse};
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Options for `FanOutProxy`
+#[derive(Debug, Clone)]
+pub struct ProxyOptions {
+    /// Maximum concurrent upstream Birdeye connections (must be >= 1)
+    pub max_upstreams: usize,
+    /// Frames buffered per subscriber before it starts skipping frames
+    pub buffer: usize,
+    /// Serve `GET /sse` next to WebSocket
+    pub sse: bool,
+}
+
+impl Default for ProxyOptions {
+    fn default() -> Self {
+        Self {
+            max_upstreams: 50,
+            buffer: 1024,
+            sse: true,
+        }
+    }
+}
+
+impl ProxyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_upstreams(mut self, max: usize) -> Self {
+        assert!(max >= 1, "max_upstreams must be at least 1");
+        self.max_upstreams = max;
+        self
+    }
+
+    pub fn with_buffer(mut self, buffer: usize) -> Self {
+        assert!(buffer >= 1, "buffer must be at least 1");
+        self.buffer = buffer;
+        self
+    }
+
+    pub fn with_sse(mut self, enabled: bool) -> Self {
+        self.sse = enabled;
+        self
+    }
+}
+
+struct Upstream {
+    frames: broadcast::Sender<Arc<str>>,
+    subscribers: usize,
+    task: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct ProxyState {
+    upstreams: HashMap<String, Upstream>,
+}
+
+/// Shares upstream Birdeye subscriptions between any number of clients
+#[derive(Clone)]
+pub struct FanOutProxy {
+    ws: BirdeyeWebSocket,
+    options: ProxyOptions,
+    state: Arc<Mutex<ProxyState>>,
+}
+
+/// A client's share of an upstream subscription; released on drop
+pub struct ProxySubscription {
+    key: String,
+    frames: broadcast::Receiver<Arc<str>>,
+    state: Arc<Mutex<ProxyState>>,
+}
+
+impl ProxySubscription {
+    /// Key identifying the shared upstream subscription
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Next raw `WebSocketResponse` frame; frames missed while lagging
+    /// behind are skipped
+    pub async fn recv(&mut self) -> Option<Arc<str>> {
+        loop {
+            match self.frames.recv().await {
+                Ok(frame) => return Some(frame),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for ProxySubscription {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(upstream) = state.upstreams.get_mut(&self.key) {
+            upstream.subscribers -= 1;
+            if upstream.subscribers == 0 {
+                if let Some(upstream) = state.upstreams.remove(&self.key) {
+                    upstream.task.abort();
+                }
+            }
+        }
+    }
+}
+
+impl FanOutProxy {
+    /// Must be used from within a Tokio runtime
+    pub fn new(api_key: impl Into<String>, options: ProxyOptions) -> Self {
+        Self::with_client(BirdeyeWebSocket::new(api_key), options)
+    }
+
+    /// Open upstreams through `ws`, e.g. one with a usage tracker
+    ///
+    /// Must be used from within a Tokio runtime
+    pub fn with_client(ws: BirdeyeWebSocket, options: ProxyOptions) -> Self {
+        Self {
+            ws,
+            options,
+            state: Arc::new(Mutex::new(ProxyState::default())),
+        }
+    }
+
+    /// Join the upstream for `message`, opening it if needed
+    ///
+    /// Returns `None` if a new upstream would exceed `max_upstreams`.
+    pub fn subscribe(&self, message: &SubscriptionMessage) -> Option<ProxySubscription> {
+        let key = subscription_key(message);
+        let mut state = self.state.lock().unwrap();
+        let frames = match state.upstreams.get_mut(&key) {
+            Some(upstream) => {
+                upstream.subscribers += 1;
+                upstream.frames.subscribe()
+            }
+            None => {
+                if state.upstreams.len() >= self.options.max_upstreams {
+                    return None;
+                }
+                let (frames, receiver) = broadcast::channel(self.options.buffer);
+                let span = tracing::info_span!("upstream", msg_type = ?message.msg_type);
+                let task = tokio::spawn(run_upstream(self.ws.clone(), message.clone(), frames.clone()).instrument(span));
+                state.upstreams.insert(key.clone(), Upstream { frames, subscribers: 1, task });
+                receiver
+            }
+        };
+        Some(ProxySubscription {
+            key,
+            frames,
+            state: self.state.clone(),
+        })
+    }
+
+    pub fn upstream_count(&self) -> usize {
+        self.state.lock().unwrap().upstreams.len()
+    }
+
+    /// Total client subscriptions across all upstreams
+    pub fn subscriber_count(&self) -> usize {
+        self.state.lock().unwrap().upstreams.values().map(|u| u.subscribers).sum()
+    }
+
+    /// Accept clients on `listener` until it fails
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), BirdeyeError> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let proxy = self.clone();
+            tokio::spawn(
+                async move {
+                    tracing::debug!("client connected");
+                    if let Err(e) = proxy.handle_connection(stream).await {
+                        tracing::debug!(error = %e, "client connection ended");
+                    }
+                }
+                .instrument(tracing::debug_span!("client", %peer)),
+            );
+        }
+    }
+
+    /// Read the request head once and route on it: `GET /sse` to SSE,
+    /// WebSocket upgrades to the Birdeye protocol
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<(), BirdeyeError> {
+        let head = read_request_head(&mut stream).await?;
+        if head.is_empty() {
+            return Ok(());
+        }
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let (method, target) = (request_line.next(), request_line.next().unwrap_or_default());
+        let path = target.split('?').next().unwrap_or_default();
+        if self.options.sse && method == Some("GET") && path == "/sse" {
+            return self.serve_sse(stream, target).await;
+        }
+
+        let upgrade = header(&head, "upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+        let Some(key) = header(&head, "sec-websocket-key").filter(|_| upgrade) else {
+            return respond(&mut stream, "400 Bad Request", "expected a WebSocket upgrade").await;
+        };
+        let accept = derive_accept_key(key.as_bytes());
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+        self.serve_websocket(WebSocketStream::from_raw_socket(stream, Role::Server, None).await)
+            .await
+    }
+
+    async fn serve_websocket(&self, socket: WebSocketStream<TcpStream>) -> Result<(), BirdeyeError> {
+        let (mut sink, mut source) = socket.split();
+        let (frames, mut outgoing) = mpsc::channel::<Arc<str>>(self.options.buffer);
+        let mut forwarders: HashMap<String, (SubscriptionType, JoinHandle<()>)> = HashMap::new();
+
+        let result = loop {
+            tokio::select! {
+                message = source.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some(reply) = self.on_client_message(&text, &mut forwarders, &frames) {
+                            if let Err(e) = sink.send(Message::Text(reply.into())).await {
+                                break Err(e.into());
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Err(e)) => break Err(e.into()),
+                    Some(Ok(_)) => {}
+                },
+                Some(frame) = outgoing.recv() => {
+                    if let Err(e) = sink.send(Message::Text(frame.as_ref().into())).await {
+                        break Err(e.into());
+                    }
+                }
+            }
+        };
+        for (_, (_, task)) in forwarders {
+            task.abort();
+        }
+        result
+    }
+
+    /// Apply a client (un)subscription; returns an error frame to send back
+    fn on_client_message(
+        &self,
+        text: &str,
+        forwarders: &mut HashMap<String, (SubscriptionType, JoinHandle<()>)>,
+        frames: &mpsc::Sender<Arc<str>>,
+    ) -> Option<String> {
+        let message: SubscriptionMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => return Some(error_frame(&format!("invalid subscription message: {e}"))),
+        };
+
+        // Birdeye unsubscribes every subscription of the type at once
+        if let Some(cancelled) = message.msg_type.unsubscribes() {
+            forwarders.retain(|_, (kind, task)| {
+                let keep = *kind != cancelled;
+                if !keep {
+                    task.abort();
+                }
+                keep
+            });
+            return None;
+        }
+
+        let key = subscription_key(&message);
+        if forwarders.contains_key(&key) {
+            return None;
+        }
+        let Some(subscription) = self.subscribe(&message) else {
+            return Some(error_frame("upstream connection limit reached"));
+        };
+        forwarders.insert(key, (message.msg_type, spawn_forwarder(subscription, frames.clone())));
+        None
+    }
+
+    async fn serve_sse(&self, mut stream: TcpStream, target: &str) -> Result<(), BirdeyeError> {
+        let url = url::Url::parse(&format!("http://localhost{target}"))?;
+        let messages: Result<Vec<SubscriptionMessage>, _> = url
+            .query_pairs()
+            .filter(|(key, _)| key == "subscribe")
+            .map(|(_, value)| serde_json::from_str(&value))
+            .collect();
+        let messages = match messages {
+            Ok(messages) if !messages.is_empty() => messages,
+            _ => return respond(&mut stream, "400 Bad Request", "expected one or more `subscribe` parameters").await,
+        };
+
+        let (frames, mut outgoing) = mpsc::channel::<Arc<str>>(self.options.buffer);
+        let mut forwarders = Vec::with_capacity(messages.len());
+        for message in &messages {
+            match self.subscribe(message) {
+                Some(subscription) => forwarders.push(spawn_forwarder(subscription, frames.clone())),
+                None => {
+                    forwarders.iter().for_each(JoinHandle::abort);
+                    return respond(&mut stream, "503 Service Unavailable", "upstream connection limit reached").await;
+                }
+            }
+        }
+
+        let result = async {
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n")
+                .await?;
+            let mut keep_alive = tokio::time::interval(SSE_KEEP_ALIVE);
+            loop {
+                tokio::select! {
+                    Some(frame) = outgoing.recv() => {
+                        stream.write_all(format!("data: {frame}\n\n").as_bytes()).await?;
+                    }
+                    _ = keep_alive.tick() => stream.write_all(b": keep-alive\n\n").await?,
+                }
+            }
+        }
+        .await;
+        forwarders.iter().for_each(JoinHandle::abort);
+        result
+    }
+}
+
+fn spawn_forwarder(mut subscription: ProxySubscription, frames: mpsc::Sender<Arc<str>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(frame) = subscription.recv().await {
+            if frames.send(frame).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Hold one upstream subscription open, reconnecting with backoff
+async fn run_upstream(ws: BirdeyeWebSocket, message: SubscriptionMessage, frames: broadcast::Sender<Arc<str>>) {
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    let mut reconnecting = false;
+    loop {
+        if reconnecting {
+            ws.record_reconnect();
+        }
+        reconnecting = true;
+        let mut stream = match ws.connect().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "reconnecting after failed connect");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                continue;
+            }
+        };
+        if let Err(e) = BirdeyeWebSocket::send_message(&mut stream, &message).await {
+            tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "reconnecting after failed subscribe");
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            continue;
+        }
+        delay = INITIAL_RECONNECT_DELAY;
+
+        loop {
+            match stream.next().await {
+                // Only protocol frames are forwarded, not welcome messages
+                Some(Ok(Message::Text(text))) if ws.decode(&text).is_ok() => {
+                    let _ = frames.send(Arc::from(text.as_str()));
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    tracing::info!("server closed the connection, reconnecting");
+                    break;
+                }
+                Some(Err(e)) => {
+                    tracing::warn!(error = %e, "connection failed, reconnecting");
+                    break;
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+/// Identifies identical subscriptions regardless of JSON key order
+pub fn subscription_key(message: &SubscriptionMessage) -> String {
+    fn canonical(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Value::Object(keys.into_iter().map(|k| (k.clone(), canonical(&map[k]))).collect::<Map<_, _>>())
+            }
+            Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+            other => other.clone(),
+        }
+    }
+    json!({ "type": message.msg_type, "data": canonical(&message.data) }).to_string()
+}
+
+fn error_frame(message: &str) -> String {
+    let response = WebSocketResponse {
+        response_type: ResponseType::Error,
+        data: json!({ "message": message }),
+        received_at: None,
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// Value of the first header called `name` (case-insensitive) in `head`
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn price(address: &str) -> SubscriptionMessage {
+        serde_json::from_value(json!({
+            "type": "SUBSCRIBE_PRICE",
+            "data": { "queryType": "simple", "chartType": "1m", "address": address, "currency": "usd" },
+        }))
+        .unwrap()
+    }
+
+    const FRAME: &str = r#"{"type":"PRICE_DATA","data":{"address":"A","c":1.5}}"#;
+
+    /// A proxy whose upstreams connect to a local stand-in sending `FRAME`
+    /// for every subscription
+    async fn proxy(options: ProxyOptions) -> FanOutProxy {
+        let (url, _) = crate::connection::stand_in_upstream(vec![FRAME.to_string()]).await;
+        FanOutProxy::with_client(BirdeyeWebSocket::new("key").with_url(url), options)
+    }
+
+    async fn start(options: ProxyOptions) -> (FanOutProxy, std::net::SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let proxy = proxy(options).await;
+        let server = proxy.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+        (proxy, addr)
+    }
+
+    async fn http(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    async fn next_text<S>(socket: &mut S) -> String
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        match tokio::time::timeout(Duration::from_secs(5), socket.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => text.to_string(),
+            other => panic!("expected a text frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn subscription_keys_ignore_json_key_order() {
+        let reordered: SubscriptionMessage = serde_json::from_value(json!({
+            "type": "SUBSCRIBE_PRICE",
+            "data": { "currency": "usd", "address": "A", "chartType": "1m", "queryType": "simple" },
+        }))
+        .unwrap();
+        assert_eq!(subscription_key(&price("A")), subscription_key(&reordered));
+        assert_ne!(subscription_key(&price("A")), subscription_key(&price("B")));
+    }
+
+    #[test]
+    fn headers_are_matched_case_insensitively() {
+        let head = "GET / HTTP/1.1\r\nHost: x\r\nupgrade:  WebSocket \r\n\r\n";
+        assert_eq!(header(head, "Upgrade"), Some("WebSocket"));
+        assert_eq!(header(head, "sec-websocket-key"), None);
+    }
+
+    #[tokio::test]
+    async fn identical_subscriptions_share_one_upstream() {
+        let proxy = proxy(ProxyOptions::new().with_max_upstreams(1)).await;
+        let mut first = proxy.subscribe(&price("A")).unwrap();
+        let mut second = proxy.subscribe(&price("A")).unwrap();
+        assert_eq!(first.key(), second.key());
+        for subscription in [&mut first, &mut second] {
+            let frame = tokio::time::timeout(Duration::from_secs(5), subscription.recv()).await.unwrap();
+            assert_eq!(frame.as_deref(), Some(FRAME));
+        }
+        assert_eq!((proxy.upstream_count(), proxy.subscriber_count()), (1, 2));
+        assert!(proxy.subscribe(&price("B")).is_none());
+
+        drop(first);
+        assert_eq!((proxy.upstream_count(), proxy.subscriber_count()), (1, 1));
+        drop(second);
+        assert_eq!(proxy.upstream_count(), 0);
+        assert!(proxy.subscribe(&price("B")).is_some());
+    }
+
+    #[tokio::test]
+    async fn websocket_clients_subscribe_through_the_proxy() {
+        let (proxy, addr) = start(ProxyOptions::new().with_max_upstreams(1)).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/")).await.unwrap();
+
+        socket.send(Message::Text("not json".into())).await.unwrap();
+        assert!(next_text(&mut socket).await.contains("invalid subscription message"));
+
+        let subscribe = serde_json::to_string(&price("A")).unwrap();
+        socket.send(Message::Text(subscribe.into())).await.unwrap();
+        assert_eq!(next_text(&mut socket).await, FRAME);
+        socket.send(Message::Text(serde_json::to_string(&price("B")).unwrap().into())).await.unwrap();
+        assert!(next_text(&mut socket).await.contains("upstream connection limit reached"));
+        assert_eq!((proxy.upstream_count(), proxy.subscriber_count()), (1, 1));
+
+        let unsubscribe = json!({ "type": "UNSUBSCRIBE_PRICE", "data": {} }).to_string();
+        socket.send(Message::Text(unsubscribe.into())).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while proxy.upstream_count() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn routes_on_the_request_head() {
+        let (_, addr) = start(ProxyOptions::new()).await;
+        let response = http(addr, "GET /sse HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400") && response.contains("subscribe"));
+        let response = http(addr, "GET /other HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400") && response.contains("WebSocket upgrade"));
+
+        let (_, addr) = start(ProxyOptions::new().with_sse(false)).await;
+        let response = http(addr, "GET /sse HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        assert!(response.contains("WebSocket upgrade"));
+    }
+
+    #[tokio::test]
+    async fn sse_streams_forward_upstream_frames() {
+        let (proxy, addr) = start(ProxyOptions::new()).await;
+        let query = url::form_urlencoded::byte_serialize(serde_json::to_string(&price("A")).unwrap().as_bytes())
+            .collect::<String>();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET /sse?subscribe={query} HTTP/1.1\r\nHost: x\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !String::from_utf8_lossy(&response).contains(FRAME) {
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut chunk)).await.unwrap().unwrap();
+            assert!(read > 0, "stream ended early");
+            response.extend_from_slice(&chunk[..read]);
+        }
+        let response = String::from_utf8_lossy(&response);
+        assert!(response.starts_with("HTTP/1.1 200 OK") && response.contains("text/event-stream"));
+        assert!(response.contains(&format!("data: {FRAME}\n\n")));
+        assert_eq!(proxy.subscriber_count(), 1);
+    }
+}
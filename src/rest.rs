@@ -1,20 +1,164 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use crate::types::BirdeyeError;
+use crate::cache::{CacheStats, RestCache};
+#[cfg(feature = "metrics")]
+use crate::metrics::BirdeyeMetrics;
+use crate::types::{BirdeyeError, REDACTED};
+use crate::usage::UsageTracker;
 
 const API_BASE_URL: &str = "https://public-api.birdeye.so";
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct BirdeyeRest {
     api_key: String,
+    base_url: String,
     chain: String,
+    client: reqwest::Client,
+    cache: Option<Arc<RestCache>>,
+    usage: Option<UsageTracker>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<BirdeyeMetrics>,
+}
+
+impl fmt::Debug for BirdeyeRest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BirdeyeRest")
+            .field("api_key", &REDACTED)
+            .field("chain", &self.chain)
+            .field("cache", &self.cache)
+            .field("usage", &self.usage)
+            .finish_non_exhaustive()
+    }
 }
 
 impl BirdeyeRest {
     pub fn new(api_key: impl Into<String>, chain: impl Into<String>) -> Self {
         Self {
             api_key: api_key.into(),
+            base_url: API_BASE_URL.to_string(),
             chain: chain.into(),
+            client: reqwest::Client::new(),
+            cache: None,
+            usage: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Send requests to `base_url` instead of Birdeye, e.g. a local stand-in
+    #[cfg(test)]
+    pub(crate) fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Serve repeated requests from `cache`; clones share it
+    pub fn with_cache(mut self, cache: RestCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Count requests, bytes and compute units into `usage`
+    ///
+    /// Cache hits are free and not counted. A blocking budget makes
+    /// requests fail with `BirdeyeError::BudgetExceeded` once used up.
+    pub fn with_usage(mut self, usage: UsageTracker) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    pub fn usage(&self) -> Option<&UsageTracker> {
+        self.usage.as_ref()
+    }
+
+    /// Record request latency by endpoint and status into `metrics`
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: BirdeyeMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<&BirdeyeMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Issue an authenticated GET against `path_and_query` and decode the JSON body
+    async fn get<T: DeserializeOwned>(&self, path_and_query: &str) -> Result<T, BirdeyeError> {
+        let Some(cache) = &self.cache else {
+            return Ok(serde_json::from_str(&self.fetch(path_and_query).await?.1)?);
+        };
+
+        let key = format!("{}:{}", self.chain, path_and_query);
+        let ttl = match cache.lookup(&key, path_and_query).await {
+            Ok(body) => {
+                tracing::trace!(key, "cache hit");
+                return Ok(serde_json::from_str(&body)?);
+            }
+            Err(ttl) => ttl,
+        };
+        let (ok, body) = self.fetch(path_and_query).await?;
+        let value: serde_json::Value = serde_json::from_str(&body)?;
+        let data = T::deserialize(&value)?;
+        // Error payloads are not worth keeping around
+        if ok && value.get("success").and_then(serde_json::Value::as_bool) != Some(false) {
+            cache.store(&key, &body, ttl).await;
+        }
+        Ok(data)
+    }
+
+    /// Raw response body and whether the status was a success
+    #[tracing::instrument(
+        name = "rest_request",
+        skip_all,
+        fields(endpoint = path_and_query.split('?').next(), chain = %self.chain)
+    )]
+    async fn fetch(&self, path_and_query: &str) -> Result<(bool, String), BirdeyeError> {
+        if let Some(usage) = &self.usage {
+            usage.check()?;
+        }
+        let url = format!("{}{}", self.base_url, path_and_query);
+        let started = Instant::now();
+        let response = async {
+            let response = self
+                .client
+                .get(&url)
+                .header("accept", "application/json")
+                .header("x-chain", &self.chain)
+                .header("X-API-KEY", &self.api_key)
+                .send()
+                .await?;
+            let status = response.status();
+            Ok::<_, reqwest::Error>((status, response.text().await?))
+        }
+        .await;
+        let elapsed = started.elapsed();
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            let endpoint = path_and_query.split('?').next().unwrap_or_default();
+            let status = response.as_ref().ok().map(|(status, _)| status.as_u16());
+            metrics.record_request(endpoint, status, elapsed);
+        }
+        let (status, body) = response.inspect_err(|e| tracing::warn!(error = %e, "request failed"))?;
+        let latency_ms = elapsed.as_millis() as u64;
+        let ok = status.is_success();
+        if ok {
+            tracing::debug!(status = status.as_u16(), latency_ms, bytes = body.len(), "request completed");
+        } else {
+            tracing::warn!(status = status.as_u16(), latency_ms, bytes = body.len(), "request returned an error status");
+        }
+        if let Some(usage) = &self.usage {
+            usage.record_request(path_and_query, body.len());
         }
+        Ok((ok, body))
     }
 
     /// Fetch OHLCV data for a token
@@ -31,22 +175,11 @@ impl BirdeyeRest {
         time_from: i64,
         time_to: i64,
     ) -> Result<OHLCVResponse, BirdeyeError> {
-        let url = format!(
-            "{}/defi/ohlcv?address={}&type={}&time_from={}&time_to={}",
-            API_BASE_URL, address, interval_type, time_from, time_to
-        );
-
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&url)
-            .header("accept", "application/json")
-            .header("x-chain", &self.chain)
-            .header("X-API-KEY", &self.api_key)
-            .send()
-            .await?;
-
-        let data = response.json().await?;
-        Ok(data)
+        self.get(&format!(
+            "/defi/ohlcv?address={}&type={}&time_from={}&time_to={}",
+            address, interval_type, time_from, time_to
+        ))
+        .await
     }
 
     /// Get token overview
@@ -54,23 +187,35 @@ impl BirdeyeRest {
     /// # Arguments
     /// * `address` - Token address
     pub async fn get_token_overview(&self, address: &str) -> Result<TokenOverviewResponse, BirdeyeError> {
-        let url = format!("{}/defi/token_overview?address={}", API_BASE_URL, address);
-
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&url)
-            .header("accept", "application/json")
-            .header("x-chain", &self.chain)
-            .header("X-API-KEY", &self.api_key)
-            .send()
-            .await?;
-
-        let data = response.json().await?;
-        Ok(data)
+        self.get(&format!("/defi/token_overview?address={}", address)).await
+    }
+
+    /// Get token security information (authorities, holder concentration, ...)
+    ///
+    /// # Arguments
+    /// * `address` - Token address
+    pub async fn get_token_security(&self, address: &str) -> Result<TokenSecurityResponse, BirdeyeError> {
+        self.get(&format!("/defi/token_security?address={}", address)).await
+    }
+
+    /// Get the creation transaction and time of a token
+    ///
+    /// # Arguments
+    /// * `address` - Token address
+    pub async fn get_token_creation_info(&self, address: &str) -> Result<TokenCreationInfoResponse, BirdeyeError> {
+        self.get(&format!("/defi/token_creation_info?address={}", address)).await
+    }
+
+    /// Get the token balances held by a wallet
+    ///
+    /// # Arguments
+    /// * `wallet` - Wallet address
+    pub async fn get_wallet_portfolio(&self, wallet: &str) -> Result<WalletPortfolioResponse, BirdeyeError> {
+        self.get(&format!("/v1/wallet/token_list?wallet={}", wallet)).await
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OHLCVData {
     pub address: String,
     #[serde(rename = "c")]
@@ -88,18 +233,18 @@ pub struct OHLCVData {
     pub volume: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OHLCVResponse {
     pub success: bool,
     pub data: OHLCVResponseData,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OHLCVResponseData {
     pub items: Vec<OHLCVData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenExtensions {
     #[serde(rename = "coingeckoId")]
     pub coingecko_id: Option<String>,
@@ -115,7 +260,7 @@ pub struct TokenExtensions {
     pub medium: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenOverviewData {
     pub address: String,
     pub decimals: u8,
@@ -128,6 +273,10 @@ pub struct TokenOverviewData {
     pub price: f64,
     pub supply: f64,
     pub mc: f64,
+    #[serde(rename = "v24hUSD")]
+    pub v24h_usd: Option<f64>,
+    #[serde(rename = "priceChange24hPercent")]
+    pub price_change_24h_percent: Option<f64>,
     #[serde(rename = "lastTradeUnixTime")]
     pub last_trade_unix_time: i64,
     #[serde(rename = "lastTradeHumanTime")]
@@ -135,8 +284,165 @@ pub struct TokenOverviewData {
     // Add other fields as needed
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenOverviewResponse {
     pub success: bool,
     pub data: TokenOverviewData,
-} 
\ No newline at end of file
+} 
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WalletPortfolioItem {
+    pub address: String,
+    pub decimals: Option<u8>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    #[serde(rename = "uiAmount")]
+    pub ui_amount: f64,
+    #[serde(rename = "priceUsd")]
+    pub price_usd: Option<f64>,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+    #[serde(rename = "logoURI")]
+    pub logo_uri: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WalletPortfolioData {
+    pub wallet: String,
+    #[serde(rename = "totalUsd")]
+    pub total_usd: Option<f64>,
+    pub items: Vec<WalletPortfolioItem>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WalletPortfolioResponse {
+    pub success: bool,
+    pub data: WalletPortfolioData,
+}
+
+/// Token security report; fields follow the Solana response and are
+/// absent when Birdeye has no data
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenSecurityData {
+    #[serde(rename = "creatorAddress")]
+    pub creator_address: Option<String>,
+    /// Current mint authority, `None` once revoked
+    #[serde(rename = "ownerAddress")]
+    pub owner_address: Option<String>,
+    #[serde(rename = "creationTime")]
+    pub creation_time: Option<i64>,
+    #[serde(rename = "creatorPercentage")]
+    pub creator_percentage: Option<f64>,
+    #[serde(rename = "ownerPercentage")]
+    pub owner_percentage: Option<f64>,
+    #[serde(rename = "top10HolderPercent")]
+    pub top10_holder_percent: Option<f64>,
+    #[serde(rename = "top10UserPercent")]
+    pub top10_user_percent: Option<f64>,
+    pub freezeable: Option<bool>,
+    #[serde(rename = "freezeAuthority")]
+    pub freeze_authority: Option<String>,
+    #[serde(rename = "mutableMetadata")]
+    pub mutable_metadata: Option<bool>,
+    #[serde(rename = "isToken2022")]
+    pub is_token_2022: Option<bool>,
+    #[serde(rename = "transferFeeEnable")]
+    pub transfer_fee_enable: Option<bool>,
+    #[serde(rename = "nonTransferable")]
+    pub non_transferable: Option<bool>,
+    #[serde(rename = "totalSupply")]
+    pub total_supply: Option<f64>,
+    #[serde(rename = "lockInfo")]
+    pub lock_info: Option<serde_json::Value>,
+    #[serde(rename = "jupStrictList")]
+    pub jup_strict_list: Option<bool>,
+}
+
+impl TokenSecurityData {
+    pub fn has_mint_authority(&self) -> bool {
+        self.owner_address.is_some()
+    }
+
+    pub fn has_freeze_authority(&self) -> bool {
+        self.freeze_authority.is_some() || self.freezeable == Some(true)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenSecurityResponse {
+    pub success: bool,
+    pub data: TokenSecurityData,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenCreationInfoData {
+    #[serde(rename = "txHash")]
+    pub tx_hash: String,
+    pub slot: Option<u64>,
+    #[serde(rename = "tokenAddress")]
+    pub token_address: String,
+    pub decimals: Option<u8>,
+    pub owner: Option<String>,
+    #[serde(rename = "blockUnixTime")]
+    pub block_unix_time: i64,
+    #[serde(rename = "blockHumanTime")]
+    pub block_human_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenCreationInfoResponse {
+    pub success: bool,
+    pub data: TokenCreationInfoData,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::capture_logs;
+
+    const KEY: &str = "secret-api-key";
+
+    #[test]
+    fn debug_output_redacts_the_api_key() {
+        let debug = format!("{:?}", BirdeyeRest::new(KEY, "solana"));
+        assert!(debug.contains(REDACTED) && debug.contains("solana") && !debug.contains(KEY));
+    }
+
+    /// Local HTTP server answering one request with `status` and `body`,
+    /// returning its base URL and the request head
+    async fn stand_in(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let head = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let head = crate::http::read_request_head(&mut stream).await.unwrap();
+            crate::http::respond_with(&mut stream, status, "application/json", body).await.unwrap();
+            head
+        });
+        (base_url, head)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn request_logs_name_the_endpoint_but_not_the_api_key() {
+        let (base_url, head) = stand_in("200 OK", r#"{"success":true,"data":{"wallet":"W","totalUsd":null,"items":[]}}"#).await;
+        let rest = BirdeyeRest::new(KEY, "solana").with_base_url(base_url);
+        let (result, logs) = capture_logs(rest.get_wallet_portfolio("W")).await;
+        assert_eq!(result.unwrap().data.wallet, "W");
+        let head = head.await.unwrap().to_lowercase();
+        assert!(head.starts_with("get /v1/wallet/token_list?wallet=w ") && head.contains(&format!("x-api-key: {KEY}")));
+
+        assert!(logs.contains("rest_request{endpoint=\"/v1/wallet/token_list\" chain=solana}"));
+        assert!(logs.contains("request completed status=200 latency_ms="));
+        assert!(!logs.contains(KEY));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn error_statuses_are_logged_as_warnings() {
+        let (base_url, _) = stand_in("401 Unauthorized", r#"{"success":false,"message":"Unauthorized"}"#).await;
+        let rest = BirdeyeRest::new(KEY, "solana").with_base_url(base_url);
+        let (result, logs) = capture_logs(rest.get_token_overview("TOKEN")).await;
+        assert!(result.is_err());
+        assert!(logs.contains("WARN") && logs.contains("request returned an error status status=401 latency_ms="));
+        assert!(logs.contains("/defi/token_overview") && !logs.contains(KEY));
+    }
+}
@@ -0,0 +1,665 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::RETRY_AFTER;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::large_trades::LargeTradeData;
+use crate::new_pair::NewPairData;
+use crate::types::BirdeyeError;
+use crate::whale::WhaleAlert;
+
+/// Discord rejects messages longer than this many characters
+const DISCORD_MAX_CONTENT: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossDirection {
+    Above,
+    Below,
+}
+
+/// A price crossing a configured threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceCrossing {
+    pub address: String,
+    pub symbol: String,
+    pub threshold: f64,
+    pub price: f64,
+    pub direction: CrossDirection,
+    pub unix_time: i64,
+}
+
+/// Events that can be forwarded to a `Sink`
+#[derive(Debug, Clone)]
+pub enum AlertEvent {
+    LargeTrade(LargeTradeData),
+    NewPair(NewPairData),
+    PriceCrossing(PriceCrossing),
+    Whale(WhaleAlert),
+}
+
+impl AlertEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AlertEvent::LargeTrade(_) => "large_trade",
+            AlertEvent::NewPair(_) => "new_pair",
+            AlertEvent::PriceCrossing(_) => "price_crossing",
+            AlertEvent::Whale(_) => "whale",
+        }
+    }
+
+    pub fn to_value(&self) -> Result<Value, BirdeyeError> {
+        Ok(match self {
+            AlertEvent::LargeTrade(data) => serde_json::to_value(data)?,
+            AlertEvent::NewPair(data) => serde_json::to_value(data)?,
+            AlertEvent::PriceCrossing(data) => serde_json::to_value(data)?,
+            AlertEvent::Whale(data) => serde_json::to_value(data)?,
+        })
+    }
+}
+
+/// Text template with `{field}` placeholders
+///
+/// Placeholders are dotted paths into the serialized event, using the
+/// Birdeye field names, e.g. `{from.symbol}` or `{volumeUSD}`. Unknown
+/// paths render as `?`.
+#[derive(Debug, Clone)]
+pub struct MessageTemplate(String);
+
+impl MessageTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    pub fn render(&self, data: &Value) -> String {
+        let mut out = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            // An unclosed `{` is kept as literal text
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            let path = &rest[start + 1..start + len];
+            out.push_str(&lookup(data, path));
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn lookup(data: &Value, path: &str) -> String {
+    let value = path.split('.').try_fold(data, |value, key| value.get(key));
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => match n.as_f64() {
+            Some(f) if n.is_f64() => format_number(f),
+            _ => n.to_string(),
+        },
+        Some(Value::Null) | None => "?".to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.abs() >= 1.0 {
+        format!("{:.2}", value)
+    } else {
+        let formatted = format!("{:.8}", value);
+        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Templates used for each `AlertEvent` kind
+#[derive(Debug, Clone)]
+pub struct Templates {
+    pub large_trade: MessageTemplate,
+    pub new_pair: MessageTemplate,
+    pub price_crossing: MessageTemplate,
+    pub whale: MessageTemplate,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            large_trade: MessageTemplate::new(
+                "Large trade ${volumeUSD}: {from.uiAmount} {from.symbol} -> {to.uiAmount} {to.symbol} on {source} ({txHash})",
+            ),
+            new_pair: MessageTemplate::new("New pair {name} on {source}: {base.symbol}/{quote.symbol} ({address})"),
+            price_crossing: MessageTemplate::new("{symbol} crossed {direction} {threshold}: now {price}"),
+            whale: MessageTemplate::new(
+                "Whale {side} {token_amount} {token_symbol} (${volume_usd}) by {owner} on {source} ({tx_hash})",
+            ),
+        }
+    }
+}
+
+impl Templates {
+    pub fn render(&self, event: &AlertEvent) -> Result<AlertMessage, BirdeyeError> {
+        let template = match event {
+            AlertEvent::LargeTrade(_) => &self.large_trade,
+            AlertEvent::NewPair(_) => &self.new_pair,
+            AlertEvent::PriceCrossing(_) => &self.price_crossing,
+            AlertEvent::Whale(_) => &self.whale,
+        };
+        let data = event.to_value()?;
+        Ok(AlertMessage {
+            kind: event.kind(),
+            text: template.render(&data),
+            data,
+        })
+    }
+}
+
+/// A rendered alert ready to be delivered
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertMessage {
+    pub kind: &'static str,
+    pub text: String,
+    pub data: Value,
+}
+
+/// Destination for alert messages
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Deliver a batch of messages
+    ///
+    /// An error causes the batch to be retried; return
+    /// `BirdeyeError::PartialDelivery` once some messages went out so only
+    /// the rest is retried.
+    async fn send_batch(&self, messages: &[AlertMessage]) -> Result<(), BirdeyeError>;
+}
+
+/// Turn a non-success response into `BirdeyeError::HttpStatus`
+fn check_status(response: reqwest::Response) -> Result<(), BirdeyeError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    // Only the delay-seconds form; HTTP dates fall back to the backoff
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64);
+    Err(BirdeyeError::HttpStatus {
+        status: status.as_u16(),
+        retry_after,
+    })
+}
+
+/// POSTs `{"messages": [...]}` with the full event data to any URL
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    async fn send_batch(&self, messages: &[AlertMessage]) -> Result<(), BirdeyeError> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({ "messages": messages }));
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        check_status(request.send().await?)
+    }
+}
+
+/// Slack incoming-webhook format: one `text` post per batch
+#[derive(Debug, Clone)]
+pub struct SlackSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SlackSink {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: webhook_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for SlackSink {
+    async fn send_batch(&self, messages: &[AlertMessage]) -> Result<(), BirdeyeError> {
+        let text = messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>().join("\n");
+        let response = self.client.post(&self.url).json(&serde_json::json!({ "text": text })).send().await?;
+        check_status(response)
+    }
+}
+
+/// Discord incoming-webhook format, split to respect the content limit
+#[derive(Debug, Clone)]
+pub struct DiscordSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl DiscordSink {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: webhook_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for DiscordSink {
+    async fn send_batch(&self, messages: &[AlertMessage]) -> Result<(), BirdeyeError> {
+        // Chunk contents with the number of messages in each
+        let mut chunks: Vec<(String, usize)> = Vec::new();
+        for message in messages {
+            let text: String = message.text.chars().take(DISCORD_MAX_CONTENT).collect();
+            match chunks.last_mut() {
+                Some((chunk, count)) if chunk.chars().count() + 1 + text.chars().count() <= DISCORD_MAX_CONTENT => {
+                    chunk.push('\n');
+                    chunk.push_str(&text);
+                    *count += 1;
+                }
+                _ => chunks.push((text, 1)),
+            }
+        }
+        let mut delivered = 0;
+        for (content, count) in chunks {
+            let request = self.client.post(&self.url).json(&serde_json::json!({ "content": content }));
+            let result = match request.send().await {
+                Ok(response) => check_status(response),
+                Err(e) => Err(e.into()),
+            };
+            match result {
+                Ok(()) => delivered += count,
+                Err(e) if delivered == 0 => return Err(e),
+                Err(e) => {
+                    return Err(BirdeyeError::PartialDelivery {
+                        delivered,
+                        source: Box::new(e),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints each message as one JSON line on stdout
+#[derive(Debug, Clone, Default)]
+pub struct StdoutSink;
+
+#[async_trait]
+impl Sink for StdoutSink {
+    async fn send_batch(&self, messages: &[AlertMessage]) -> Result<(), BirdeyeError> {
+        for message in messages {
+            println!("{}", serde_json::to_string(message)?);
+        }
+        Ok(())
+    }
+}
+
+/// Exponential backoff for failed deliveries
+///
+/// Client errors other than 429 are not retried; a 429 waits for its
+/// `Retry-After` when given, capped at `max_backoff`. Messages a sink reported as delivered are
+/// not sent again.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts >= 1, "max_attempts must be at least 1");
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Deliver `messages` through `sink`, retrying with backoff
+    ///
+    /// If some messages went out before giving up, the error is a
+    /// `BirdeyeError::PartialDelivery` counting them from the start of
+    /// `messages`.
+    pub async fn send(&self, sink: &dyn Sink, messages: &[AlertMessage]) -> Result<(), BirdeyeError> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        let mut delivered = 0;
+        loop {
+            let error = match sink.send_batch(&messages[delivered..]).await {
+                Ok(()) => return Ok(()),
+                Err(BirdeyeError::PartialDelivery { delivered: count, source }) => {
+                    delivered = (delivered + count).min(messages.len());
+                    *source
+                }
+                Err(e) => e,
+            };
+            match retry_delay(&error, backoff, self.max_backoff) {
+                Some(delay) if attempt < self.max_attempts => {
+                    tracing::debug!(error = %error, attempt, retry_in_ms = delay.as_millis() as u64, "retrying alert delivery");
+                    tokio::time::sleep(delay).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                _ if delivered > 0 => {
+                    return Err(BirdeyeError::PartialDelivery {
+                        delivered,
+                        source: Box::new(error),
+                    })
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+}
+
+/// How long to wait before retrying after `error`; `None` if retrying
+/// cannot help
+fn retry_delay(error: &BirdeyeError, backoff: Duration, max_backoff: Duration) -> Option<Duration> {
+    match error {
+        BirdeyeError::HttpStatus { status: 429, retry_after } => Some(retry_after.unwrap_or(backoff).min(max_backoff)),
+        BirdeyeError::HttpStatus { status, .. } if (400..500).contains(status) => None,
+        BirdeyeError::Json(_) => None,
+        _ => Some(backoff),
+    }
+}
+
+/// Batches, renders and delivers `AlertEvent`s to a sink
+pub struct AlertDispatcher {
+    sink: Arc<dyn Sink>,
+    templates: Templates,
+    retry: RetryPolicy,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl AlertDispatcher {
+    pub fn new(sink: impl Sink + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            templates: Templates::default(),
+            retry: RetryPolicy::default(),
+            batch_size: 20,
+            flush_interval: Duration::from_secs(2),
+        }
+    }
+
+    pub fn with_templates(mut self, templates: Templates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Flush once `batch_size` messages are queued or `flush_interval` elapsed
+    pub fn with_batching(mut self, batch_size: usize, flush_interval: Duration) -> Self {
+        assert!(batch_size >= 1, "batch_size must be at least 1");
+        self.batch_size = batch_size;
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Render and deliver a single event immediately
+    pub async fn dispatch(&self, event: &AlertEvent) -> Result<(), BirdeyeError> {
+        let message = self.templates.render(event)?;
+        self.retry.send(self.sink.as_ref(), &[message]).await
+    }
+
+    /// Run the dispatcher in the background; messages that still fail
+    /// after retrying are passed to `on_error` and dropped
+    pub fn spawn<F>(self, mut on_error: F) -> (mpsc::Sender<AlertEvent>, JoinHandle<()>)
+    where
+        F: FnMut(BirdeyeError, Vec<AlertMessage>) + Send + 'static,
+    {
+        let (sender, mut events) = mpsc::channel::<AlertEvent>(self.batch_size * 16);
+        let handle = tokio::spawn(async move {
+            let mut batch: Vec<AlertMessage> = Vec::with_capacity(self.batch_size);
+            let mut ticker = tokio::time::interval(self.flush_interval);
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Some(event) => {
+                            match self.templates.render(&event) {
+                                Ok(message) => batch.push(message),
+                                Err(e) => on_error(e, Vec::new()),
+                            }
+                            if batch.len() >= self.batch_size {
+                                self.flush(&mut batch, &mut on_error).await;
+                            }
+                        }
+                        None => {
+                            self.flush(&mut batch, &mut on_error).await;
+                            break;
+                        }
+                    },
+                    _ = ticker.tick() => self.flush(&mut batch, &mut on_error).await,
+                }
+            }
+        });
+        (sender, handle)
+    }
+
+    async fn flush<F>(&self, batch: &mut Vec<AlertMessage>, on_error: &mut F)
+    where
+        F: FnMut(BirdeyeError, Vec<AlertMessage>),
+    {
+        if batch.is_empty() {
+            return;
+        }
+        let mut messages = std::mem::take(batch);
+        if let Err(e) = self.retry.send(self.sink.as_ref(), &messages).await {
+            if let BirdeyeError::PartialDelivery { delivered, .. } = &e {
+                messages.drain(..*delivered);
+            }
+            on_error(e, messages);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Local HTTP server answering with `statuses` in turn, recording the
+    /// request bodies
+    async fn stand_in(statuses: Vec<(u16, Option<&'static str>)>) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        tokio::spawn(async move {
+            for (status, retry_after) in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                let body_start = loop {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map_or(0, |value| value.trim().parse().unwrap());
+                while request.len() < body_start + length {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                recorded.lock().unwrap().push(serde_json::from_slice(&request[body_start..]).unwrap());
+
+                let retry_after = retry_after.map_or(String::new(), |secs| format!("Retry-After: {secs}\r\n"));
+                let response = format!("HTTP/1.1 {status} X\r\n{retry_after}Content-Length: 0\r\nConnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, bodies)
+    }
+
+    fn message(text: String) -> AlertMessage {
+        AlertMessage {
+            kind: "test",
+            text,
+            data: Value::Null,
+        }
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn discord_retries_only_undelivered_chunks() {
+        let (url, bodies) = stand_in(vec![(204, None), (500, None), (204, None), (204, None)]).await;
+        let messages: Vec<_> = ["a", "b", "c"].iter().map(|c| message(c.repeat(1500))).collect();
+        fast_retry().send(&DiscordSink::new(url), &messages).await.unwrap();
+
+        let bodies = bodies.lock().unwrap();
+        let firsts: Vec<String> = bodies.iter().map(|b| b["content"].as_str().unwrap()[..1].to_string()).collect();
+        assert_eq!(firsts, ["a", "b", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn discord_batches_short_messages() {
+        let (url, bodies) = stand_in(vec![(204, None)]).await;
+        let messages = vec![message("one".to_string()), message("two".to_string())];
+        DiscordSink::new(url).send_batch(&messages).await.unwrap();
+        assert_eq!(bodies.lock().unwrap()[0]["content"], "one\ntwo");
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let (url, bodies) = stand_in(vec![(400, None), (204, None)]).await;
+        let result = fast_retry().send(&WebhookSink::new(url), &[message("x".to_string())]).await;
+        assert!(matches!(result, Err(BirdeyeError::HttpStatus { status: 400, .. })));
+        assert_eq!(bodies.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried() {
+        let (url, bodies) = stand_in(vec![(503, None), (200, None)]).await;
+        fast_retry().send(&SlackSink::new(url), &[message("x".to_string())]).await.unwrap();
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[1]["text"], "x");
+    }
+
+    #[tokio::test]
+    async fn rate_limits_honour_retry_after() {
+        let (url, bodies) = stand_in(vec![(429, Some("1")), (200, None)]).await;
+        let started = Instant::now();
+        let retry = RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_secs(5));
+        retry.send(&WebhookSink::new(url), &[message("x".to_string())]).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(bodies.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_capped_at_max_backoff() {
+        let (url, bodies) = stand_in(vec![(429, Some("3600")), (200, None)]).await;
+        let started = Instant::now();
+        fast_retry().send(&WebhookSink::new(url), &[message("x".to_string())]).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(bodies.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn dispatcher_reports_only_undelivered_messages() {
+        let (url, _) = stand_in(vec![(204, None), (400, None)]).await;
+        let failed = Arc::new(Mutex::new(Vec::new()));
+        let reported = failed.clone();
+        let dispatcher = AlertDispatcher::new(DiscordSink::new(url))
+            .with_retry(fast_retry())
+            .with_templates(Templates {
+                price_crossing: MessageTemplate::new("{symbol}"),
+                ..Templates::default()
+            })
+            .with_batching(2, Duration::from_secs(60));
+        let (events, handle) = dispatcher.spawn(move |_, messages| {
+            reported.lock().unwrap().extend(messages.into_iter().map(|m| m.text));
+        });
+        for symbol in ["A", "B"] {
+            let crossing = PriceCrossing {
+                address: symbol.to_string(),
+                symbol: symbol.repeat(1500),
+                threshold: 1.0,
+                price: 2.0,
+                direction: CrossDirection::Above,
+                unix_time: 0,
+            };
+            events.send(AlertEvent::PriceCrossing(crossing)).await.unwrap();
+        }
+        drop(events);
+        handle.await.unwrap();
+        assert_eq!(*failed.lock().unwrap(), ["B".repeat(1500)]);
+    }
+
+    #[test]
+    fn templates_render_dotted_paths() {
+        let data = serde_json::json!({ "from": { "symbol": "SOL", "uiAmount": 1.5 }, "volumeUSD": 0.000123 });
+        let template = MessageTemplate::new("{from.uiAmount} {from.symbol} ${volumeUSD} {missing}");
+        assert_eq!(template.render(&data), "1.50 SOL $0.000123 ?");
+    }
+
+    #[test]
+    fn templates_keep_unbalanced_braces() {
+        let data = serde_json::json!({ "b": "x" });
+        assert_eq!(MessageTemplate::new("a {b").render(&data), "a {b");
+        assert_eq!(MessageTemplate::new("{b} and {b").render(&data), "x and {b");
+        assert_eq!(MessageTemplate::new("a } {b}").render(&data), "a } x");
+    }
+}
@@ -0,0 +1,662 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::large_trades::LargeTradeData;
+use crate::new_pair::NewPairData;
+use crate::price::{Candle, PriceData};
+use crate::rest::OHLCVData;
+use crate::token_listing::TokenListingData;
+use crate::txs::TransactionData;
+use crate::types::BirdeyeError;
+use crate::wallet_txs::WalletTxData;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS candles (
+    address TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    unix_time INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume REAL NOT NULL,
+    PRIMARY KEY (address, interval, unix_time)
+);
+CREATE TABLE IF NOT EXISTS trades (
+    tx_hash TEXT NOT NULL,
+    source TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    block_unix_time INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    pool_address TEXT NOT NULL,
+    volume_usd REAL NOT NULL,
+    from_address TEXT NOT NULL,
+    from_symbol TEXT NOT NULL,
+    from_amount REAL NOT NULL,
+    from_price REAL,
+    to_address TEXT NOT NULL,
+    to_symbol TEXT NOT NULL,
+    to_amount REAL NOT NULL,
+    to_price REAL,
+    PRIMARY KEY (tx_hash, source, pool_address, from_address, to_address)
+);
+CREATE INDEX IF NOT EXISTS trades_from_time ON trades (from_address, block_unix_time);
+CREATE INDEX IF NOT EXISTS trades_to_time ON trades (to_address, block_unix_time);
+CREATE TABLE IF NOT EXISTS new_pairs (
+    address TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    source TEXT NOT NULL,
+    base_address TEXT NOT NULL,
+    base_symbol TEXT NOT NULL,
+    quote_address TEXT NOT NULL,
+    quote_symbol TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    block_time INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS listings (
+    address TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    liquidity TEXT NOT NULL,
+    liquidity_added_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS wallet_txs (
+    tx_hash TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    block_unix_time INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    pool_address TEXT,
+    volume_usd REAL NOT NULL,
+    network TEXT NOT NULL,
+    raw TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS wallet_txs_owner_time ON wallet_txs (owner, block_unix_time);
+";
+
+/// Events that can be persisted by `SqliteStore`
+#[derive(Debug)]
+pub enum StoredEvent {
+    /// A candle with its interval, e.g. `"1m"`
+    Candle { interval: String, candle: Candle },
+    Price(PriceData),
+    Ohlcv(OHLCVData),
+    Transaction(TransactionData),
+    LargeTrade(LargeTradeData),
+    NewPair(NewPairData),
+    Listing(TokenListingData),
+    WalletTx(WalletTxData),
+}
+
+/// A row of the `trades` table
+#[derive(Debug, Clone)]
+pub struct StoredTrade {
+    pub tx_hash: String,
+    /// `"txs"` or `"large_trade"`
+    pub kind: String,
+    pub block_unix_time: i64,
+    pub owner: String,
+    pub source: String,
+    pub pool_address: Option<String>,
+    pub volume_usd: f64,
+    pub from_address: String,
+    pub from_symbol: String,
+    pub from_amount: f64,
+    pub from_price: Option<f64>,
+    pub to_address: String,
+    pub to_symbol: String,
+    pub to_amount: f64,
+    pub to_price: Option<f64>,
+}
+
+/// A row of the `new_pairs` table
+#[derive(Debug, Clone)]
+pub struct StoredNewPair {
+    pub address: String,
+    pub name: String,
+    pub source: String,
+    pub base_address: String,
+    pub base_symbol: String,
+    pub quote_address: String,
+    pub quote_symbol: String,
+    pub tx_hash: String,
+    pub block_time: i64,
+}
+
+/// SQLite persistence for streamed and REST data
+///
+/// Inserts are upserts keyed by `(tx_hash, source, pool_address,
+/// from_address, to_address)` (trades), `tx_hash` (wallet transactions),
+/// `address` (pairs, listings) or `(address, interval, unix_time)`
+/// (candles), so replaying the same events is harmless. Each hop of a
+/// multi-hop swap is its own trade row; events carry no leg index, so two
+/// hops through the same pool and tokens within one transaction are stored
+/// as one.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BirdeyeError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, BirdeyeError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, BirdeyeError> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn insert(&mut self, event: &StoredEvent) -> Result<(), BirdeyeError> {
+        self.insert_batch(std::slice::from_ref(event))
+    }
+
+    /// Write `events` in a single transaction
+    pub fn insert_batch(&mut self, events: &[StoredEvent]) -> Result<(), BirdeyeError> {
+        let tx = self.conn.transaction()?;
+        for event in events {
+            match event {
+                StoredEvent::Candle { interval, candle } => upsert_candle(&tx, interval, candle)?,
+                StoredEvent::Price(price) => upsert_candle(&tx, &price.chart_type, &Candle::from(price))?,
+                StoredEvent::Ohlcv(ohlcv) => upsert_candle(&tx, &ohlcv.r#type, &Candle::from(ohlcv))?,
+                StoredEvent::Transaction(data) => upsert_trade(
+                    &tx,
+                    &TradeRow {
+                        tx_hash: &data.tx_hash,
+                        source: &data.source,
+                        kind: "txs",
+                        block_unix_time: data.block_unix_time,
+                        owner: &data.owner,
+                        pool_address: data.pool_address.as_deref(),
+                        volume_usd: data.volume_usd,
+                        from: (&data.from.address, &data.from.symbol, data.from.ui_amount, data.from.price),
+                        to: (&data.to.address, &data.to.symbol, data.to.ui_amount, data.to.price),
+                    },
+                )?,
+                StoredEvent::LargeTrade(data) => upsert_trade(
+                    &tx,
+                    &TradeRow {
+                        tx_hash: &data.tx_hash,
+                        source: &data.source,
+                        kind: "large_trade",
+                        block_unix_time: data.block_unix_time,
+                        owner: &data.owner,
+                        pool_address: Some(&data.pool_address),
+                        volume_usd: data.volume_usd,
+                        from: (&data.from.address, &data.from.symbol, data.from.ui_amount, data.from.price),
+                        to: (&data.to.address, &data.to.symbol, data.to.ui_amount, data.to.price),
+                    },
+                )?,
+                StoredEvent::NewPair(data) => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO new_pairs
+                         (address, name, source, base_address, base_symbol, quote_address, quote_symbol, tx_hash, block_time)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            data.address,
+                            data.name,
+                            data.source,
+                            data.base.address,
+                            data.base.symbol,
+                            data.quote.address,
+                            data.quote.symbol,
+                            data.tx_hash,
+                            data.block_time,
+                        ],
+                    )?;
+                }
+                StoredEvent::Listing(data) => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO listings
+                         (address, name, symbol, decimals, liquidity, liquidity_added_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            data.address,
+                            data.name,
+                            data.symbol,
+                            data.decimals,
+                            data.liquidity,
+                            data.liquidity_added_at,
+                        ],
+                    )?;
+                }
+                StoredEvent::WalletTx(data) => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO wallet_txs
+                         (tx_hash, owner, tx_type, block_unix_time, source, pool_address, volume_usd, network, raw)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            data.tx_hash,
+                            data.owner,
+                            data.tx_type,
+                            data.block_unix_time,
+                            data.source,
+                            data.pool_address,
+                            data.volume_usd,
+                            data.network,
+                            serde_json::to_string(data)?,
+                        ],
+                    )?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Candles of `address` at `interval` with `time_from <= unix_time <= time_to`
+    pub fn candles(&self, address: &str, interval: &str, time_from: i64, time_to: i64) -> Result<Vec<Candle>, BirdeyeError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT address, unix_time, open, high, low, close, volume FROM candles
+             WHERE address = ?1 AND interval = ?2 AND unix_time BETWEEN ?3 AND ?4
+             ORDER BY unix_time",
+        )?;
+        let rows = stmt.query_map(params![address, interval, time_from, time_to], |row| {
+            Ok(Candle {
+                address: row.get(0)?,
+                symbol: None,
+                unix_time: row.get(1)?,
+                open: row.get(2)?,
+                high: row.get(3)?,
+                low: row.get(4)?,
+                close: row.get(5)?,
+                volume: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Trades where `token` is on either side, oldest first; the hops of a
+    /// transaction come in the order they were first stored
+    pub fn trades(&self, token: &str, time_from: i64, time_to: i64) -> Result<Vec<StoredTrade>, BirdeyeError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tx_hash, source, kind, block_unix_time, owner, NULLIF(pool_address, ''), volume_usd,
+                    from_address, from_symbol, from_amount, from_price, to_address, to_symbol, to_amount, to_price
+             FROM trades
+             WHERE (from_address = ?1 OR to_address = ?1) AND block_unix_time BETWEEN ?2 AND ?3
+             ORDER BY block_unix_time, tx_hash, rowid",
+        )?;
+        let rows = stmt.query_map(params![token, time_from, time_to], |row| {
+            Ok(StoredTrade {
+                tx_hash: row.get(0)?,
+                source: row.get(1)?,
+                kind: row.get(2)?,
+                block_unix_time: row.get(3)?,
+                owner: row.get(4)?,
+                pool_address: row.get(5)?,
+                volume_usd: row.get(6)?,
+                from_address: row.get(7)?,
+                from_symbol: row.get(8)?,
+                from_amount: row.get(9)?,
+                from_price: row.get(10)?,
+                to_address: row.get(11)?,
+                to_symbol: row.get(12)?,
+                to_amount: row.get(13)?,
+                to_price: row.get(14)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Pairs created at or after `since`, newest first
+    pub fn new_pairs_since(&self, since: i64) -> Result<Vec<StoredNewPair>, BirdeyeError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT address, name, source, base_address, base_symbol, quote_address, quote_symbol, tx_hash, block_time
+                 FROM new_pairs WHERE block_time >= ?1 ORDER BY block_time DESC",
+            )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(StoredNewPair {
+                address: row.get(0)?,
+                name: row.get(1)?,
+                source: row.get(2)?,
+                base_address: row.get(3)?,
+                base_symbol: row.get(4)?,
+                quote_address: row.get(5)?,
+                quote_symbol: row.get(6)?,
+                tx_hash: row.get(7)?,
+                block_time: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Listings whose liquidity was added at or after `since`, newest first
+    pub fn listings_since(&self, since: i64) -> Result<Vec<TokenListingData>, BirdeyeError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT address, decimals, name, symbol, liquidity, liquidity_added_at FROM listings
+             WHERE liquidity_added_at >= ?1 ORDER BY liquidity_added_at DESC",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(TokenListingData {
+                address: row.get(0)?,
+                decimals: row.get(1)?,
+                name: row.get(2)?,
+                symbol: row.get(3)?,
+                liquidity: row.get(4)?,
+                liquidity_added_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Wallet transactions of `owner`, oldest first
+    pub fn wallet_txs(&self, owner: &str, time_from: i64, time_to: i64) -> Result<Vec<WalletTxData>, BirdeyeError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT raw FROM wallet_txs WHERE owner = ?1 AND block_unix_time BETWEEN ?2 AND ?3
+             ORDER BY block_unix_time",
+        )?;
+        let raws = stmt
+            .query_map(params![owner, time_from, time_to], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        raws.iter()
+            .map(|raw| Ok(serde_json::from_str(raw)?))
+            .collect()
+    }
+
+    /// Unix time of the newest stored candle, e.g. to resume a backfill
+    pub fn latest_candle_time(&self, address: &str, interval: &str) -> Result<Option<i64>, BirdeyeError> {
+        let time = self
+            .conn
+            .query_row(
+                "SELECT MAX(unix_time) FROM candles WHERE address = ?1 AND interval = ?2",
+                params![address, interval],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()?
+            .flatten();
+        Ok(time)
+    }
+}
+
+/// Address, symbol, UI amount and price of one side of a trade
+type TradeSide<'a> = (&'a str, &'a str, f64, Option<f64>);
+
+struct TradeRow<'a> {
+    tx_hash: &'a str,
+    source: &'a str,
+    kind: &'a str,
+    block_unix_time: i64,
+    owner: &'a str,
+    pool_address: Option<&'a str>,
+    volume_usd: f64,
+    from: TradeSide<'a>,
+    to: TradeSide<'a>,
+}
+
+/// Upsert one hop; updating in place keeps its rowid, and with it the
+/// order of the hops
+fn upsert_trade(tx: &rusqlite::Transaction<'_>, row: &TradeRow<'_>) -> Result<(), BirdeyeError> {
+    let (from_address, from_symbol, from_amount, from_price) = row.from;
+    let (to_address, to_symbol, to_amount, to_price) = row.to;
+    tx.execute(
+        "INSERT INTO trades
+         (tx_hash, source, kind, block_unix_time, owner, pool_address, volume_usd,
+          from_address, from_symbol, from_amount, from_price, to_address, to_symbol, to_amount, to_price)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+         ON CONFLICT (tx_hash, source, pool_address, from_address, to_address) DO UPDATE SET
+             kind = excluded.kind, block_unix_time = excluded.block_unix_time, owner = excluded.owner,
+             volume_usd = excluded.volume_usd, from_symbol = excluded.from_symbol,
+             from_amount = excluded.from_amount, from_price = excluded.from_price,
+             to_symbol = excluded.to_symbol, to_amount = excluded.to_amount, to_price = excluded.to_price",
+        params![
+            row.tx_hash,
+            row.source,
+            row.kind,
+            row.block_unix_time,
+            row.owner,
+            row.pool_address.unwrap_or_default(),
+            row.volume_usd,
+            from_address,
+            from_symbol,
+            from_amount,
+            from_price,
+            to_address,
+            to_symbol,
+            to_amount,
+            to_price,
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_candle(tx: &rusqlite::Transaction<'_>, interval: &str, candle: &Candle) -> Result<(), BirdeyeError> {
+    tx.execute(
+        "INSERT INTO candles (address, interval, unix_time, open, high, low, close, volume)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (address, interval, unix_time) DO UPDATE SET
+             open = excluded.open, high = excluded.high, low = excluded.low,
+             close = excluded.close, volume = excluded.volume",
+        params![
+            candle.address,
+            interval,
+            candle.unix_time,
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.volume,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Options for `spawn_writer`
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+    pub channel_capacity: usize,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            flush_interval: Duration::from_secs(1),
+            channel_capacity: 10_000,
+        }
+    }
+}
+
+impl WriterOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size >= 1, "batch_size must be at least 1");
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+}
+
+/// Batch events from the returned sender into `store`
+///
+/// Writes run on the blocking thread pool. The task ends, after a final
+/// flush, once every sender is dropped; the first write error, or a panic
+/// on the blocking thread, stops it.
+pub fn spawn_writer(
+    store: SqliteStore,
+    options: WriterOptions,
+) -> (mpsc::Sender<StoredEvent>, JoinHandle<Result<(), BirdeyeError>>) {
+    let (sender, mut events) = mpsc::channel(options.channel_capacity);
+    let store = Arc::new(Mutex::new(store));
+
+    let handle = tokio::spawn(async move {
+        let mut batch = Vec::with_capacity(options.batch_size);
+        let mut ticker = tokio::time::interval(options.flush_interval);
+        loop {
+            let closed = tokio::select! {
+                event = events.recv() => match event {
+                    Some(event) => {
+                        batch.push(event);
+                        if batch.len() < options.batch_size {
+                            continue;
+                        }
+                        false
+                    }
+                    None => true,
+                },
+                _ = ticker.tick() => false,
+            };
+
+            if !batch.is_empty() {
+                let pending = std::mem::replace(&mut batch, Vec::with_capacity(options.batch_size));
+                let store = store.clone();
+                tokio::task::spawn_blocking(move || store.lock().unwrap().insert_batch(&pending)).await??;
+            }
+            if closed {
+                return Ok(());
+            }
+        }
+    });
+    (sender, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A hop of `hash` swapping `ui_amount` of `from` into `to`
+    fn hop(hash: &str, from: &str, to: &str, ui_amount: f64) -> StoredEvent {
+        let side = |address: &str, type_swap: &str| {
+            json!({
+                "symbol": address, "decimals": 6, "address": address, "amount": 0,
+                "type": "", "typeSwap": type_swap, "uiAmount": ui_amount, "price": null,
+                "nearestPrice": null, "changeAmount": 0, "uiChangeAmount": 0.0,
+            })
+        };
+        StoredEvent::Transaction(
+            serde_json::from_value(json!({
+                "blockUnixTime": 100, "owner": "alice", "source": "jupiter", "txHash": hash,
+                "alias": null, "isTradeOnBe": false, "platform": "", "volumeUSD": 10.0,
+                "poolAddress": format!("{from}-{to}"),
+                "from": side(from, "from"), "to": side(to, "to"),
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn candle(unix_time: i64, close: f64) -> StoredEvent {
+        StoredEvent::Candle {
+            interval: "1m".to_string(),
+            candle: Candle {
+                address: "T".to_string(),
+                symbol: None,
+                unix_time,
+                open: 1.0,
+                high: close.max(1.0),
+                low: close.min(1.0),
+                close,
+                volume: 5.0,
+            },
+        }
+    }
+
+    #[test]
+    fn multi_hop_swaps_keep_every_leg() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .insert_batch(&[hop("tx", "SOL", "USDC", 1.0), hop("tx", "USDC", "BONK", 1.0)])
+            .unwrap();
+
+        let legs = store.trades("USDC", 0, 200).unwrap();
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].pool_address.as_deref(), Some("SOL-USDC"));
+        assert_eq!((legs[1].from_address.as_str(), legs[1].to_address.as_str()), ("USDC", "BONK"));
+    }
+
+    #[test]
+    fn replaying_trades_is_harmless() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.insert(&hop("tx", "SOL", "USDC", 1.0)).unwrap();
+        store.insert(&hop("tx", "USDC", "BONK", 1.0)).unwrap();
+        store.insert(&hop("tx", "USDC", "BONK", 1.0)).unwrap();
+        // A replay with re-serialized amounts updates the hop in place
+        store.insert(&hop("tx", "SOL", "USDC", 1.0000000001)).unwrap();
+
+        let legs = store.trades("USDC", 0, 200).unwrap();
+        let hops: Vec<_> = legs.iter().map(|t| (t.from_address.as_str(), t.from_amount)).collect();
+        assert_eq!(hops, [("SOL", 1.0000000001), ("USDC", 1.0)]);
+        assert_eq!(store.trades("BONK", 0, 200).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn candles_upsert_on_address_interval_and_time() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.insert_batch(&[candle(60, 2.0), candle(120, 3.0), candle(60, 4.0)]).unwrap();
+
+        let candles = store.candles("T", "1m", 0, 1000).unwrap();
+        assert_eq!(candles.iter().map(|c| (c.unix_time, c.close)).collect::<Vec<_>>(), [(60, 4.0), (120, 3.0)]);
+        assert!(store.candles("T", "5m", 0, 1000).unwrap().is_empty());
+        assert_eq!(store.latest_candle_time("T", "1m").unwrap(), Some(120));
+        assert_eq!(store.latest_candle_time("T", "5m").unwrap(), None);
+    }
+
+    #[test]
+    fn new_pairs_and_listings_round_trip() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let token = |address: &str| json!({ "address": address, "name": address, "symbol": address, "decimals": 6 });
+        let pair = |address: &str, block_time: i64| {
+            StoredEvent::NewPair(
+                serde_json::from_value(json!({
+                    "address": address, "name": "BASE-SOL", "source": "raydium",
+                    "base": token("BASE"), "quote": token("SOL"), "txHash": "tx", "blockTime": block_time,
+                }))
+                .unwrap(),
+            )
+        };
+        let listing = StoredEvent::Listing(
+            serde_json::from_value(json!({
+                "address": "BASE", "decimals": 6, "name": "Base", "symbol": "BASE",
+                "liquidity": "1234.5", "liquidityAddedAt": 150,
+            }))
+            .unwrap(),
+        );
+        store.insert_batch(&[pair("old", 50), pair("new", 200), listing]).unwrap();
+
+        let pairs = store.new_pairs_since(100).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].address.as_str(), pairs[0].base_symbol.as_str()), ("new", "BASE"));
+        let listings = store.listings_since(100).unwrap();
+        assert_eq!((listings[0].symbol.as_str(), listings[0].liquidity.as_str()), ("BASE", "1234.5"));
+    }
+
+    #[tokio::test]
+    async fn writer_flushes_pending_events_when_senders_drop() {
+        let dir = std::env::temp_dir().join(format!("birdeye-sqlite-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("writer.db");
+        let _ = std::fs::remove_file(&path);
+
+        let options = WriterOptions::new().with_batch_size(100).with_flush_interval(Duration::from_secs(3600));
+        let (sender, handle) = spawn_writer(SqliteStore::open(&path).unwrap(), options);
+        sender.send(candle(60, 2.0)).await.unwrap();
+        sender.send(hop("tx", "SOL", "USDC", 1.0)).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.candles("T", "1m", 0, 100).unwrap().len(), 1);
+        assert_eq!(store.trades("SOL", 0, 200).unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenListingData {
     pub address: String,
     pub decimals: u8,
@@ -13,7 +13,7 @@ pub struct TokenListingData {
 }
 
 /// Options for token listing subscription
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TokenListingOptions {
     /// Whether to include listings from meme platforms (e.g., pump.fun)
     pub meme_platform_enabled: Option<bool>,
@@ -23,16 +23,6 @@ pub struct TokenListingOptions {
     pub max_liquidity: Option<f64>,
 }
 
-impl Default for TokenListingOptions {
-    fn default() -> Self {
-        Self {
-            meme_platform_enabled: None,
-            min_liquidity: None,
-            max_liquidity: None,
-        }
-    }
-}
-
 impl TokenListingOptions {
     pub fn new() -> Self {
         Self::default()
@@ -0,0 +1,248 @@
+use serde::Serialize;
+
+use crate::rest::{BirdeyeRest, TokenCreationInfoData, TokenOverviewData, TokenSecurityData};
+use crate::types::{unix_now, BirdeyeError};
+
+/// Whether the token's liquidity is locked or burned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LpLockStatus {
+    Locked,
+    Unlocked,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl RiskLevel {
+    fn from_score(score: u8) -> Self {
+        match score {
+            0..=24 => RiskLevel::Low,
+            25..=49 => RiskLevel::Medium,
+            50..=74 => RiskLevel::High,
+            _ => RiskLevel::Critical,
+        }
+    }
+}
+
+/// Individual inputs of the risk score; `None` when Birdeye had no data
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskSignals {
+    pub mint_authority: bool,
+    pub freeze_authority: bool,
+    /// Share of supply held by the top 10 holders (0.0 - 1.0)
+    pub top10_holder_percent: Option<f64>,
+    pub lp_lock: LpLockStatus,
+    pub liquidity_usd: Option<f64>,
+    pub age_secs: Option<i64>,
+}
+
+/// Risk verdict for a token
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenRiskReport {
+    pub address: String,
+    /// 0 (safest) to 100 (riskiest); see `TokenRisk` for the weights
+    pub score: u8,
+    pub level: RiskLevel,
+    pub signals: RiskSignals,
+}
+
+/// Rug-risk scoring built on the token security, creation-info and
+/// overview endpoints
+///
+/// The score adds up the following penalties:
+///
+/// | Signal                                   | Points |
+/// |------------------------------------------|--------|
+/// | Mint authority not revoked               | 25     |
+/// | Freeze authority present                 | 20     |
+/// | Top 10 holders > 80% / > 50% / > 30%     | 20 / 12 / 5 |
+/// | LP unlocked / lock status unknown        | 15 / 7 |
+/// | Liquidity < $1k / < $10k / < $50k         | 15 / 10 / 5 |
+/// | Created less than an hour ago            | 5      |
+///
+/// Missing holder or liquidity data scores as the worst bucket.
+/// Levels: `Low` < 25, `Medium` < 50, `High` < 75, otherwise `Critical`.
+#[derive(Debug, Clone)]
+pub struct TokenRisk {
+    rest: BirdeyeRest,
+}
+
+impl TokenRisk {
+    pub fn new(rest: BirdeyeRest) -> Self {
+        Self { rest }
+    }
+
+    /// Fetch all inputs concurrently and score `address`
+    ///
+    /// Fails only if the security report is unavailable; overview and
+    /// creation info are optional.
+    pub async fn assess(&self, address: &str) -> Result<TokenRiskReport, BirdeyeError> {
+        let (security, overview, creation) = tokio::join!(
+            self.rest.get_token_security(address),
+            self.rest.get_token_overview(address),
+            self.rest.get_token_creation_info(address),
+        );
+        let security = security?.data;
+        let overview = overview.ok().map(|r| r.data);
+        let creation = creation.ok().map(|r| r.data);
+        Ok(score_token(address, &security, overview.as_ref(), creation.as_ref()))
+    }
+}
+
+/// Score already fetched data; see `TokenRisk` for the weights
+pub fn score_token(
+    address: &str,
+    security: &TokenSecurityData,
+    overview: Option<&TokenOverviewData>,
+    creation: Option<&TokenCreationInfoData>,
+) -> TokenRiskReport {
+    let created_at = creation
+        .map(|c| c.block_unix_time)
+        .or(security.creation_time);
+    let signals = RiskSignals {
+        mint_authority: security.has_mint_authority(),
+        freeze_authority: security.has_freeze_authority(),
+        top10_holder_percent: security.top10_holder_percent,
+        lp_lock: lp_lock_status(security),
+        liquidity_usd: overview.map(|o| o.liquidity),
+        age_secs: created_at.map(|at| unix_now() - at),
+    };
+
+    let mut score: u32 = 0;
+    if signals.mint_authority {
+        score += 25;
+    }
+    if signals.freeze_authority {
+        score += 20;
+    }
+    score += match signals.top10_holder_percent {
+        Some(p) if p > 0.8 => 20,
+        Some(p) if p > 0.5 => 12,
+        Some(p) if p > 0.3 => 5,
+        Some(_) => 0,
+        None => 20,
+    };
+    score += match signals.lp_lock {
+        LpLockStatus::Locked => 0,
+        LpLockStatus::Unlocked => 15,
+        LpLockStatus::Unknown => 7,
+    };
+    score += match signals.liquidity_usd {
+        Some(l) if l < 1_000.0 => 15,
+        Some(l) if l < 10_000.0 => 10,
+        Some(l) if l < 50_000.0 => 5,
+        Some(_) => 0,
+        None => 15,
+    };
+    if signals.age_secs.is_some_and(|age| age < 3600) {
+        score += 5;
+    }
+
+    let score = score.min(100) as u8;
+    TokenRiskReport {
+        address: address.to_string(),
+        score,
+        level: RiskLevel::from_score(score),
+        signals,
+    }
+}
+
+fn lp_lock_status(security: &TokenSecurityData) -> LpLockStatus {
+    match &security.lock_info {
+        None => LpLockStatus::Unknown,
+        Some(serde_json::Value::Null) => LpLockStatus::Unlocked,
+        Some(serde_json::Value::Object(map)) if map.is_empty() => LpLockStatus::Unlocked,
+        Some(serde_json::Value::Array(items)) if items.is_empty() => LpLockStatus::Unlocked,
+        Some(_) => LpLockStatus::Locked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn security(fields: Value) -> TokenSecurityData {
+        serde_json::from_value(fields).unwrap()
+    }
+
+    fn overview(liquidity: f64) -> TokenOverviewData {
+        serde_json::from_value(json!({
+            "address": "T", "decimals": 6, "symbol": "T", "name": "T", "extensions": {},
+            "logoURI": null, "liquidity": liquidity, "price": 1.0, "supply": 0.0, "mc": 0.0,
+            "v24hUSD": null, "priceChange24hPercent": null, "lastTradeUnixTime": 0,
+            "lastTradeHumanTime": "",
+        }))
+        .unwrap()
+    }
+
+    fn created(secs_ago: i64) -> TokenCreationInfoData {
+        serde_json::from_value(json!({
+            "txHash": "hash", "slot": null, "tokenAddress": "T", "decimals": 6, "owner": null,
+            "blockUnixTime": unix_now() - secs_ago, "blockHumanTime": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn missing_data_scores_as_worst_bucket() {
+        let report = score_token("T", &security(json!({})), None, None);
+        // Holders 20, unknown LP lock 7, liquidity 15
+        assert_eq!(report.score, 42);
+        assert_eq!(report.level, RiskLevel::Medium);
+        assert_eq!(report.signals.lp_lock, LpLockStatus::Unknown);
+        assert_eq!(report.signals.age_secs, None);
+    }
+
+    #[test]
+    fn safe_token_scores_low() {
+        let security = security(json!({
+            "top10HolderPercent": 0.2,
+            "lockInfo": { "locked": true },
+        }));
+        let report = score_token("T", &security, Some(&overview(100_000.0)), Some(&created(86_400)));
+        assert_eq!(report.score, 0);
+        assert_eq!(report.level, RiskLevel::Low);
+        assert_eq!(report.signals.lp_lock, LpLockStatus::Locked);
+    }
+
+    #[test]
+    fn fresh_rug_scores_critical() {
+        let security = security(json!({
+            "ownerAddress": "creator",
+            "freezeAuthority": "creator",
+            "top10HolderPercent": 0.9,
+            "lockInfo": {},
+        }));
+        let report = score_token("T", &security, Some(&overview(500.0)), Some(&created(60)));
+        // 25 + 20 + 20 + 15 + 15 + 5
+        assert_eq!(report.score, 100);
+        assert_eq!(report.level, RiskLevel::Critical);
+        assert!(report.signals.mint_authority && report.signals.freeze_authority);
+    }
+
+    #[test]
+    fn buckets_follow_the_documented_weights() {
+        let security = security(json!({ "top10HolderPercent": 0.6, "lockInfo": [] }));
+        let report = score_token("T", &security, Some(&overview(5_000.0)), None);
+        // Holders 12, unlocked LP 15, liquidity 10
+        assert_eq!(report.score, 37);
+        assert_eq!(report.signals.lp_lock, LpLockStatus::Unlocked);
+    }
+
+    #[test]
+    fn levels_split_at_quarters() {
+        assert_eq!(RiskLevel::from_score(24), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score(25), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score(50), RiskLevel::High);
+        assert_eq!(RiskLevel::from_score(75), RiskLevel::Critical);
+    }
+}
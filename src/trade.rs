@@ -0,0 +1,448 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::dex::{DexSource, TradeVenue};
+use crate::large_trades::{LargeTradeData, TradeTokenInfo};
+use crate::txs::{TokenTransferInfo, TransactionData};
+use crate::wallet_txs::{WalletTokenInfo, WalletTxData, WalletTxKind};
+
+/// Tokens treated as the quote side when no base token is given
+pub const KNOWN_QUOTE_TOKENS: &[&str] = &[
+    // Solana: wSOL, USDC, USDT
+    "So11111111111111111111111111111111111111112",
+    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+    "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
+    // Ethereum: WETH, USDC, USDT
+    "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+    "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+    "0xdac17f958d2ee523a2206206994597c13d831ec7",
+    // BSC: WBNB, USDT
+    "0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c",
+    "0x55d398326f99059ff775485246999027b3197955",
+];
+
+/// Whether `address` is one of the `KNOWN_QUOTE_TOKENS`
+pub fn is_quote_token(address: &str) -> bool {
+    KNOWN_QUOTE_TOKENS
+        .iter()
+        .any(|quote| same_address(quote, address))
+}
+
+/// Compare addresses, ignoring case for EVM hex addresses
+pub(crate) fn same_address(a: &str, b: &str) -> bool {
+    if a.starts_with("0x") || a.starts_with("0X") {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+/// Transaction hashes seen recently, for de-duplication
+///
+/// Only the newest `capacity` hashes are remembered, so memory stays bounded
+/// on long-running streams.
+#[derive(Debug)]
+pub(crate) struct SeenTxs {
+    hashes: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl Default for SeenTxs {
+    fn default() -> Self {
+        Self::new(4096)
+    }
+}
+
+impl SeenTxs {
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        Self {
+            hashes: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn contains(&self, tx_hash: &str) -> bool {
+        self.hashes.contains(tx_hash)
+    }
+
+    /// Remember `tx_hash`; returns false if it was already seen
+    pub(crate) fn insert(&mut self, tx_hash: &str) -> bool {
+        if !self.hashes.insert(tx_hash.to_string()) {
+            return false;
+        }
+        self.order.push_back(tx_hash.to_string());
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Canonical form of an address for map keys: EVM hex addresses are
+/// lowercased, base58 addresses are case-sensitive and kept as is
+pub(crate) fn normalize_address(address: &str) -> String {
+    if address.starts_with("0x") || address.starts_with("0X") {
+        address.to_ascii_lowercase()
+    } else {
+        address.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// A swap normalized around a base token
+///
+/// `side` is from the trader's point of view: `Buy` means the trader
+/// received the base token.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trade {
+    pub tx_hash: String,
+    pub owner: String,
+    pub block_unix_time: i64,
+    pub side: TradeSide,
+    pub base_address: String,
+    pub base_symbol: String,
+    pub base_amount: f64,
+    pub quote_address: String,
+    pub quote_symbol: String,
+    pub quote_amount: f64,
+    /// Execution price in quote tokens per base token
+    pub price: f64,
+    /// USD price of the base token, if Birdeye reported one
+    pub price_usd: Option<f64>,
+    pub volume_usd: f64,
+    pub venue: DexSource,
+    pub pool_address: Option<String>,
+}
+
+/// Which way a leg moved from the owner's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegDirection {
+    Sent,
+    Received,
+}
+
+impl LegDirection {
+    /// From a `typeSwap` value (`"from"` / `"to"`)
+    fn from_type_swap(type_swap: &str) -> Option<Self> {
+        match type_swap {
+            "from" => Some(LegDirection::Sent),
+            "to" => Some(LegDirection::Received),
+            _ => None,
+        }
+    }
+
+    /// From the sign of the owner's balance change
+    fn from_change(ui_change_amount: f64) -> Option<Self> {
+        if ui_change_amount < 0.0 {
+            Some(LegDirection::Sent)
+        } else if ui_change_amount > 0.0 {
+            Some(LegDirection::Received)
+        } else {
+            None
+        }
+    }
+}
+
+/// One side of a swap as reported by Birdeye
+#[derive(Debug, Clone, Copy)]
+pub struct TradeLeg<'a> {
+    pub address: &'a str,
+    pub symbol: &'a str,
+    pub ui_amount: f64,
+    pub price: Option<f64>,
+    /// Explicit direction reported with the leg, if any
+    pub direction: Option<LegDirection>,
+}
+
+impl<'a> From<&'a TokenTransferInfo> for TradeLeg<'a> {
+    fn from(info: &'a TokenTransferInfo) -> Self {
+        TradeLeg {
+            address: &info.address,
+            symbol: &info.symbol,
+            ui_amount: info.ui_amount,
+            price: info.price.or(info.nearest_price),
+            direction: LegDirection::from_type_swap(&info.typeSwap)
+                .or_else(|| LegDirection::from_change(info.ui_change_amount)),
+        }
+    }
+}
+
+impl<'a> From<&'a TradeTokenInfo> for TradeLeg<'a> {
+    fn from(info: &'a TradeTokenInfo) -> Self {
+        TradeLeg {
+            address: &info.address,
+            symbol: &info.symbol,
+            ui_amount: info.ui_amount,
+            price: info.price.or(info.nearest_price),
+            direction: LegDirection::from_change(info.ui_change_amount),
+        }
+    }
+}
+
+impl<'a> From<&'a WalletTokenInfo> for TradeLeg<'a> {
+    fn from(info: &'a WalletTokenInfo) -> Self {
+        TradeLeg {
+            address: &info.address,
+            symbol: &info.symbol,
+            ui_amount: info.ui_amount,
+            price: info.price.or(info.nearest_price),
+            direction: LegDirection::from_change(info.ui_change_amount),
+        }
+    }
+}
+
+/// Trade payloads that can be normalized into a `Trade`
+pub trait ToTrade: TradeVenue {
+    /// Token sent and token received by the owner, if this is a swap
+    fn legs(&self) -> Option<(TradeLeg<'_>, TradeLeg<'_>)>;
+    fn tx_hash(&self) -> &str;
+    fn owner(&self) -> &str;
+    fn block_unix_time(&self) -> i64;
+    fn volume_usd(&self) -> f64;
+    fn pool_address(&self) -> Option<&str> {
+        None
+    }
+
+    /// Token sent and token received, honouring explicit leg directions
+    ///
+    /// Legs are taken in `from`/`to` order unless both report a direction
+    /// saying otherwise.
+    fn directed_legs(&self) -> Option<(TradeLeg<'_>, TradeLeg<'_>)> {
+        let (from, to) = self.legs()?;
+        match (from.direction, to.direction) {
+            (Some(LegDirection::Received), Some(LegDirection::Sent)) => Some((to, from)),
+            _ => Some((from, to)),
+        }
+    }
+
+    /// Normalize around `base_token`; `None` if it is not traded here
+    fn to_trade(&self, base_token: &str) -> Option<Trade> {
+        let (sent, received) = self.directed_legs()?;
+        let (side, base, quote) = if same_address(received.address, base_token) {
+            (TradeSide::Buy, received, sent)
+        } else if same_address(sent.address, base_token) {
+            (TradeSide::Sell, sent, received)
+        } else {
+            return None;
+        };
+
+        let base_amount = base.ui_amount.abs();
+        let quote_amount = quote.ui_amount.abs();
+        let price = if base_amount > 0.0 {
+            quote_amount / base_amount
+        } else {
+            0.0
+        };
+
+        Some(Trade {
+            tx_hash: self.tx_hash().to_string(),
+            owner: self.owner().to_string(),
+            block_unix_time: self.block_unix_time(),
+            side,
+            base_address: base.address.to_string(),
+            base_symbol: base.symbol.to_string(),
+            base_amount,
+            quote_address: quote.address.to_string(),
+            quote_symbol: quote.symbol.to_string(),
+            quote_amount,
+            price,
+            price_usd: base.price,
+            volume_usd: self.volume_usd(),
+            venue: self.dex_source(),
+            pool_address: self.pool_address().map(str::to_string),
+        })
+    }
+
+    /// Normalize using the leg that is not a known quote token as base
+    ///
+    /// Swaps between two quote tokens have no base and give `None`; when
+    /// neither leg is a quote token the received one is the base.
+    fn to_trade_inferred(&self) -> Option<Trade> {
+        let (sent, received) = self.directed_legs()?;
+        let base = match (is_quote_token(sent.address), is_quote_token(received.address)) {
+            (true, true) => return None,
+            (false, true) => sent.address,
+            _ => received.address,
+        };
+        self.to_trade(base)
+    }
+}
+
+impl ToTrade for TransactionData {
+    fn legs(&self) -> Option<(TradeLeg<'_>, TradeLeg<'_>)> {
+        Some(((&self.from).into(), (&self.to).into()))
+    }
+
+    fn tx_hash(&self) -> &str {
+        &self.tx_hash
+    }
+
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn block_unix_time(&self) -> i64 {
+        self.block_unix_time
+    }
+
+    fn volume_usd(&self) -> f64 {
+        self.volume_usd
+    }
+
+    fn pool_address(&self) -> Option<&str> {
+        self.pool_address.as_deref()
+    }
+}
+
+impl ToTrade for LargeTradeData {
+    fn legs(&self) -> Option<(TradeLeg<'_>, TradeLeg<'_>)> {
+        Some(((&self.from).into(), (&self.to).into()))
+    }
+
+    fn tx_hash(&self) -> &str {
+        &self.tx_hash
+    }
+
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn block_unix_time(&self) -> i64 {
+        self.block_unix_time
+    }
+
+    fn volume_usd(&self) -> f64 {
+        self.volume_usd
+    }
+
+    fn pool_address(&self) -> Option<&str> {
+        Some(&self.pool_address)
+    }
+}
+
+impl ToTrade for WalletTxData {
+    fn legs(&self) -> Option<(TradeLeg<'_>, TradeLeg<'_>)> {
+        if self.kind() != WalletTxKind::Swap {
+            return None;
+        }
+        Some((self.from.as_ref()?.into(), self.to.as_ref()?.into()))
+    }
+
+    fn tx_hash(&self) -> &str {
+        &self.tx_hash
+    }
+
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn block_unix_time(&self) -> i64 {
+        self.block_unix_time
+    }
+
+    fn volume_usd(&self) -> f64 {
+        self.volume_usd
+    }
+
+    fn pool_address(&self) -> Option<&str> {
+        self.pool_address.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn leg(address: &str, ui_amount: f64, type_swap: &str, change: f64) -> serde_json::Value {
+        json!({
+            "symbol": "T", "decimals": 6, "address": address, "amount": 1,
+            "type": "transfer", "typeSwap": type_swap, "uiAmount": ui_amount,
+            "price": null, "nearestPrice": 2.0, "changeAmount": 0, "uiChangeAmount": change,
+        })
+    }
+
+    fn tx(from: serde_json::Value, to: serde_json::Value) -> TransactionData {
+        serde_json::from_value(json!({
+            "blockUnixTime": 1_700_000_000, "owner": "trader", "source": "raydium",
+            "txHash": "hash", "alias": null, "isTradeOnBe": false, "platform": "raydium",
+            "volumeUSD": 100.0, "from": from, "to": to,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn buy_and_sell_follow_the_base_token() {
+        let swap = tx(leg(SOL, 1.0, "from", -1.0), leg(BONK, 4000.0, "to", 4000.0));
+        let buy = swap.to_trade(BONK).unwrap();
+        assert_eq!(buy.side, TradeSide::Buy);
+        assert_eq!(buy.quote_address, SOL);
+        assert_eq!(buy.price, 1.0 / 4000.0);
+
+        let sell = swap.to_trade(SOL).unwrap();
+        assert_eq!(sell.side, TradeSide::Sell);
+        assert_eq!(sell.price, 4000.0);
+        assert!(swap.to_trade(USDC).is_none());
+    }
+
+    #[test]
+    fn explicit_directions_override_leg_order() {
+        // Legs reported in the opposite order, with typeSwap saying so
+        let swap = tx(leg(BONK, 4000.0, "to", 4000.0), leg(SOL, 1.0, "from", -1.0));
+        assert_eq!(swap.to_trade(BONK).unwrap().side, TradeSide::Buy);
+        assert_eq!(swap.to_trade_inferred().unwrap().base_address, BONK);
+
+        // Without typeSwap, the balance change decides
+        let swap = tx(leg(BONK, 4000.0, "", 4000.0), leg(SOL, 1.0, "", -1.0));
+        assert_eq!(swap.to_trade(BONK).unwrap().side, TradeSide::Buy);
+    }
+
+    #[test]
+    fn inferred_base_skips_quote_tokens() {
+        let sell = tx(leg(BONK, 4000.0, "from", -4000.0), leg(USDC, 10.0, "to", 10.0));
+        let trade = sell.to_trade_inferred().unwrap();
+        assert_eq!(trade.base_address, BONK);
+        assert_eq!(trade.side, TradeSide::Sell);
+    }
+
+    #[test]
+    fn quote_to_quote_swaps_have_no_inferred_base() {
+        let swap = tx(leg(SOL, 1.0, "from", -1.0), leg(USDC, 150.0, "to", 150.0));
+        assert!(swap.to_trade_inferred().is_none());
+        assert!(swap.to_trade(SOL).is_some());
+    }
+
+    #[test]
+    fn seen_txs_forget_the_oldest_beyond_capacity() {
+        let mut seen = SeenTxs::new(2);
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b") && seen.insert("c"));
+        assert!(!seen.contains("a") && seen.contains("b") && seen.contains("c"));
+        assert!(seen.insert("a"));
+        assert!(!seen.contains("b"));
+    }
+
+    #[test]
+    fn evm_addresses_compare_case_insensitively() {
+        assert!(same_address("0xABCdef", "0xabcDEF"));
+        assert!(!same_address("AbC", "abc"));
+        assert!(is_quote_token("0xC02AAA39B223FE8D0A0E5C4F27EAD9083C756CC2"));
+    }
+}
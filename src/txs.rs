@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
-use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
+use crate::types::{SubscriptionMessage, SubscriptionType};
 
-#[derive(Debug, Deserialize)]
+// `typeSwap` keeps the wire name, as in the original public API
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenTransferInfo {
     pub symbol: String,
     pub decimals: u8,
@@ -22,7 +24,7 @@ pub struct TokenTransferInfo {
     pub icon: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TransactionData {
     #[serde(rename = "blockUnixTime")]
     pub block_unix_time: i64,
@@ -36,6 +38,8 @@ pub struct TransactionData {
     pub platform: String,
     #[serde(rename = "volumeUSD")]
     pub volume_usd: f64,
+    #[serde(rename = "poolAddress", default)]
+    pub pool_address: Option<String>,
     pub from: TokenTransferInfo,
     pub to: TokenTransferInfo,
 }
@@ -1,6 +1,6 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
-use thiserror::Error;
-use tokio_tungstenite::tungstenite::http::header::InvalidHeaderValue;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BirdeyeError {
@@ -12,9 +12,68 @@ pub enum BirdeyeError {
     Json(#[from] serde_json::Error),
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("compute unit budget exceeded: {used:.0} of {limit:.0} CU used")]
+    BudgetExceeded { used: f64, limit: f64 },
+    /// A response with a non-success status; `retry_after` comes from the
+    /// `Retry-After` header
+    #[error("HTTP status {status}")]
+    HttpStatus { status: u16, retry_after: Option<Duration> },
+    /// A sink delivered the first `delivered` messages of a batch before
+    /// failing
+    #[error("delivered {delivered} messages before failing: {source}")]
+    PartialDelivery {
+        delivered: usize,
+        #[source]
+        source: Box<BirdeyeError>,
+    },
+    /// A recent failure remembered by a cache, with its message
+    #[error("cached failure: {0}")]
+    CachedFailure(String),
+    /// A blocking or background task panicked or was cancelled
+    #[error("background task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[cfg(feature = "metrics")]
+    #[error("Metrics error: {0}")]
+    Metrics(#[from] prometheus::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(Box<rusqlite::Error>),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(Box<arrow::error::ArrowError>),
+    #[cfg(feature = "arrow")]
+    #[error("Parquet error: {0}")]
+    Parquet(Box<parquet::errors::ParquetError>),
+}
+
+// Large source errors are boxed to keep `Result<_, BirdeyeError>` small
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for BirdeyeError {
+    fn from(e: rusqlite::Error) -> Self {
+        BirdeyeError::Sqlite(Box::new(e))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow::error::ArrowError> for BirdeyeError {
+    fn from(e: arrow::error::ArrowError) -> Self {
+        BirdeyeError::Arrow(Box::new(e))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<parquet::errors::ParquetError> for BirdeyeError {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        BirdeyeError::Parquet(Box::new(e))
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SubscriptionType {
     SubscribePrice,
@@ -33,7 +92,7 @@ pub enum SubscriptionType {
     UnsubscribeLargeTradeTxs,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ResponseType {
     PriceData,
@@ -46,16 +105,123 @@ pub enum ResponseType {
     Error,
 }
 
-#[derive(Debug, Serialize)]
+impl ResponseType {
+    /// The `type` field as sent by the server
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseType::PriceData => "PRICE_DATA",
+            ResponseType::TxsData => "TXS_DATA",
+            ResponseType::TokenNewListing => "TOKEN_NEW_LISTING",
+            ResponseType::NewPair => "NEW_PAIR",
+            ResponseType::WalletTxsData => "WALLET_TXS_DATA",
+            ResponseType::BaseQuotePriceData => "BASE_QUOTE_PRICE_DATA",
+            ResponseType::TxsLargeTradeData => "TXS_LARGE_TRADE_DATA",
+            ResponseType::Error => "ERROR",
+        }
+    }
+}
+
+impl SubscriptionType {
+    /// The subscription an unsubscribe message cancels, `None` for
+    /// subscribe messages
+    pub fn unsubscribes(&self) -> Option<SubscriptionType> {
+        match self {
+            SubscriptionType::UnsubscribePrice => Some(SubscriptionType::SubscribePrice),
+            SubscriptionType::UnsubscribeTxs => Some(SubscriptionType::SubscribeTxs),
+            SubscriptionType::UnsubscribeTokenNewListing => Some(SubscriptionType::SubscribeTokenNewListing),
+            SubscriptionType::UnsubscribeNewPair => Some(SubscriptionType::SubscribeNewPair),
+            SubscriptionType::UnsubscribeWalletTxs => Some(SubscriptionType::SubscribeWalletTxs),
+            SubscriptionType::UnsubscribeBaseQuotePrice => Some(SubscriptionType::SubscribeBaseQuotePrice),
+            SubscriptionType::UnsubscribeLargeTradeTxs => Some(SubscriptionType::SubscribeLargeTradeTxs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscriptionMessage {
     #[serde(rename = "type")]
     pub msg_type: SubscriptionType,
+    #[serde(default)]
     pub data: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebSocketResponse {
     #[serde(rename = "type")]
     pub response_type: ResponseType,
     pub data: serde_json::Value,
-} 
\ No newline at end of file
+    /// Unix time in milliseconds the message was received, set by
+    /// `BirdeyeWebSocket::decode`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<i64>,
+}
+
+impl WebSocketResponse {
+    /// Exchange-provided `unixTime`/`blockUnixTime` of the event, in seconds
+    pub fn event_time(&self) -> Option<i64> {
+        ["unixTime", "blockUnixTime"]
+            .iter()
+            .find_map(|field| self.data.get(field).and_then(serde_json::Value::as_i64))
+    }
+
+    /// Milliseconds between the event and its receipt; negative when the
+    /// local clock is behind the exchange's
+    pub fn latency_ms(&self) -> Option<i64> {
+        Some(self.received_at? - self.event_time()? * 1000)
+    }
+}
+
+/// Stands in for API keys in `Debug` output and logs
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Current Unix time in seconds
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Current Unix time in milliseconds
+pub(crate) fn unix_now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Run `future` on the current thread, returning its output and every
+/// `tracing` event it emitted
+#[cfg(test)]
+pub(crate) async fn capture_logs<F: std::future::Future>(future: F) -> (F::Output, String) {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let guard = tracing::subscriber::set_default(subscriber);
+    let output = future.await;
+    drop(guard);
+    let logs = String::from_utf8_lossy(&buffer.0.lock().unwrap()).into_owned();
+    (output, logs)
+}
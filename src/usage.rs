@@ -0,0 +1,482 @@
+//! Request, byte and compute-unit accounting for API keys
+//!
+//! Share one `UsageTracker` between `BirdeyeRest::with_usage` and
+//! `BirdeyeWebSocket::with_usage` to see where compute units go. Costs are
+//! estimates from `ComputeUnitCosts`; Birdeye's own dashboard remains the
+//! source of truth for billing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::types::{unix_now, BirdeyeError, ResponseType};
+
+/// Message bucket for frames that are not protocol messages, such as the
+/// welcome message
+pub(crate) const OTHER_MESSAGES: &str = "OTHER";
+
+/// Estimated compute units charged per REST request and WebSocket byte
+#[derive(Debug, Clone)]
+pub struct ComputeUnitCosts {
+    endpoints: HashMap<String, f64>,
+    /// Cost of endpoints without an explicit entry
+    pub default_request: f64,
+    /// Cost of every byte received over a WebSocket
+    pub websocket_per_byte: f64,
+}
+
+impl Default for ComputeUnitCosts {
+    fn default() -> Self {
+        let endpoints = [
+            ("/defi/ohlcv", 40.0),
+            ("/defi/token_overview", 30.0),
+            ("/defi/token_security", 50.0),
+            ("/defi/token_creation_info", 80.0),
+            ("/v1/wallet/token_list", 100.0),
+        ];
+        Self {
+            endpoints: endpoints.into_iter().map(|(path, cost)| (path.to_string(), cost)).collect(),
+            default_request: 10.0,
+            websocket_per_byte: 0.003,
+        }
+    }
+}
+
+impl ComputeUnitCosts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cost of `path`, e.g. `"/defi/token_overview"`
+    pub fn with_endpoint(mut self, path: impl Into<String>, compute_units: f64) -> Self {
+        assert!(compute_units >= 0.0, "compute_units must not be negative");
+        self.endpoints.insert(path.into(), compute_units);
+        self
+    }
+
+    pub fn with_default_request(mut self, compute_units: f64) -> Self {
+        assert!(compute_units >= 0.0, "compute_units must not be negative");
+        self.default_request = compute_units;
+        self
+    }
+
+    pub fn with_websocket_per_byte(mut self, compute_units: f64) -> Self {
+        assert!(compute_units >= 0.0, "compute_units must not be negative");
+        self.websocket_per_byte = compute_units;
+        self
+    }
+
+    /// Cost of one request to `path` (without query string)
+    pub fn request(&self, path: &str) -> f64 {
+        self.endpoints.get(path).copied().unwrap_or(self.default_request)
+    }
+
+    /// Cost of a WebSocket message of `bytes` bytes
+    pub fn message(&self, bytes: usize) -> f64 {
+        bytes as f64 * self.websocket_per_byte
+    }
+}
+
+/// What happens once the budget is used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetMode {
+    /// Keep going; only the warning and budget callback fire
+    Warn,
+    /// Fail new REST requests and WebSocket connections with
+    /// `BirdeyeError::BudgetExceeded`
+    Block,
+}
+
+/// Compute units that may be spent before `UsageTracker::reset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsageBudget {
+    pub compute_units: f64,
+    pub mode: BudgetMode,
+}
+
+type BudgetCallback = Arc<dyn Fn(&UsageReport) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct UsageOptions {
+    pub costs: ComputeUnitCosts,
+    pub budget: Option<UsageBudget>,
+    on_budget_exceeded: Option<BudgetCallback>,
+}
+
+impl fmt::Debug for UsageOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UsageOptions")
+            .field("costs", &self.costs)
+            .field("budget", &self.budget)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UsageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_costs(mut self, costs: ComputeUnitCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_budget(mut self, compute_units: f64, mode: BudgetMode) -> Self {
+        assert!(compute_units > 0.0, "compute_units must be greater than 0");
+        self.budget = Some(UsageBudget { compute_units, mode });
+        self
+    }
+
+    /// Called once per budget period, when usage first reaches the budget
+    pub fn on_budget_exceeded(mut self, callback: impl Fn(&UsageReport) + Send + Sync + 'static) -> Self {
+        self.on_budget_exceeded = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Totals of one endpoint or message type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct UsageCounter {
+    pub count: u64,
+    pub bytes: u64,
+    pub compute_units: f64,
+}
+
+impl UsageCounter {
+    fn add(&mut self, bytes: usize, compute_units: f64) {
+        self.count += 1;
+        self.bytes += bytes as u64;
+        self.compute_units += compute_units;
+    }
+
+    fn merge(mut self, other: &UsageCounter) -> Self {
+        self.count += other.count;
+        self.bytes += other.bytes;
+        self.compute_units += other.compute_units;
+        self
+    }
+}
+
+/// Snapshot of a `UsageTracker`
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    /// Unix time the budget period started
+    pub since: i64,
+    /// Unix time of the snapshot
+    pub at: i64,
+    /// REST requests by path
+    pub endpoints: BTreeMap<String, UsageCounter>,
+    /// WebSocket messages by response type
+    pub messages: BTreeMap<String, UsageCounter>,
+    /// Requests and connections refused by a blocking budget
+    pub blocked: u64,
+    pub budget: Option<f64>,
+}
+
+impl UsageReport {
+    /// All REST requests
+    pub fn requests(&self) -> UsageCounter {
+        self.endpoints.values().fold(UsageCounter::default(), UsageCounter::merge)
+    }
+
+    /// All WebSocket messages
+    pub fn websocket(&self) -> UsageCounter {
+        self.messages.values().fold(UsageCounter::default(), UsageCounter::merge)
+    }
+
+    pub fn compute_units(&self) -> f64 {
+        self.requests().compute_units + self.websocket().compute_units
+    }
+
+    /// Compute units left in the budget, `None` without one
+    pub fn budget_remaining(&self) -> Option<f64> {
+        self.budget.map(|budget| (budget - self.compute_units()).max(0.0))
+    }
+}
+
+impl fmt::Display for UsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "usage over {}s: {:.1} CU", self.at - self.since, self.compute_units())?;
+        for (name, counter) in self.endpoints.iter().chain(&self.messages) {
+            writeln!(
+                f,
+                "  {name}: {} x, {} bytes, {:.1} CU",
+                counter.count, counter.bytes, counter.compute_units
+            )?;
+        }
+        if let (Some(budget), Some(remaining)) = (self.budget, self.budget_remaining()) {
+            writeln!(f, "  budget: {remaining:.1} of {budget:.1} CU left, {} blocked", self.blocked)?;
+        }
+        Ok(())
+    }
+}
+
+struct UsageState {
+    since: i64,
+    endpoints: HashMap<String, UsageCounter>,
+    messages: HashMap<&'static str, UsageCounter>,
+    compute_units: f64,
+    blocked: u64,
+    exceeded: bool,
+}
+
+impl UsageState {
+    fn new() -> Self {
+        Self {
+            since: unix_now(),
+            endpoints: HashMap::new(),
+            messages: HashMap::new(),
+            compute_units: 0.0,
+            blocked: 0,
+            exceeded: false,
+        }
+    }
+
+    fn report(&self, budget: Option<UsageBudget>) -> UsageReport {
+        UsageReport {
+            since: self.since,
+            at: unix_now(),
+            endpoints: self.endpoints.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            messages: self.messages.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            blocked: self.blocked,
+            budget: budget.map(|budget| budget.compute_units),
+        }
+    }
+}
+
+/// Shared usage counters; clones count into the same totals
+#[derive(Clone)]
+pub struct UsageTracker {
+    options: Arc<UsageOptions>,
+    state: Arc<Mutex<UsageState>>,
+}
+
+impl fmt::Debug for UsageTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UsageTracker")
+            .field("options", &self.options)
+            .field("compute_units", &self.compute_units())
+            .finish()
+    }
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self::new(UsageOptions::default())
+    }
+}
+
+impl UsageTracker {
+    pub fn new(options: UsageOptions) -> Self {
+        Self {
+            options: Arc::new(options),
+            state: Arc::new(Mutex::new(UsageState::new())),
+        }
+    }
+
+    /// Compute units used in the current budget period
+    pub fn compute_units(&self) -> f64 {
+        self.state.lock().unwrap().compute_units
+    }
+
+    pub fn budget_exceeded(&self) -> bool {
+        self.state.lock().unwrap().exceeded
+    }
+
+    pub fn report(&self) -> UsageReport {
+        self.state.lock().unwrap().report(self.options.budget)
+    }
+
+    /// Start a new budget period, returning the report of the last one
+    pub fn reset(&self) -> UsageReport {
+        let mut state = self.state.lock().unwrap();
+        let report = state.report(self.options.budget);
+        *state = UsageState::new();
+        report
+    }
+
+    /// Fail with `BirdeyeError::BudgetExceeded` if a blocking budget is
+    /// used up
+    pub fn check(&self) -> Result<(), BirdeyeError> {
+        let Some(budget) = self.options.budget.filter(|budget| budget.mode == BudgetMode::Block) else {
+            return Ok(());
+        };
+        let mut state = self.state.lock().unwrap();
+        if state.compute_units < budget.compute_units {
+            return Ok(());
+        }
+        state.blocked += 1;
+        tracing::debug!(used = state.compute_units, limit = budget.compute_units, "blocked by compute unit budget");
+        Err(BirdeyeError::BudgetExceeded {
+            used: state.compute_units,
+            limit: budget.compute_units,
+        })
+    }
+
+    /// Count a REST request to `path_and_query` that returned `bytes` bytes
+    pub fn record_request(&self, path_and_query: &str, bytes: usize) {
+        let path = path_and_query.split_once('?').map_or(path_and_query, |(path, _)| path);
+        let cost = self.options.costs.request(path);
+        self.record(cost, |state| {
+            state.endpoints.entry(path.to_string()).or_default().add(bytes, cost)
+        });
+    }
+
+    /// Count a WebSocket message of `bytes` bytes; `None` for frames that
+    /// are not protocol messages
+    pub fn record_message(&self, response_type: Option<&ResponseType>, bytes: usize) {
+        let name = response_type.map_or(OTHER_MESSAGES, ResponseType::as_str);
+        let cost = self.options.costs.message(bytes);
+        self.record(cost, |state| state.messages.entry(name).or_default().add(bytes, cost));
+    }
+
+    fn record(&self, cost: f64, count: impl FnOnce(&mut UsageState)) {
+        let report = {
+            let mut state = self.state.lock().unwrap();
+            count(&mut state);
+            state.compute_units += cost;
+            match self.options.budget {
+                Some(budget) if !state.exceeded && state.compute_units >= budget.compute_units => {
+                    state.exceeded = true;
+                    tracing::warn!(
+                        used = state.compute_units,
+                        limit = budget.compute_units,
+                        mode = ?budget.mode,
+                        "compute unit budget exceeded"
+                    );
+                    Some(state.report(Some(budget)))
+                }
+                _ => None,
+            }
+        };
+        // Outside the lock so the callback may query the tracker
+        if let (Some(report), Some(callback)) = (report, &self.options.on_budget_exceeded) {
+            callback(&report);
+        }
+    }
+
+    /// Hand a report to `on_report` every `period`
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn_reporter(
+        &self,
+        period: Duration,
+        mut on_report: impl FnMut(UsageReport) + Send + 'static,
+    ) -> JoinHandle<()> {
+        assert!(!period.is_zero(), "period must be greater than 0");
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(period);
+            // The first tick completes immediately
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                on_report(tracker.report());
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{BirdeyeRest, BirdeyeWebSocket};
+
+    fn costs() -> ComputeUnitCosts {
+        ComputeUnitCosts::new()
+            .with_endpoint("/defi/token_overview", 30.0)
+            .with_default_request(10.0)
+            .with_websocket_per_byte(0.5)
+    }
+
+    #[test]
+    fn requests_are_counted_per_path_without_the_query() {
+        let tracker = UsageTracker::new(UsageOptions::new().with_costs(costs()));
+        tracker.record_request("/defi/token_overview?address=A", 100);
+        tracker.record_request("/defi/token_overview?address=B", 50);
+        tracker.record_request("/defi/price?address=A", 10);
+
+        let report = tracker.report();
+        assert_eq!(
+            report.endpoints["/defi/token_overview"],
+            UsageCounter { count: 2, bytes: 150, compute_units: 60.0 }
+        );
+        assert_eq!(report.endpoints["/defi/price"].compute_units, 10.0);
+        assert_eq!(report.requests().count, 3);
+        assert_eq!(tracker.compute_units(), 70.0);
+        assert_eq!(report.budget_remaining(), None);
+    }
+
+    #[test]
+    fn decoded_messages_are_counted_per_response_type() {
+        let tracker = UsageTracker::new(UsageOptions::new().with_costs(costs()));
+        let ws = BirdeyeWebSocket::new("key").with_usage(tracker.clone());
+        let price = r#"{"type":"PRICE_DATA","data":{}}"#;
+        ws.decode(price).unwrap();
+        assert!(ws.decode(r#"{"message":"welcome"}"#).is_err());
+
+        let report = tracker.report();
+        let name = ResponseType::PriceData.as_str();
+        let expected = UsageCounter {
+            count: 1,
+            bytes: price.len() as u64,
+            compute_units: price.len() as f64 * 0.5,
+        };
+        assert_eq!(report.messages[name], expected);
+        assert_eq!(report.messages[OTHER_MESSAGES].count, 1);
+        assert_eq!(report.websocket().count, 2);
+    }
+
+    #[test]
+    fn warn_budget_fires_the_callback_once_and_never_blocks() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let tracker = UsageTracker::new(
+            UsageOptions::new()
+                .with_costs(costs())
+                .with_budget(25.0, BudgetMode::Warn)
+                .on_budget_exceeded(move |report| {
+                    assert!(report.compute_units() >= 25.0);
+                    counted.fetch_add(1, Ordering::SeqCst);
+                }),
+        );
+        tracker.record_request("/defi/price", 0);
+        tracker.record_request("/defi/price", 0);
+        assert!(!tracker.budget_exceeded());
+        tracker.record_request("/defi/price", 0);
+        tracker.record_request("/defi/price", 0);
+
+        assert!(tracker.budget_exceeded());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(tracker.check().is_ok());
+        assert_eq!(tracker.report().budget_remaining(), Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn block_budget_refuses_requests_and_connections() {
+        let tracker = UsageTracker::new(UsageOptions::new().with_costs(costs()).with_budget(20.0, BudgetMode::Block));
+        tracker.record_request("/defi/price", 0);
+        assert!(tracker.check().is_ok());
+        tracker.record_request("/defi/price", 0);
+
+        let rest = BirdeyeRest::new("key", "solana").with_usage(tracker.clone());
+        let error = rest.get_token_overview("A").await.unwrap_err();
+        assert!(matches!(error, BirdeyeError::BudgetExceeded { used, limit } if used == 20.0 && limit == 20.0));
+        let ws = BirdeyeWebSocket::new("key").with_usage(tracker.clone());
+        assert!(matches!(ws.connect().await, Err(BirdeyeError::BudgetExceeded { .. })));
+
+        let report = tracker.reset();
+        assert_eq!(report.blocked, 2);
+        assert!(report.to_string().contains("0.0 of 20.0 CU left, 2 blocked"));
+        assert_eq!(tracker.compute_units(), 0.0);
+        assert!(!tracker.budget_exceeded());
+        assert!(tracker.check().is_ok());
+    }
+}
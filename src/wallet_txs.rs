@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use crate::types::{SubscriptionMessage, SubscriptionType, BirdeyeError};
 use serde_json::Value;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WalletTokenInfo {
     pub symbol: String,
     pub decimals: u8,
@@ -28,6 +28,101 @@ impl WalletTokenInfo {
     }
 }
 
+/// Wallet transaction categories reported in the `type` field
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WalletTxKind {
+    Swap,
+    Transfer,
+    AddLiquidity,
+    RemoveLiquidity,
+    Mint,
+    Burn,
+    Stake,
+    Unstake,
+    Other(String),
+}
+
+impl WalletTxKind {
+    pub fn parse(raw: &str) -> Self {
+        let normalized: String = raw
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "swap" => WalletTxKind::Swap,
+            "transfer" | "send" | "receive" => WalletTxKind::Transfer,
+            // Plain "deposit"/"withdraw" also covers staking and exchange
+            // transfers, so those stay `Other`
+            "addliquidity" | "addlp" => WalletTxKind::AddLiquidity,
+            "removeliquidity" | "removelp" => WalletTxKind::RemoveLiquidity,
+            "mint" => WalletTxKind::Mint,
+            "burn" => WalletTxKind::Burn,
+            "stake" => WalletTxKind::Stake,
+            "unstake" => WalletTxKind::Unstake,
+            _ => WalletTxKind::Other(raw.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SwapPayload {
+    pub from: WalletTokenInfo,
+    pub to: WalletTokenInfo,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferPayload {
+    #[serde(default, alias = "fromAddress", alias = "fromOwner")]
+    pub sender: Option<String>,
+    #[serde(default, alias = "toAddress", alias = "toOwner")]
+    pub receiver: Option<String>,
+    /// Token moved; taken from the `from`/`to` leg when not reported separately
+    #[serde(default)]
+    pub token: Option<WalletTokenInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LiquidityPayload {
+    /// Pool tokens deposited or withdrawn
+    #[serde(default)]
+    pub tokens: Vec<WalletTokenInfo>,
+    #[serde(default, rename = "lpToken")]
+    pub lp_token: Option<WalletTokenInfo>,
+    #[serde(default, rename = "poolAddress")]
+    pub pool_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SupplyChangePayload {
+    #[serde(default)]
+    pub token: Option<WalletTokenInfo>,
+    #[serde(default, alias = "mintAuthority")]
+    pub authority: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StakePayload {
+    #[serde(default)]
+    pub token: Option<WalletTokenInfo>,
+    #[serde(default, alias = "voteAccount", alias = "stakeAccount", alias = "poolAddress")]
+    pub validator: Option<String>,
+}
+
+/// Typed contents of a wallet transaction, selected by `WalletTxKind`
+#[derive(Debug, Clone)]
+pub enum WalletTxPayload {
+    Swap(SwapPayload),
+    Transfer(TransferPayload),
+    AddLiquidity(LiquidityPayload),
+    RemoveLiquidity(LiquidityPayload),
+    Mint(SupplyChangePayload),
+    Burn(SupplyChangePayload),
+    Stake(StakePayload),
+    Unstake(StakePayload),
+    Other(Value),
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WalletTxData {
     #[serde(rename = "type")]
@@ -52,6 +147,81 @@ pub struct WalletTxData {
     pub to: Option<WalletTokenInfo>,
 }
 
+impl WalletTxData {
+    pub fn kind(&self) -> WalletTxKind {
+        WalletTxKind::parse(&self.tx_type)
+    }
+
+    /// Decode the kind-specific fields of this transaction
+    pub fn payload(&self) -> Result<WalletTxPayload, BirdeyeError> {
+        let raw = self.raw_payload()?;
+        let legs: Vec<WalletTokenInfo> = self.from.iter().chain(self.to.iter()).cloned().collect();
+
+        Ok(match self.kind() {
+            WalletTxKind::Swap => WalletTxPayload::Swap(serde_json::from_value(raw)?),
+            WalletTxKind::Transfer => {
+                let mut payload: TransferPayload = serde_json::from_value(raw)?;
+                if payload.token.is_none() {
+                    payload.token = legs.into_iter().next();
+                }
+                WalletTxPayload::Transfer(payload)
+            }
+            kind @ (WalletTxKind::AddLiquidity | WalletTxKind::RemoveLiquidity) => {
+                let mut payload: LiquidityPayload = serde_json::from_value(raw)?;
+                if payload.tokens.is_empty() {
+                    payload.tokens = legs;
+                }
+                if kind == WalletTxKind::AddLiquidity {
+                    WalletTxPayload::AddLiquidity(payload)
+                } else {
+                    WalletTxPayload::RemoveLiquidity(payload)
+                }
+            }
+            kind @ (WalletTxKind::Mint | WalletTxKind::Burn) => {
+                let mut payload: SupplyChangePayload = serde_json::from_value(raw)?;
+                if payload.token.is_none() {
+                    payload.token = legs.into_iter().next();
+                }
+                if kind == WalletTxKind::Mint {
+                    WalletTxPayload::Mint(payload)
+                } else {
+                    WalletTxPayload::Burn(payload)
+                }
+            }
+            kind @ (WalletTxKind::Stake | WalletTxKind::Unstake) => {
+                let mut payload: StakePayload = serde_json::from_value(raw)?;
+                if payload.token.is_none() {
+                    payload.token = legs.into_iter().next();
+                }
+                if kind == WalletTxKind::Stake {
+                    WalletTxPayload::Stake(payload)
+                } else {
+                    WalletTxPayload::Unstake(payload)
+                }
+            }
+            WalletTxKind::Other(_) => WalletTxPayload::Other(raw),
+        })
+    }
+
+    /// `extra_fields` merged with the `from`/`to` legs
+    fn raw_payload(&self) -> Result<Value, BirdeyeError> {
+        let mut raw = match &self.extra_fields {
+            Value::Object(map) => map.clone(),
+            _ => serde_json::Map::new(),
+        };
+        if let Some(from) = &self.from {
+            raw.insert("from".to_string(), serde_json::to_value(from)?);
+        }
+        if let Some(to) = &self.to {
+            raw.insert("to".to_string(), serde_json::to_value(to)?);
+        }
+        if let Some(pool) = &self.pool_address {
+            raw.entry("poolAddress").or_insert_with(|| Value::String(pool.clone()));
+        }
+        Ok(Value::Object(raw))
+    }
+}
+
 /// Create a subscription for wallet transactions
 /// 
 /// # Arguments
@@ -61,6 +231,8 @@ pub struct WalletTxData {
 /// 
 /// # Example
 /// ```rust
+/// use birdeye_api_rs::wallet_txs::create_wallet_txs_subscription;
+///
 /// // For Ethereum/BSC
 /// let sub = create_wallet_txs_subscription("0xae2Fc483527B8EF99EB5D9B44875F005ba1FaE13");
 /// 
@@ -86,4 +258,86 @@ pub fn create_wallet_txs_unsubscription() -> SubscriptionMessage {
 
 pub fn parse_wallet_txs_data(data: serde_json::Value) -> Result<WalletTxData, BirdeyeError> {
     Ok(serde_json::from_value(data)?)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn token(address: &str, change: f64) -> Value {
+        json!({
+            "symbol": "T", "decimals": 9, "address": address, "uiAmount": change.abs(),
+            "amount": "1000", "price": 1.5, "nearestPrice": null, "uiChangeAmount": change,
+        })
+    }
+
+    fn wallet_tx(tx_type: &str, extra: Value) -> WalletTxData {
+        let mut data = json!({
+            "type": tx_type, "blockUnixTime": 1_700_000_000, "blockHumanTime": "",
+            "owner": "wallet", "source": "raydium", "poolAddress": "pool", "txHash": "hash",
+            "volumeUSD": 10.0, "network": "solana",
+        });
+        data.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        parse_wallet_txs_data(data).unwrap()
+    }
+
+    #[test]
+    fn kinds_ignore_case_and_separators() {
+        assert_eq!(WalletTxKind::parse("SWAP"), WalletTxKind::Swap);
+        assert_eq!(WalletTxKind::parse("add_liquidity"), WalletTxKind::AddLiquidity);
+        assert_eq!(WalletTxKind::parse("Remove-Liquidity"), WalletTxKind::RemoveLiquidity);
+        assert_eq!(WalletTxKind::parse("send"), WalletTxKind::Transfer);
+    }
+
+    #[test]
+    fn deposits_and_withdrawals_are_not_liquidity() {
+        assert_eq!(WalletTxKind::parse("deposit"), WalletTxKind::Other("deposit".to_string()));
+        assert_eq!(WalletTxKind::parse("Withdraw"), WalletTxKind::Other("Withdraw".to_string()));
+    }
+
+    #[test]
+    fn swap_payload_uses_both_legs() {
+        let tx = wallet_tx("swap", json!({ "from": token("A", -1.0), "to": token("B", 2.0) }));
+        match tx.payload().unwrap() {
+            WalletTxPayload::Swap(swap) => {
+                assert_eq!(swap.from.address, "A");
+                assert_eq!(swap.to.address, "B");
+                assert_eq!(swap.to.amount(), "1000");
+            }
+            other => panic!("unexpected payload {other:?}"),
+        }
+    }
+
+    #[test]
+    fn transfer_falls_back_to_leg_token() {
+        let tx = wallet_tx("transfer", json!({ "from": token("A", -1.0), "toAddress": "friend" }));
+        match tx.payload().unwrap() {
+            WalletTxPayload::Transfer(transfer) => {
+                assert_eq!(transfer.receiver.as_deref(), Some("friend"));
+                assert_eq!(transfer.token.unwrap().address, "A");
+            }
+            other => panic!("unexpected payload {other:?}"),
+        }
+    }
+
+    #[test]
+    fn liquidity_payload_collects_legs_and_pool() {
+        let tx = wallet_tx("addLiquidity", json!({ "from": token("A", -1.0), "to": token("B", -2.0) }));
+        match tx.payload().unwrap() {
+            WalletTxPayload::AddLiquidity(liquidity) => {
+                assert_eq!(liquidity.tokens.len(), 2);
+                assert_eq!(liquidity.pool_address.as_deref(), Some("pool"));
+            }
+            other => panic!("unexpected payload {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_kinds_keep_the_raw_payload() {
+        let tx = wallet_tx("deposit", json!({ "exchange": "cex" }));
+        match tx.payload().unwrap() {
+            WalletTxPayload::Other(raw) => assert_eq!(raw["exchange"], "cex"),
+            other => panic!("unexpected payload {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::Instrument;
+
+use crate::connection::{BirdeyeStream, BirdeyeWebSocket};
+use crate::trade::{normalize_address, same_address};
+use crate::types::{BirdeyeError, ResponseType};
+use crate::wallet_txs::{
+    create_wallet_txs_subscription, create_wallet_txs_unsubscription, parse_wallet_txs_data, WalletTxData,
+    WalletTxPayload,
+};
+
+/// Default number of wallets multiplexed on one connection
+///
+/// Birdeye does not document a per-connection subscription limit; 100 is a
+/// conservative choice that keeps the re-subscription after each removal
+/// short. Set `wallets_per_connection` to what your plan allows.
+pub const DEFAULT_WALLETS_PER_CONNECTION: usize = 100;
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A wallet transaction tagged with the watched wallet it belongs to
+#[derive(Debug)]
+pub struct WatchedWalletTx {
+    pub wallet: String,
+    pub data: WalletTxData,
+}
+
+/// Options for `WalletWatcher`
+#[derive(Debug, Clone)]
+pub struct WalletWatcherOptions {
+    /// Maximum wallets subscribed on a single connection (must be >= 1)
+    pub wallets_per_connection: usize,
+    /// Capacity of the channel receiving tagged transactions
+    pub channel_capacity: usize,
+}
+
+impl Default for WalletWatcherOptions {
+    fn default() -> Self {
+        Self {
+            wallets_per_connection: DEFAULT_WALLETS_PER_CONNECTION,
+            channel_capacity: 1024,
+        }
+    }
+}
+
+impl WalletWatcherOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_wallets_per_connection(mut self, count: usize) -> Self {
+        assert!(count >= 1, "wallets_per_connection must be at least 1");
+        self.wallets_per_connection = count;
+        self
+    }
+
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+}
+
+enum ConnectionCommand {
+    Add(String),
+    Remove(String),
+}
+
+/// The task running one connection
+struct Connection {
+    commands: mpsc::UnboundedSender<ConnectionCommand>,
+    task: JoinHandle<()>,
+}
+
+/// What adding a wallet requires from the connections
+#[derive(Debug, PartialEq, Eq)]
+enum Added {
+    /// Open a new connection `slot` for `wallet`
+    Opened { slot: u64, wallet: String },
+    /// Subscribe `wallet` on the existing connection `slot`
+    Joined { slot: u64, wallet: String },
+}
+
+/// What removing a wallet requires from the connections
+#[derive(Debug, PartialEq, Eq)]
+enum Removed {
+    /// `wallet` was the last one of `slot`; close the connection
+    Closed { slot: u64 },
+    /// Unsubscribe `wallet` from the connection `slot`
+    Left { slot: u64, wallet: String },
+}
+
+/// Assignment of wallets to connection slots
+#[derive(Default)]
+struct WatcherState {
+    slots: HashMap<u64, HashSet<String>>,
+    wallet_slots: HashMap<String, u64>,
+    connections: HashMap<u64, Connection>,
+    next_slot_id: u64,
+}
+
+impl WatcherState {
+    /// Put `wallet` on the fullest slot with room, or a new one; `None` if
+    /// it is already watched
+    fn add(&mut self, wallet: &str, per_connection: usize) -> Option<Added> {
+        let wallet = normalize_address(wallet);
+        if self.wallet_slots.contains_key(&wallet) {
+            return None;
+        }
+        let free_slot = self
+            .slots
+            .iter()
+            .filter(|(_, wallets)| wallets.len() < per_connection)
+            .max_by_key(|(_, wallets)| wallets.len())
+            .map(|(id, _)| *id);
+        let (slot, opened) = match free_slot {
+            Some(id) => (id, false),
+            None => {
+                self.next_slot_id += 1;
+                (self.next_slot_id - 1, true)
+            }
+        };
+        self.slots.entry(slot).or_default().insert(wallet.clone());
+        self.wallet_slots.insert(wallet.clone(), slot);
+        Some(if opened {
+            Added::Opened { slot, wallet }
+        } else {
+            Added::Joined { slot, wallet }
+        })
+    }
+
+    /// Take `wallet` off its slot; `None` if it is not watched
+    fn remove(&mut self, wallet: &str) -> Option<Removed> {
+        let wallet = normalize_address(wallet);
+        let slot = self.wallet_slots.remove(&wallet)?;
+        let wallets = self.slots.get_mut(&slot)?;
+        wallets.remove(&wallet);
+        if wallets.is_empty() {
+            self.slots.remove(&slot);
+            Some(Removed::Closed { slot })
+        } else {
+            Some(Removed::Left { slot, wallet })
+        }
+    }
+
+    /// Forget connections whose task has exited, e.g. after the event
+    /// receiver was dropped, along with their wallets
+    fn prune_finished(&mut self) {
+        let finished: Vec<u64> = self
+            .connections
+            .iter()
+            .filter(|(_, connection)| connection.task.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished {
+            self.connections.remove(&id);
+            for wallet in self.slots.remove(&id).unwrap_or_default() {
+                self.wallet_slots.remove(&wallet);
+            }
+        }
+    }
+}
+
+/// Watches a dynamic set of wallets over as few connections as possible
+///
+/// Must be used from within a Tokio runtime. Connections are opened lazily
+/// as wallets are added and closed once their last wallet is removed.
+pub struct WalletWatcher {
+    ws: BirdeyeWebSocket,
+    options: WalletWatcherOptions,
+    events: mpsc::Sender<WatchedWalletTx>,
+    state: Mutex<WatcherState>,
+    decode_failures: Arc<AtomicU64>,
+}
+
+impl WalletWatcher {
+    pub fn new(api_key: impl Into<String>, options: WalletWatcherOptions) -> (Self, mpsc::Receiver<WatchedWalletTx>) {
+        Self::with_client(BirdeyeWebSocket::new(api_key), options)
+    }
+
+    /// Open connections through `ws`, e.g. one with a usage tracker
+    pub fn with_client(ws: BirdeyeWebSocket, options: WalletWatcherOptions) -> (Self, mpsc::Receiver<WatchedWalletTx>) {
+        let (events, receiver) = mpsc::channel(options.channel_capacity);
+        let watcher = Self {
+            ws,
+            options,
+            events,
+            state: Mutex::new(WatcherState::default()),
+            decode_failures: Arc::new(AtomicU64::new(0)),
+        };
+        (watcher, receiver)
+    }
+
+    /// Start watching `wallet`; returns false if it was already watched
+    ///
+    /// EVM addresses are compared and reported in lowercase.
+    pub fn add_wallet(&self, wallet: impl Into<String>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.prune_finished();
+        match state.add(&wallet.into(), self.options.wallets_per_connection) {
+            Some(Added::Opened { slot, wallet }) => {
+                let (commands, command_rx) = mpsc::unbounded_channel();
+                let task = tokio::spawn(
+                    run_connection(
+                        self.ws.clone(),
+                        std::iter::once(wallet).collect(),
+                        command_rx,
+                        self.events.clone(),
+                        self.decode_failures.clone(),
+                    )
+                    .instrument(tracing::info_span!("wallet_connection", slot)),
+                );
+                state.connections.insert(slot, Connection { commands, task });
+                true
+            }
+            Some(Added::Joined { slot, wallet }) => {
+                if let Some(connection) = state.connections.get(&slot) {
+                    let _ = connection.commands.send(ConnectionCommand::Add(wallet));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop watching `wallet`; returns false if it was not watched
+    pub fn remove_wallet(&self, wallet: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.remove(wallet) {
+            Some(Removed::Closed { slot }) => {
+                if let Some(connection) = state.connections.remove(&slot) {
+                    connection.task.abort();
+                }
+                true
+            }
+            Some(Removed::Left { slot, wallet }) => {
+                if let Some(connection) = state.connections.get(&slot) {
+                    let _ = connection.commands.send(ConnectionCommand::Remove(wallet));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn wallets(&self) -> Vec<String> {
+        let mut state = self.state.lock().unwrap();
+        state.prune_finished();
+        state.wallet_slots.keys().cloned().collect()
+    }
+
+    pub fn connection_count(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.prune_finished();
+        state.connections.len()
+    }
+
+    /// Wallet transactions that could not be parsed and were dropped
+    pub fn decode_failures(&self) -> u64 {
+        self.decode_failures.load(Ordering::Relaxed)
+    }
+
+    /// Close every connection and forget all wallets
+    pub fn shutdown(&self) {
+        let mut state = self.state.lock().unwrap();
+        for (_, connection) in state.connections.drain() {
+            connection.task.abort();
+        }
+        state.slots.clear();
+        state.wallet_slots.clear();
+    }
+}
+
+impl Drop for WalletWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+async fn run_connection(
+    ws: BirdeyeWebSocket,
+    mut wallets: HashSet<String>,
+    mut commands: mpsc::UnboundedReceiver<ConnectionCommand>,
+    events: mpsc::Sender<WatchedWalletTx>,
+    decode_failures: Arc<AtomicU64>,
+) {
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    let mut reconnecting = false;
+    loop {
+        if reconnecting {
+            ws.record_reconnect();
+        }
+        reconnecting = true;
+        let mut stream = match ws.connect().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "reconnecting after failed connect");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                continue;
+            }
+        };
+
+        if let Err(e) = subscribe_all(&mut stream, &wallets).await {
+            tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "reconnecting after failed subscribe");
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            continue;
+        }
+        tracing::debug!(wallets = wallets.len(), "subscribed");
+        delay = INITIAL_RECONNECT_DELAY;
+
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(ConnectionCommand::Add(wallet)) => {
+                        let message = create_wallet_txs_subscription(wallet.clone());
+                        wallets.insert(wallet);
+                        if BirdeyeWebSocket::send_message(&mut stream, &message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(ConnectionCommand::Remove(wallet)) => {
+                        // Unsubscribing drops every wallet, so re-subscribe the rest
+                        wallets.remove(&wallet);
+                        let unsubscribe = create_wallet_txs_unsubscription();
+                        if BirdeyeWebSocket::send_message(&mut stream, &unsubscribe).await.is_err()
+                            || subscribe_all(&mut stream, &wallets).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                    None => {
+                        let _ = stream.close(None).await;
+                        return;
+                    }
+                },
+                message = stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some(event) = decode_wallet_tx(&ws, &text, &wallets, &decode_failures) {
+                            if events.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => {
+                        tracing::info!("server closed the connection, reconnecting");
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!(error = %e, "connection failed, reconnecting");
+                        break;
+                    }
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
+
+async fn subscribe_all(stream: &mut BirdeyeStream, wallets: &HashSet<String>) -> Result<(), BirdeyeError> {
+    for wallet in wallets {
+        let message = create_wallet_txs_subscription(wallet.clone());
+        BirdeyeWebSocket::send_message(stream, &message).await?;
+    }
+    Ok(())
+}
+
+fn decode_wallet_tx(
+    ws: &BirdeyeWebSocket,
+    text: &str,
+    wallets: &HashSet<String>,
+    decode_failures: &AtomicU64,
+) -> Option<WatchedWalletTx> {
+    // Frames that are not protocol messages are logged by `decode`
+    let response = ws.decode(text).ok()?;
+    if !matches!(response.response_type, ResponseType::WalletTxsData) {
+        return None;
+    }
+    let data = match parse_wallet_txs_data(response.data) {
+        Ok(data) => data,
+        Err(e) => {
+            decode_failures.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(error = %e, "dropping undecodable wallet transaction");
+            return None;
+        }
+    };
+    let wallet = match_wallet(&data, wallets)?;
+    Some(WatchedWalletTx { wallet, data })
+}
+
+/// Find the watched wallet a transaction belongs to
+///
+/// Checks `owner` first, then the sender and receiver of a transfer.
+/// Mints and pools are never matched. A connection watching a single
+/// wallet attributes everything to it.
+fn match_wallet(data: &WalletTxData, wallets: &HashSet<String>) -> Option<String> {
+    let find = |candidate: &str| wallets.iter().find(|w| same_address(w, candidate)).cloned();
+
+    if let Some(wallet) = find(&data.owner) {
+        return Some(wallet);
+    }
+    if let Ok(WalletTxPayload::Transfer(transfer)) = data.payload() {
+        if let Some(wallet) = [transfer.sender, transfer.receiver].iter().flatten().find_map(|c| find(c)) {
+            return Some(wallet);
+        }
+    }
+    if wallets.len() == 1 {
+        return wallets.iter().next().cloned();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn wallet_tx(owner: &str, extra: serde_json::Value) -> WalletTxData {
+        let mut data = json!({
+            "type": "transfer", "blockUnixTime": 1_700_000_000, "blockHumanTime": "",
+            "owner": owner, "source": "", "txHash": "hash", "volumeUSD": 0.0, "network": "ethereum",
+        });
+        data.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        parse_wallet_txs_data(data).unwrap()
+    }
+
+    fn wallets(list: &[&str]) -> HashSet<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_owner_ignoring_evm_case() {
+        let watched = wallets(&["0xabc", "0xdef"]);
+        let tx = wallet_tx("0xABC", json!({}));
+        assert_eq!(match_wallet(&tx, &watched).as_deref(), Some("0xabc"));
+    }
+
+    #[test]
+    fn matches_counterparty_fields() {
+        let watched = wallets(&["0xabc", "0xdef"]);
+        let tx = wallet_tx("0x999", json!({ "toAddress": "0xDEF" }));
+        assert_eq!(match_wallet(&tx, &watched).as_deref(), Some("0xdef"));
+    }
+
+    #[test]
+    fn single_wallet_connection_takes_everything() {
+        let tx = wallet_tx("0x999", json!({}));
+        assert_eq!(match_wallet(&tx, &wallets(&["0xabc"])).as_deref(), Some("0xabc"));
+        assert_eq!(match_wallet(&tx, &wallets(&["0xabc", "0xdef"])), None);
+    }
+
+    #[test]
+    fn ignores_watched_addresses_outside_owner_and_counterparties() {
+        let watched = wallets(&["0xabc", "0xdef"]);
+        let token = |address: &str| {
+            json!({
+                "symbol": "T", "decimals": 18, "address": address, "uiAmount": 1.0, "amount": "1",
+                "price": null, "nearestPrice": null, "uiChangeAmount": 1.0,
+            })
+        };
+        let swap = wallet_tx("0x999", json!({ "type": "swap", "poolAddress": "0xabc", "from": token("0xdef"), "to": token("0x1") }));
+        assert_eq!(match_wallet(&swap, &watched), None);
+        let mint = wallet_tx("0x999", json!({ "type": "mint", "authority": "0xdef" }));
+        assert_eq!(match_wallet(&mint, &watched), None);
+    }
+
+    #[test]
+    fn wallet_keys_are_normalised() {
+        let mut state = WatcherState::default();
+        assert_eq!(state.add("0xAbC", 10), Some(Added::Opened { slot: 0, wallet: "0xabc".to_string() }));
+        assert_eq!(state.add("0xabc", 10), None);
+        assert_eq!(state.add("So1ana", 10), Some(Added::Joined { slot: 0, wallet: "So1ana".to_string() }));
+        assert!(state.add("so1ana", 10).is_some());
+        assert_eq!(state.remove("0XABC"), Some(Removed::Left { slot: 0, wallet: "0xabc".to_string() }));
+        assert_eq!(state.remove("0xabc"), None);
+        assert_eq!(state.wallet_slots.len(), 2);
+    }
+
+    #[test]
+    fn wallets_are_packed_per_connection() {
+        let mut state = WatcherState::default();
+        for wallet in ["a", "b", "c"] {
+            state.add(wallet, 2);
+        }
+        assert_eq!(state.slots.len(), 2);
+        assert_eq!(state.remove("c"), Some(Removed::Closed { slot: 1 }));
+        // The fuller connection with room is filled first
+        assert_eq!(state.add("d", 3), Some(Added::Joined { slot: 0, wallet: "d".to_string() }));
+        assert_eq!(state.add("e", 3), Some(Added::Opened { slot: 2, wallet: "e".to_string() }));
+    }
+
+    #[tokio::test]
+    async fn finished_connections_are_dropped() {
+        let mut state = WatcherState::default();
+        assert!(matches!(state.add("gone", 10), Some(Added::Opened { slot: 0, .. })));
+        assert!(matches!(state.add("kept", 1), Some(Added::Opened { slot: 1, .. })));
+        let task = tokio::spawn(async {});
+        while !task.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        let (commands, _) = mpsc::unbounded_channel();
+        state.connections.insert(0, Connection { commands, task });
+
+        state.prune_finished();
+        assert!(state.connections.is_empty());
+        assert_eq!(state.wallet_slots.keys().collect::<Vec<_>>(), ["kept"]);
+        assert!(state.add("gone", 10).is_some());
+    }
+}
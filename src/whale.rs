@@ -0,0 +1,328 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::dex::DexSource;
+use crate::large_trades::LargeTradeData;
+use crate::rest::TokenOverviewData;
+use crate::trade::{normalize_address, SeenTxs, ToTrade, TradeSide};
+
+/// Default USD volume below which no rule alerts
+pub const DEFAULT_MIN_ALERT_VOLUME_USD: f64 = 1_000.0;
+
+/// Liquidity and volume context used by relative thresholds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenStats {
+    pub liquidity_usd: Option<f64>,
+    pub volume_24h_usd: Option<f64>,
+}
+
+impl From<&TokenOverviewData> for TokenStats {
+    fn from(overview: &TokenOverviewData) -> Self {
+        Self {
+            liquidity_usd: Some(overview.liquidity),
+            volume_24h_usd: overview.v24h_usd,
+        }
+    }
+}
+
+/// Rules deciding which large trades become alerts
+///
+/// Token and wallet keys are compared with EVM addresses lowercased; the
+/// `with_*` builders normalise them, so prefer those over filling the
+/// fields directly.
+#[derive(Debug, Clone)]
+pub struct WhaleRules {
+    /// USD volume alerting for tokens without their own threshold
+    pub min_volume_usd: f64,
+    /// Floor below which no rule alerts, so ratio rules do not fire on
+    /// dust trades in illiquid tokens
+    pub min_alert_volume_usd: f64,
+    /// Per-token USD thresholds overriding `min_volume_usd`
+    pub token_thresholds: HashMap<String, f64>,
+    /// Alert when volume / liquidity is at least this ratio
+    pub min_liquidity_ratio: Option<f64>,
+    /// Alert when volume / 24h volume is at least this ratio
+    pub min_volume_24h_ratio: Option<f64>,
+    /// If non-empty, only trades by these wallets alert
+    pub allowed_wallets: HashSet<String>,
+    pub denied_wallets: HashSet<String>,
+    /// Minimum time between two alerts for the same token
+    pub cooldown: Duration,
+}
+
+impl WhaleRules {
+    pub fn new(min_volume_usd: f64) -> Self {
+        Self {
+            min_volume_usd,
+            min_alert_volume_usd: DEFAULT_MIN_ALERT_VOLUME_USD.min(min_volume_usd),
+            token_thresholds: HashMap::new(),
+            min_liquidity_ratio: None,
+            min_volume_24h_ratio: None,
+            allowed_wallets: HashSet::new(),
+            denied_wallets: HashSet::new(),
+            cooldown: Duration::ZERO,
+        }
+    }
+
+    /// Alert on no trade below `min_volume_usd`, whatever the rule
+    pub fn with_min_alert_volume(mut self, min_volume_usd: f64) -> Self {
+        assert!(min_volume_usd >= 0.0, "min_alert_volume_usd must not be negative");
+        self.min_alert_volume_usd = min_volume_usd;
+        self
+    }
+
+    pub fn with_token_threshold(mut self, token: impl Into<String>, min_volume_usd: f64) -> Self {
+        self.token_thresholds.insert(normalize_address(&token.into()), min_volume_usd);
+        self
+    }
+
+    pub fn with_liquidity_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "liquidity ratio must be positive");
+        self.min_liquidity_ratio = Some(ratio);
+        self
+    }
+
+    pub fn with_volume_24h_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "24h volume ratio must be positive");
+        self.min_volume_24h_ratio = Some(ratio);
+        self
+    }
+
+    pub fn with_allowed_wallets(mut self, wallets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_wallets
+            .extend(wallets.into_iter().map(|wallet| normalize_address(&wallet.into())));
+        self
+    }
+
+    pub fn with_denied_wallets(mut self, wallets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.denied_wallets
+            .extend(wallets.into_iter().map(|wallet| normalize_address(&wallet.into())));
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// Why a trade triggered an alert
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum WhaleAlertReason {
+    Volume { threshold_usd: f64 },
+    TokenVolume { threshold_usd: f64 },
+    LiquidityRatio { ratio: f64 },
+    Volume24hRatio { ratio: f64 },
+}
+
+/// A de-duplicated large-trade alert
+#[derive(Debug, Clone, Serialize)]
+pub struct WhaleAlert {
+    pub tx_hash: String,
+    pub token_address: String,
+    pub token_symbol: String,
+    pub side: TradeSide,
+    pub owner: String,
+    pub volume_usd: f64,
+    pub token_amount: f64,
+    pub price_usd: Option<f64>,
+    pub pool_address: String,
+    pub source: DexSource,
+    pub network: String,
+    pub block_unix_time: i64,
+    pub reasons: Vec<WhaleAlertReason>,
+}
+
+/// Rule engine turning `LargeTradeData` into `WhaleAlert`s
+///
+/// The traded token is the leg that is not a known quote token. Cooldowns
+/// use the block time of the trades, not the local clock.
+#[derive(Debug)]
+pub struct WhaleAlertEngine {
+    rules: WhaleRules,
+    stats: HashMap<String, TokenStats>,
+    last_alert: HashMap<String, i64>,
+    seen: SeenTxs,
+}
+
+impl WhaleAlertEngine {
+    pub fn new(rules: WhaleRules) -> Self {
+        Self {
+            rules,
+            stats: HashMap::new(),
+            last_alert: HashMap::new(),
+            seen: SeenTxs::default(),
+        }
+    }
+
+    pub fn rules(&self) -> &WhaleRules {
+        &self.rules
+    }
+
+    /// Provide liquidity / 24h volume for relative thresholds
+    pub fn update_token_stats(&mut self, token: impl Into<String>, stats: TokenStats) {
+        self.stats.insert(normalize_address(&token.into()), stats);
+    }
+
+    /// Evaluate one trade; `None` if it is filtered, a duplicate or cooling down
+    pub fn process(&mut self, data: &LargeTradeData) -> Option<WhaleAlert> {
+        if self.seen.contains(&data.tx_hash) {
+            return None;
+        }
+        if data.volume_usd < self.rules.min_alert_volume_usd {
+            return None;
+        }
+        let owner = normalize_address(&data.owner);
+        if self.rules.denied_wallets.contains(&owner) {
+            return None;
+        }
+        if !self.rules.allowed_wallets.is_empty() && !self.rules.allowed_wallets.contains(&owner) {
+            return None;
+        }
+
+        let trade = data.to_trade_inferred()?;
+        let token = normalize_address(&trade.base_address);
+        let reasons = self.reasons(&token, data.volume_usd);
+        if reasons.is_empty() {
+            return None;
+        }
+
+        let cooldown = self.rules.cooldown.as_secs() as i64;
+        if let Some(last) = self.last_alert.get(&token) {
+            if data.block_unix_time - last < cooldown {
+                return None;
+            }
+        }
+        self.last_alert.insert(token, data.block_unix_time);
+        self.seen.insert(&data.tx_hash);
+
+        Some(WhaleAlert {
+            tx_hash: trade.tx_hash,
+            token_address: trade.base_address,
+            token_symbol: trade.base_symbol,
+            side: trade.side,
+            owner: trade.owner,
+            volume_usd: data.volume_usd,
+            token_amount: trade.base_amount,
+            price_usd: trade.price_usd,
+            pool_address: data.pool_address.clone(),
+            source: trade.venue,
+            network: data.network.clone(),
+            block_unix_time: data.block_unix_time,
+            reasons,
+        })
+    }
+
+    fn reasons(&self, token: &str, volume_usd: f64) -> Vec<WhaleAlertReason> {
+        let mut reasons = Vec::new();
+        match self.rules.token_thresholds.get(token) {
+            Some(&threshold_usd) if volume_usd >= threshold_usd => {
+                reasons.push(WhaleAlertReason::TokenVolume { threshold_usd })
+            }
+            Some(_) => {}
+            None if volume_usd >= self.rules.min_volume_usd => reasons.push(WhaleAlertReason::Volume {
+                threshold_usd: self.rules.min_volume_usd,
+            }),
+            None => {}
+        }
+
+        let stats = self.stats.get(token).copied().unwrap_or_default();
+        if let (Some(min), Some(liquidity)) = (self.rules.min_liquidity_ratio, stats.liquidity_usd) {
+            let ratio = volume_usd / liquidity;
+            if liquidity > 0.0 && ratio >= min {
+                reasons.push(WhaleAlertReason::LiquidityRatio { ratio });
+            }
+        }
+        if let (Some(min), Some(volume_24h)) = (self.rules.min_volume_24h_ratio, stats.volume_24h_usd) {
+            let ratio = volume_usd / volume_24h;
+            if volume_24h > 0.0 && ratio >= min {
+                reasons.push(WhaleAlertReason::Volume24hRatio { ratio });
+            }
+        }
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::large_trades::parse_large_trade_data;
+    use serde_json::json;
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    const TOKEN: &str = "0xAbCdEf0000000000000000000000000000000001";
+
+    fn trade(hash: &str, owner: &str, volume_usd: f64, block_unix_time: i64) -> LargeTradeData {
+        let leg = |address: &str, change: f64| {
+            json!({
+                "symbol": "T", "decimals": 18, "address": address, "uiAmount": change.abs(),
+                "price": null, "nearestPrice": null, "uiChangeAmount": change,
+            })
+        };
+        parse_large_trade_data(json!({
+            "blockUnixTime": block_unix_time, "blockHumanTime": "", "owner": owner,
+            "source": "uniswap_v2", "poolAddress": "pool", "txHash": hash,
+            "volumeUSD": volume_usd, "network": "ethereum",
+            "from": leg(WETH, -1.0), "to": leg(TOKEN, 100.0),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn volume_alerts_are_deduplicated() {
+        let mut engine = WhaleAlertEngine::new(WhaleRules::new(10_000.0));
+        let alert = engine.process(&trade("a", "w", 20_000.0, 0)).unwrap();
+        assert_eq!(alert.side, TradeSide::Buy);
+        assert_eq!(alert.reasons, [WhaleAlertReason::Volume { threshold_usd: 10_000.0 }]);
+        assert!(engine.process(&trade("a", "w", 20_000.0, 0)).is_none());
+        assert!(engine.process(&trade("b", "w", 5_000.0, 0)).is_none());
+    }
+
+    #[test]
+    fn token_thresholds_ignore_evm_case() {
+        let rules = WhaleRules::new(100_000.0).with_token_threshold(TOKEN.to_lowercase(), 5_000.0);
+        let mut engine = WhaleAlertEngine::new(rules);
+        let alert = engine.process(&trade("a", "w", 6_000.0, 0)).unwrap();
+        assert_eq!(alert.reasons, [WhaleAlertReason::TokenVolume { threshold_usd: 5_000.0 }]);
+    }
+
+    #[test]
+    fn wallet_lists_ignore_evm_case() {
+        let rules = WhaleRules::new(1_000.0).with_denied_wallets(["0xBAD"]);
+        let mut engine = WhaleAlertEngine::new(rules);
+        assert!(engine.process(&trade("a", "0xbad", 5_000.0, 0)).is_none());
+
+        let rules = WhaleRules::new(1_000.0).with_allowed_wallets(["0xGood"]);
+        let mut engine = WhaleAlertEngine::new(rules);
+        assert!(engine.process(&trade("a", "0xother", 5_000.0, 0)).is_none());
+        assert!(engine.process(&trade("b", "0xGOOD", 5_000.0, 0)).is_some());
+    }
+
+    #[test]
+    fn ratio_rules_respect_the_floor() {
+        let rules = WhaleRules::new(f64::INFINITY)
+            .with_liquidity_ratio(0.1)
+            .with_min_alert_volume(1_000.0);
+        let mut engine = WhaleAlertEngine::new(rules);
+        let stats = TokenStats {
+            liquidity_usd: Some(2_000.0),
+            volume_24h_usd: None,
+        };
+        engine.update_token_stats(TOKEN.to_lowercase(), stats);
+        assert!(engine.process(&trade("a", "w", 500.0, 0)).is_none());
+        let alert = engine.process(&trade("b", "w", 1_000.0, 0)).unwrap();
+        assert_eq!(alert.reasons, [WhaleAlertReason::LiquidityRatio { ratio: 0.5 }]);
+    }
+
+    #[test]
+    fn cooldown_uses_block_time() {
+        let rules = WhaleRules::new(1_000.0).with_cooldown(Duration::from_secs(60));
+        let mut engine = WhaleAlertEngine::new(rules);
+        assert!(engine.process(&trade("a", "w", 5_000.0, 100)).is_some());
+        assert!(engine.process(&trade("b", "w", 5_000.0, 130)).is_none());
+        assert!(engine.process(&trade("c", "w", 5_000.0, 160)).is_some());
+    }
+}